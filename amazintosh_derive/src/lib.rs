@@ -0,0 +1,347 @@
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote, quote_spanned};
+use syn::spanned::Spanned;
+use syn::{
+    parse_macro_input, Attribute, Data, DeriveInput, Error, Field, Fields, Lit, Meta, NestedMeta,
+};
+
+/// Implements `amazintosh_rs::render::vertex::Vertex` for a `#[repr(C)]`
+/// struct.
+///
+/// Every field is laid out as one attribute (or more, for types such as
+/// matrices that span several locations) using the field's offset within the
+/// struct. Locations are assigned in declaration order starting at 0, and a
+/// field can be moved to a specific location with `#[location = N]`, after
+/// which numbering continues from that location. Fields whose locations
+/// overlap fail to compile, as do overrides on generic structs, since their
+/// field types aren't known until they are used.
+#[proc_macro_derive(Vertex, attributes(location))]
+pub fn derive_vertex(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    derive_vertex_impl(&input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+fn derive_vertex_impl(input: &DeriveInput) -> Result<TokenStream2, Error> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let pointers = attrib_pointers(input, "Vertex", quote!(0))?;
+
+    Ok(quote! {
+        impl #impl_generics ::amazintosh_rs::render::vertex::Vertex
+            for #name #ty_generics #where_clause
+        {
+            #[allow(unused_mut, unused_assignments)]
+            fn attrib_pointers() -> ::std::vec::Vec<::amazintosh_rs::render::vertex::VertexAttribPointer> {
+                #pointers
+            }
+        }
+    })
+}
+
+/// Implements `amazintosh_rs::render::vertex::InstanceData` for a
+/// `#[repr(C)]` struct.
+///
+/// Fields are laid out the same way as with `#[derive(Vertex)]`, except that
+/// locations start after the last location used by the mesh's vertices.
+/// Since that depends on the mesh, fields can't be moved with
+/// `#[location = N]`.
+#[proc_macro_derive(InstanceData, attributes(location))]
+pub fn derive_instance_data(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    derive_instance_data_impl(&input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+fn derive_instance_data_impl(input: &DeriveInput) -> Result<TokenStream2, Error> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let pointers = attrib_pointers(input, "InstanceData", quote!(first_location))?;
+
+    if let Data::Struct(data) = &input.data {
+        for field in &data.fields {
+            if let Some(attr) = field
+                .attrs
+                .iter()
+                .find(|attr| attr.path.is_ident("location"))
+            {
+                return Err(Error::new(
+                    attr.span(),
+                    "#[derive(InstanceData)] fields can't be moved with #[location], since their locations follow the mesh's vertices",
+                ));
+            }
+        }
+    }
+
+    Ok(quote! {
+        impl #impl_generics ::amazintosh_rs::render::vertex::InstanceData
+            for #name #ty_generics #where_clause
+        {
+            #[allow(unused_mut, unused_assignments)]
+            fn attrib_pointers(
+                first_location: usize,
+            ) -> ::std::vec::Vec<::amazintosh_rs::render::vertex::VertexAttribPointer> {
+                #pointers
+            }
+        }
+    })
+}
+
+/// Implements `amazintosh_rs::render::uniform_block::Std140` for a
+/// `#[repr(C)]` struct.
+///
+/// The offset of every field is checked at compile time against where a
+/// `layout(std140)` uniform block would put it, so a struct that needs
+/// padding fails to compile with the name of the misplaced field. The size of
+/// the struct is checked too, since std140 rounds blocks up to a multiple of
+/// 16 bytes.
+#[proc_macro_derive(Std140)]
+pub fn derive_std140(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    derive_std140_impl(&input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+fn derive_std140_impl(input: &DeriveInput) -> Result<TokenStream2, Error> {
+    let name = &input.ident;
+    let members = struct_members(input, "Std140")?;
+
+    // The layout is checked in a constant, which can't refer to generics
+    if !input.generics.params.is_empty() {
+        return Err(Error::new(
+            input.generics.span(),
+            "#[derive(Std140)] is not supported on generic structs",
+        ));
+    }
+
+    let mut statements = Vec::new();
+    for (field, member) in members {
+        let ty = &field.ty;
+        let offset_message = format!(
+            "field `{}` of `{}` is not at its std140 offset, try adding padding before it",
+            member, name
+        );
+        let size_message = format!(
+            "field `{}` of `{}` is stored differently by std140",
+            member, name
+        );
+
+        statements.push(quote_spanned! {ty.span()=>
+            let alignment = <#ty as ::amazintosh_rs::render::uniform_block::Std140Field>::ALIGNMENT;
+            offset = offset.div_ceil(alignment) * alignment;
+            assert!(::std::mem::offset_of!(#name, #member) == offset, #offset_message);
+            assert!(
+                ::std::mem::size_of::<#ty>()
+                    == <#ty as ::amazintosh_rs::render::uniform_block::Std140Field>::SIZE,
+                #size_message
+            );
+            offset += <#ty as ::amazintosh_rs::render::uniform_block::Std140Field>::SIZE;
+        });
+    }
+
+    let struct_size_message = format!(
+        "`{}` is not the size of its std140 block, try adding trailing padding to round it up to 16 bytes",
+        name
+    );
+
+    Ok(quote! {
+        #[allow(unused_assignments, unused_mut, unused_variables)]
+        const _: () = {
+            let mut offset: usize = 0;
+            #(#statements)*
+            assert!(
+                ::std::mem::size_of::<#name>() == offset.div_ceil(16) * 16,
+                #struct_size_message
+            );
+        };
+
+        impl ::amazintosh_rs::render::uniform_block::Std140 for #name {}
+    })
+}
+
+/// Generates the body of an `attrib_pointers` function for the provided
+/// struct, checking that it has a layout that can be described to OpenGL.
+/// Locations are numbered from the `first_location` expression.
+fn attrib_pointers(
+    input: &DeriveInput,
+    derive_name: &str,
+    first_location: TokenStream2,
+) -> Result<TokenStream2, Error> {
+    let name = &input.ident;
+    let members = struct_members(input, derive_name)?;
+
+    let mut statements = Vec::new();
+    // Fields can only overlap once they are moved, so the locations of every
+    // field are only checked then
+    let mut overridden = None;
+    let mut ranges = Vec::new();
+    let mut range_checks = Vec::new();
+    for (index, (field, member)) in members.iter().enumerate() {
+        let ty = &field.ty;
+        let start = format_ident!("start_{}", index);
+        let end = format_ident!("end_{}", index);
+
+        let start_location = match location_override(&field.attrs)? {
+            Some(location) => {
+                overridden.get_or_insert(field.span());
+                statements.push(quote! { location = #location; });
+                quote!(#location)
+            }
+            None if index == 0 => quote!(0),
+            None => {
+                let previous = format_ident!("end_{}", index - 1);
+                quote!(#previous)
+            }
+        };
+        range_checks.push(quote_spanned! {ty.span()=>
+            let #start: usize = #start_location;
+            let #end: usize =
+                #start + <#ty as ::amazintosh_rs::render::vertex::VertexAttribute>::LOCATIONS;
+        });
+
+        for (other_member, other_start, other_end) in &ranges {
+            let message = format!(
+                "fields `{}` and `{}` of `{}` share attribute locations",
+                other_member, member, name
+            );
+            range_checks.push(quote_spanned! {ty.span()=>
+                assert!(#end <= #other_start || #other_end <= #start, #message);
+            });
+        }
+        ranges.push((member.to_string(), start, end));
+
+        statements.push(quote_spanned! {ty.span()=>
+            pointers.extend(
+                <#ty as ::amazintosh_rs::render::vertex::VertexAttribute>::attrib_pointers(
+                    location,
+                    ::std::mem::offset_of!(Self, #member),
+                ),
+            );
+            location += <#ty as ::amazintosh_rs::render::vertex::VertexAttribute>::LOCATIONS;
+        });
+    }
+
+    // The ranges are checked in a constant, which can't refer to generics
+    let range_check = match overridden {
+        Some(span) if !input.generics.params.is_empty() => {
+            return Err(Error::new(
+                span,
+                format!(
+                    "#[location] is not supported on generic #[derive({})] structs",
+                    derive_name
+                ),
+            ))
+        }
+        Some(_) => quote! {
+            #[allow(unused_variables)]
+            const _: () = {
+                #(#range_checks)*
+            };
+        },
+        None => quote!(),
+    };
+
+    Ok(quote! {
+        #range_check
+        let mut location: usize = #first_location;
+        let mut pointers = ::std::vec::Vec::new();
+        #(#statements)*
+        pointers
+    })
+}
+
+/// Gets every field of a `#[repr(C)]` struct along with how to refer to it
+/// in `offset_of!`, or an error naming the derive if the input isn't one.
+fn struct_members<'a>(
+    input: &'a DeriveInput,
+    derive_name: &str,
+) -> Result<Vec<(&'a Field, TokenStream2)>, Error> {
+    let name = &input.ident;
+
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => {
+            return Err(Error::new(
+                Span::call_site(),
+                format!("#[derive({})] is only supported on structs", derive_name),
+            ))
+        }
+    };
+
+    // Without a defined layout the field offsets could be anything
+    if !is_repr_c(&input.attrs)? {
+        return Err(Error::new(
+            name.span(),
+            format!(
+                "#[derive({})] requires the struct to be #[repr(C)]",
+                derive_name
+            ),
+        ));
+    }
+
+    Ok(match fields {
+        Fields::Named(fields) => fields
+            .named
+            .iter()
+            .map(|field| {
+                let ident = field.ident.as_ref().unwrap();
+                (field, quote!(#ident))
+            })
+            .collect(),
+        Fields::Unnamed(fields) => fields
+            .unnamed
+            .iter()
+            .enumerate()
+            .map(|(i, field)| {
+                let index = syn::Index::from(i);
+                (field, quote!(#index))
+            })
+            .collect(),
+        Fields::Unit => Vec::new(),
+    })
+}
+
+/// Checks whether the attributes contain `#[repr(C)]`.
+fn is_repr_c(attrs: &[Attribute]) -> Result<bool, Error> {
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("repr")) {
+        if let Meta::List(list) = attr.parse_meta()? {
+            let has_c = list.nested.iter().any(|nested| match nested {
+                NestedMeta::Meta(Meta::Path(path)) => path.is_ident("C"),
+                _ => false,
+            });
+            if has_c {
+                return Ok(true);
+            }
+        }
+    }
+
+    Ok(false)
+}
+
+/// Reads the location from a `#[location = N]` attribute, if one is present.
+fn location_override(attrs: &[Attribute]) -> Result<Option<usize>, Error> {
+    let attr = match attrs.iter().find(|attr| attr.path.is_ident("location")) {
+        Some(attr) => attr,
+        None => return Ok(None),
+    };
+
+    match attr.parse_meta()? {
+        Meta::NameValue(meta) => match &meta.lit {
+            Lit::Int(int) => int.base10_parse().map(Some),
+            lit => Err(Error::new(lit.span(), "expected an integer location")),
+        },
+        meta => Err(Error::new(
+            meta.span(),
+            "expected a location like #[location = 0]",
+        )),
+    }
+}
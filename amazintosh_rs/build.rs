@@ -10,7 +10,16 @@ fn main() {
     )
     .expect("Failed to get output bindings file");
 
-    Registry::new(Api::Gl, (3, 3), Profile::Core, Fallbacks::All, [])
-        .write_bindings(StructGenerator, &mut file)
-        .expect("failed to write Rust OpenGL bindings");
+    // Program binaries are loaded if the driver supports them, to skip
+    // compiling shaders that haven't changed, and debug output reports errors
+    // as they happen in debug builds
+    Registry::new(
+        Api::Gl,
+        (3, 3),
+        Profile::Core,
+        Fallbacks::All,
+        ["GL_ARB_get_program_binary", "GL_KHR_debug"],
+    )
+    .write_bindings(StructGenerator, &mut file)
+    .expect("failed to write Rust OpenGL bindings");
 }
@@ -0,0 +1,193 @@
+use crate::error::EngineError;
+use crate::render::RenderHandler;
+use crate::timing::FixedTimestep;
+use crate::window::AWindow;
+use sdl2::event::{Event, WindowEvent};
+use std::time::Duration;
+
+/// Whether the application should keep running after one of its hooks.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Control {
+    Continue,
+    Quit,
+}
+
+/// A game or tool run by a `Runner`, which calls these hooks from its loop.
+///
+/// Each hook is passed the window it runs in, which can be used to change
+/// the window or the timestep, such as to pause the simulation.
+pub trait Application<RHType: RenderHandler>: Sized {
+    /// Creates the application's state once the window and its context
+    /// exist.
+    fn init<WindowType: AWindow<RHType, Event>>(
+        window: &mut WindowType,
+        render_handler: &mut RHType,
+    ) -> Result<Self, EngineError>;
+
+    /// Advances the simulation by one tick of the provided length. This is
+    /// called as many times each frame as the window's timestep calls for.
+    fn update<WindowType: AWindow<RHType, Event>>(
+        &mut self,
+        _window: &mut WindowType,
+        _dt: Duration,
+    ) -> Control {
+        Control::Continue
+    }
+
+    /// Draws a frame, with `alpha` saying how far the frame is between the
+    /// previous tick and the latest one, from 0 to 1.
+    fn render<WindowType: AWindow<RHType, Event>>(
+        &mut self,
+        window: &mut WindowType,
+        render_handler: &mut RHType,
+        alpha: f32,
+    );
+
+    /// Handles an event. By default, the application quits when the window
+    /// is closed. The application always quits after an `AppTerminating`
+    /// event, whatever this returns.
+    fn on_event<WindowType: AWindow<RHType, Event>>(
+        &mut self,
+        _window: &mut WindowType,
+        event: Event,
+    ) -> Control {
+        match event {
+            Event::Quit { .. }
+            | Event::Window {
+                win_event: WindowEvent::Close,
+                ..
+            } => Control::Quit,
+            _ => Control::Continue,
+        }
+    }
+
+    /// Called with the window's new size in pixels whenever its size
+    /// changes, including when its display mode changes, before the
+    /// `SizeChanged` event is passed to `on_event`. The viewport has already
+    /// been updated to the new size.
+    fn on_resize<WindowType: AWindow<RHType, Event>>(
+        &mut self,
+        _window: &mut WindowType,
+        _render_handler: &mut RHType,
+        _size: (u32, u32),
+    ) {
+    }
+
+    /// Called once when the application is about to stop running, while the
+    /// window and its context still exist.
+    fn on_quit<WindowType: AWindow<RHType, Event>>(&mut self, _window: &mut WindowType) {}
+}
+
+/// Owns a window and its context and runs an application in it.
+pub struct Runner<RHType: RenderHandler, WindowType: AWindow<RHType, Event>> {
+    window: WindowType,
+    render_handler: RHType,
+}
+
+impl<RHType, WindowType> Runner<RHType, WindowType>
+where
+    RHType: RenderHandler,
+    WindowType: AWindow<RHType, Event>,
+    EngineError: From<WindowType::ErrorType>,
+{
+    pub fn new(mut window: WindowType) -> Result<Self, EngineError> {
+        let render_handler = window.ctx().ok_or(EngineError::NoContext)?;
+        Ok(Self {
+            window,
+            render_handler,
+        })
+    }
+
+    /// Sets how often the application is updated, which can also be changed
+    /// while it runs through the window passed to each hook.
+    pub fn with_timestep(mut self, timestep: FixedTimestep) -> Self {
+        *self.window.timestep_mut() = timestep;
+        self
+    }
+
+    pub fn window_mut(&mut self) -> &mut WindowType {
+        &mut self.window
+    }
+
+    /// Initializes the application and runs it until one of its hooks quits.
+    pub fn run<App: Application<RHType>>(self) -> Result<(), EngineError> {
+        let Self {
+            mut window,
+            mut render_handler,
+        } = self;
+        let app = App::init(&mut window, &mut render_handler)?;
+
+        window.start_loop(
+            RunnerState {
+                app,
+                render_handler,
+                quit: false,
+            },
+            |window, state, dt| {
+                let control = state.app.update(window, dt);
+                state.quit_if(window, control)
+            },
+            |window, state| {
+                let alpha = window.timestep().alpha();
+                state.app.render(window, &mut state.render_handler, alpha);
+
+                if let Err(e) = state.render_handler.check_errors() {
+                    log::error!("OpenGL error while rendering: {}", e);
+                }
+                false
+            },
+            |_, _| false,
+            |window, state, event| {
+                if let Event::Window {
+                    win_event: WindowEvent::SizeChanged(..),
+                    ..
+                } = event
+                {
+                    // The event's size is in screen coordinates rather than
+                    // pixels on high DPI displays
+                    let size = state.render_handler.default_framebuffer_size();
+                    state.app.on_resize(window, &mut state.render_handler, size);
+                }
+
+                // The window is about to stop running, which can't be
+                // refused
+                let terminating = matches!(event, Event::AppTerminating { .. });
+                let control = match state.app.on_event(window, event) {
+                    _ if terminating => Control::Quit,
+                    control => control,
+                };
+                state.quit_if(window, control)
+            },
+        )?;
+
+        Ok(())
+    }
+}
+
+/// The data the runner passes through the window's loop.
+struct RunnerState<RHType: RenderHandler, App: Application<RHType>> {
+    app: App,
+    render_handler: RHType,
+    /// Whether `on_quit` has been called.
+    quit: bool,
+}
+
+impl<RHType: RenderHandler, App: Application<RHType>> RunnerState<RHType, App> {
+    /// Lets the application know it is quitting, returning whether the loop
+    /// should stop. The application is only told once.
+    fn quit_if<WindowType: AWindow<RHType, Event>>(
+        &mut self,
+        window: &mut WindowType,
+        control: Control,
+    ) -> bool {
+        if control == Control::Quit {
+            if !self.quit {
+                self.quit = true;
+                self.app.on_quit(window);
+            }
+            true
+        } else {
+            false
+        }
+    }
+}
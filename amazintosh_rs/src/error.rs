@@ -0,0 +1,110 @@
+use crate::render::atlas::AtlasError;
+use crate::render::buffer::BufferError;
+use crate::render::capture::CaptureError;
+use crate::render::debug::GlError;
+use crate::render::framebuffer::FramebufferError;
+use crate::render::mesh::MeshError;
+use crate::render::shader::ShaderError;
+use crate::render::text::TextError;
+use crate::render::texture::TextureError;
+use crate::window::SdlWindowError;
+use crate::window_config::SettingsError;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+/// Any error the engine can return, so that code using several parts of the
+/// engine can return them all with `?`.
+///
+/// This displays the same message as the error it wraps and has the same
+/// source, so the chain of causes reads the same either way.
+#[derive(Debug)]
+pub enum EngineError {
+    /// The window has no context to render with.
+    NoContext,
+    Window(SdlWindowError),
+    Settings(SettingsError),
+    Gl(GlError),
+    Shader(ShaderError),
+    Buffer(BufferError),
+    Mesh(MeshError),
+    Texture(TextureError),
+    Atlas(AtlasError),
+    Text(TextError),
+    Framebuffer(FramebufferError),
+    Capture(CaptureError),
+}
+
+impl EngineError {
+    /// The error this wraps, if it wraps one.
+    pub fn inner(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::NoContext => None,
+            Self::Window(error) => Some(error),
+            Self::Settings(error) => Some(error),
+            Self::Gl(error) => Some(error),
+            Self::Shader(error) => Some(error),
+            Self::Buffer(error) => Some(error),
+            Self::Mesh(error) => Some(error),
+            Self::Texture(error) => Some(error),
+            Self::Atlas(error) => Some(error),
+            Self::Text(error) => Some(error),
+            Self::Framebuffer(error) => Some(error),
+            Self::Capture(error) => Some(error),
+        }
+    }
+}
+
+impl Display for EngineError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.inner() {
+            Some(error) => Display::fmt(error, f),
+            None => write!(f, "the window has no context to render with"),
+        }
+    }
+}
+
+impl Error for EngineError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.inner().and_then(Error::source)
+    }
+}
+
+macro_rules! engine_error_from {
+    ($($error:ty => $variant:ident),* $(,)?) => {
+        $(
+            impl From<$error> for EngineError {
+                fn from(error: $error) -> Self {
+                    Self::$variant(error)
+                }
+            }
+        )*
+    };
+}
+
+engine_error_from!(
+    SdlWindowError => Window,
+    SettingsError => Settings,
+    GlError => Gl,
+    ShaderError => Shader,
+    BufferError => Buffer,
+    MeshError => Mesh,
+    TextureError => Texture,
+    AtlasError => Atlas,
+    TextError => Text,
+    FramebufferError => Framebuffer,
+    CaptureError => Capture,
+);
+
+/// Formats an error followed by each of the errors that caused it, such as
+/// "failed to create an OpenGL context: GLXBadFBConfig", which is what
+/// most error reports should show.
+pub fn report(error: &dyn Error) -> String {
+    let mut report = error.to_string();
+    let mut source = error.source();
+    while let Some(error) = source {
+        report.push_str(": ");
+        report.push_str(&error.to_string());
+        source = error.source();
+    }
+    report
+}
@@ -0,0 +1,223 @@
+use crate::render::leak;
+use crate::render::{Gl, RenderHandler};
+use crate::timing::FixedTimestep;
+use crate::window::{run_ticks, AWindow, SdlWindow, SdlWindowError};
+use crate::window_config::{DisplayMode, Vsync, WindowConfig};
+use sdl2::event::{Event, WindowEvent};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// A window that is never shown, for running the game loop on machines
+/// without a display, such as for soak tests, replays and benchmarks.
+///
+/// Rather than waiting for real time to pass, every frame advances time by
+/// a fixed amount as fast as possible, so runs are repeatable. The only
+/// events are synthetic ones, which are scheduled for a given frame.
+pub struct HeadlessWindow<RHType: RenderHandler> {
+    render_handler: RHType,
+    config: WindowConfig,
+    timestep: FixedTimestep,
+    frame_time: Duration,
+    frame: u64,
+    max_frames: Option<u64>,
+    /// Events waiting to be sent, by the frame they are sent after.
+    events: BTreeMap<u64, Vec<Event>>,
+    /// The hidden window that owns the OpenGL context, if there is one.
+    _hidden: Option<SdlWindow>,
+}
+
+impl<RHType: RenderHandler> HeadlessWindow<RHType> {
+    /// Creates a window drawing with the provided render handler, which
+    /// doesn't need a display, such as `MockRenderHandler`.
+    pub fn new(render_handler: &RHType, width: usize, height: usize) -> Self {
+        let mut render_handler = render_handler.clone();
+        render_handler.set_default_framebuffer_size((width as u32, height as u32));
+
+        Self {
+            render_handler,
+            config: WindowConfig::new("", width, height).with_hidden(true),
+            timestep: FixedTimestep::default(),
+            frame_time: Duration::from_secs(1) / 60,
+            frame: 0,
+            max_frames: None,
+            events: BTreeMap::new(),
+            _hidden: None,
+        }
+    }
+
+    /// Sets how much time passes each frame, which defaults to a sixtieth of
+    /// a second.
+    pub fn with_frame_time(mut self, frame_time: Duration) -> Self {
+        self.frame_time = frame_time;
+        self
+    }
+
+    /// Stops the loop after the provided number of frames, rather than
+    /// waiting for a callback to stop it. An `AppTerminating` event is sent
+    /// before the loop stops, so that the application can shut down.
+    pub fn with_max_frames(mut self, max_frames: u64) -> Self {
+        self.max_frames = Some(max_frames);
+        self
+    }
+
+    /// Sends an event after the provided frame is drawn, counting from 0.
+    /// Events for frames that have already been drawn are sent after the
+    /// current frame.
+    pub fn schedule_event(&mut self, frame: u64, event: Event) {
+        self.events.entry(frame).or_default().push(event);
+    }
+
+    /// Sends an event after the current frame is drawn.
+    pub fn push_event(&mut self, event: Event) {
+        self.schedule_event(self.frame, event);
+    }
+
+    /// The number of frames that have been drawn.
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// The amount of time that has passed in the loop.
+    pub fn elapsed(&self) -> Duration {
+        // Long runs have more frames than fit in a `u32`
+        let nanos = self.frame_time.as_nanos() * self.frame as u128;
+        Duration::new(
+            (nanos / 1_000_000_000) as u64,
+            (nanos % 1_000_000_000) as u32,
+        )
+    }
+
+    /// Takes every event scheduled up to the current frame, in order.
+    fn take_events(&mut self) -> Vec<Event> {
+        let later = self.events.split_off(&(self.frame + 1));
+        std::mem::replace(&mut self.events, later)
+            .into_values()
+            .flatten()
+            .collect()
+    }
+}
+
+impl HeadlessWindow<Gl> {
+    /// Creates a window with a real OpenGL context in a hidden window, for
+    /// when there is a display but drawing should still happen offscreen.
+    pub fn hidden(width: usize, height: usize) -> Result<Self, SdlWindowError> {
+        let mut window = SdlWindow::hidden("", width, height)?;
+        let gl = window.ctx().expect("SDL windows always have a context");
+
+        let mut headless = Self::new(&gl, width, height);
+        headless.config = window.config();
+        headless._hidden = Some(window);
+        Ok(headless)
+    }
+}
+
+impl<RHType: RenderHandler> AWindow<RHType, Event> for HeadlessWindow<RHType> {
+    type ErrorType = SdlWindowError;
+
+    fn set_title(&mut self, title: &str) -> Result<(), Self::ErrorType> {
+        self.config.title = title.to_owned();
+        Ok(())
+    }
+
+    /// Resizes the window, sending a `SizeChanged` event after the current
+    /// frame like a real window would.
+    fn set_size(&mut self, size: (usize, usize)) -> Result<(), Self::ErrorType> {
+        self.config.size = size;
+        self.push_event(Event::Window {
+            timestamp: 0,
+            window_id: 0,
+            win_event: WindowEvent::SizeChanged(size.0 as i32, size.1 as i32),
+        });
+        Ok(())
+    }
+
+    fn size(&self) -> (usize, usize) {
+        self.config.size
+    }
+
+    fn set_display_mode(&mut self, display_mode: DisplayMode) -> Result<(), Self::ErrorType> {
+        self.config.display_mode = display_mode;
+        Ok(())
+    }
+
+    fn set_vsync(&mut self, vsync: Vsync) -> Result<(), Self::ErrorType> {
+        self.config.vsync = vsync;
+        Ok(())
+    }
+
+    fn config(&self) -> WindowConfig {
+        self.config.clone()
+    }
+
+    fn hide(&mut self) -> Result<(), Self::ErrorType> {
+        // Never shown
+        Ok(())
+    }
+
+    fn ctx(&mut self) -> Option<RHType> {
+        Some(self.render_handler.clone())
+    }
+
+    fn timestep(&self) -> &FixedTimestep {
+        &self.timestep
+    }
+
+    fn timestep_mut(&mut self) -> &mut FixedTimestep {
+        &mut self.timestep
+    }
+
+    fn start_loop<
+        DataType,
+        Update: Fn(&mut Self, &mut DataType, Duration) -> bool,
+        PreRender: Fn(&mut Self, &mut DataType) -> bool,
+        PostRender: Fn(&mut Self, &mut DataType) -> bool,
+        EventHandler: Fn(&mut Self, &mut DataType, Event) -> bool,
+    >(
+        mut self,
+        mut data: DataType,
+        update: Update,
+        pre_render: PreRender,
+        post_render: PostRender,
+        event_handler: EventHandler,
+    ) -> Result<(), Self::ErrorType> {
+        'running: loop {
+            if self.max_frames == Some(self.frame) {
+                event_handler(&mut self, &mut data, Event::AppTerminating { timestamp: 0 });
+                break 'running;
+            }
+
+            let frame_time = self.frame_time;
+            if run_ticks(&mut self, &mut data, frame_time, &update) {
+                break 'running;
+            }
+
+            if pre_render(&mut self, &mut data) || post_render(&mut self, &mut data) {
+                break 'running;
+            }
+
+            for event in self.take_events() {
+                // Resize the viewport like a real window does
+                if let Event::Window {
+                    win_event: WindowEvent::SizeChanged(w, h),
+                    ..
+                } = event
+                {
+                    self.render_handler.viewport(0, 0, w as u32, h as u32);
+                    self.render_handler
+                        .set_default_framebuffer_size((w as u32, h as u32));
+                }
+
+                if event_handler(&mut self, &mut data, event) {
+                    break 'running;
+                }
+            }
+
+            self.frame += 1;
+        }
+
+        drop(data);
+        leak::report_leaks();
+
+        Ok(())
+    }
+}
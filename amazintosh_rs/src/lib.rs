@@ -1,11 +1,21 @@
 #![feature(cstring_from_vec_with_nul)]
 
+// Allows the derive macros to refer to this crate by name from inside of it
+extern crate self as amazintosh_rs;
+
 pub extern crate nalgebra_glm as glm;
 
+pub mod application;
+pub mod error;
+pub mod headless;
+pub mod logging;
 pub mod render;
+pub mod timing;
 pub mod window;
+pub mod window_config;
 pub mod world;
 
+pub use log;
 pub use nalgebra;
 pub use sdl2;
 pub use specs;
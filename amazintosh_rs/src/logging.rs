@@ -0,0 +1,127 @@
+use log::{Level, LevelFilter, Log, Metadata, Record, SetLoggerError};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+/// A message that was logged, kept so it can be shown in game.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogEntry {
+    pub level: Level,
+    /// The module that logged the message, such as
+    /// `amazintosh_rs::render::buffer`.
+    pub target: String,
+    pub message: String,
+}
+
+#[derive(Debug)]
+struct HistoryState {
+    entries: VecDeque<LogEntry>,
+    capacity: usize,
+    generation: u64,
+}
+
+/// The most recent log messages, shared between the logger and anything that
+/// displays them, such as the log console overlay.
+#[derive(Debug, Clone)]
+pub struct LogHistory(Arc<Mutex<HistoryState>>);
+
+impl LogHistory {
+    /// Creates an empty history that keeps up to `capacity` messages,
+    /// forgetting the oldest ones first.
+    pub fn new(capacity: usize) -> Self {
+        Self(Arc::new(Mutex::new(HistoryState {
+            entries: VecDeque::with_capacity(capacity),
+            capacity,
+            generation: 0,
+        })))
+    }
+
+    pub fn push(&self, entry: LogEntry) {
+        let mut state = self.0.lock().unwrap();
+        if state.entries.len() == state.capacity {
+            state.entries.pop_front();
+        }
+        if state.capacity > 0 {
+            state.entries.push_back(entry);
+        }
+        state.generation += 1;
+    }
+
+    /// Up to `count` of the most recent messages, oldest first.
+    pub fn recent(&self, count: usize) -> Vec<LogEntry> {
+        let state = self.0.lock().unwrap();
+        let skip = state.entries.len().saturating_sub(count);
+        state.entries.iter().skip(skip).cloned().collect()
+    }
+
+    /// The number of messages ever pushed, which can be compared to find out
+    /// whether anything new has been logged.
+    pub fn generation(&self) -> u64 {
+        self.0.lock().unwrap().generation
+    }
+}
+
+/// Prints log messages to stderr and keeps the important ones in a history.
+pub struct EngineLogger {
+    level: LevelFilter,
+    history_level: LevelFilter,
+    history: LogHistory,
+}
+
+impl EngineLogger {
+    /// Creates a logger that prints messages at or above `level`, and keeps
+    /// warnings and errors in the provided history.
+    pub fn new(level: LevelFilter, history: LogHistory) -> Self {
+        Self {
+            level,
+            history_level: LevelFilter::Warn,
+            history,
+        }
+    }
+
+    /// Sets the least important level of messages kept in the history.
+    pub fn with_history_level(mut self, level: LevelFilter) -> Self {
+        self.history_level = level;
+        self
+    }
+
+    pub fn history(&self) -> &LogHistory {
+        &self.history
+    }
+
+    /// Installs this as the logger used by the `log` macros. This can only
+    /// be done once.
+    pub fn install(self) -> Result<(), SetLoggerError> {
+        log::set_max_level(self.level.max(self.history_level));
+        log::set_boxed_logger(Box::new(self))
+    }
+}
+
+impl Log for EngineLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.level() <= self.level || metadata.level() <= self.history_level
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if record.level() <= self.level {
+            eprintln!("[{} {}] {}", record.level(), record.target(), record.args());
+        }
+
+        if record.level() <= self.history_level {
+            self.history.push(LogEntry {
+                level: record.level(),
+                target: record.target().to_owned(),
+                message: record.args().to_string(),
+            });
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Installs an `EngineLogger` that prints messages at or above `level`,
+/// returning the history of recent warnings and errors.
+pub fn init(level: LevelFilter) -> Result<LogHistory, SetLoggerError> {
+    let history = LogHistory::new(64);
+    EngineLogger::new(level, history.clone()).install()?;
+    Ok(history)
+}
@@ -0,0 +1,443 @@
+use crate::render::texture::{
+    surface_to_rgba, Texture2D, TextureError, TextureFormat, TextureOptions,
+};
+use crate::render::RenderHandler;
+use nalgebra::Vector2;
+use sdl2::image::LoadSurface;
+use sdl2::surface::Surface;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+use std::path::Path;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct SkylineNode {
+    x: u32,
+    y: u32,
+    width: u32,
+}
+
+/// Packs rectangles into a fixed size area using the skyline bottom-left
+/// heuristic. The packer only tracks the top edge of the placed rectangles,
+/// which keeps insertion fast and allows rectangles to be added at any time,
+/// such as when glyphs are rasterized on demand.
+#[derive(Debug, Clone)]
+pub struct SkylinePacker {
+    width: u32,
+    height: u32,
+    skyline: Vec<SkylineNode>,
+}
+
+impl SkylinePacker {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            skyline: vec![SkylineNode { x: 0, y: 0, width }],
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Finds a place for a rectangle of the provided size and reserves it,
+    /// returning the position of its corner nearest to the origin. Returns
+    /// `None` if there is no space left for the rectangle.
+    pub fn insert(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+        // Find the node that leaves the lowest top edge, preferring the
+        // narrowest node when there is a tie to reduce wasted space
+        let mut best: Option<(usize, u32, u32, u32)> = None;
+        for index in 0..self.skyline.len() {
+            if let Some(y) = self.fit(index, width, height) {
+                let node_width = self.skyline[index].width;
+                let better = match best {
+                    None => true,
+                    Some((_, best_y, _, best_width)) => {
+                        y < best_y || (y == best_y && node_width < best_width)
+                    }
+                };
+                if better {
+                    best = Some((index, y, self.skyline[index].x, node_width));
+                }
+            }
+        }
+
+        let (index, y, x, _) = best?;
+        self.add_level(index, x, y, width, height);
+
+        Some((x, y))
+    }
+
+    /// Checks whether a rectangle fits with its left edge on the node at the
+    /// provided index, returning the height it would be placed at.
+    fn fit(&self, index: usize, width: u32, height: u32) -> Option<u32> {
+        let x = self.skyline[index].x;
+        if x.checked_add(width).is_none_or(|right| right > self.width) {
+            return None;
+        }
+
+        // The rectangle has to rest on the highest node it spans
+        let mut y = 0;
+        let mut remaining = width as i64;
+        for node in &self.skyline[index..] {
+            if remaining <= 0 {
+                break;
+            }
+            y = y.max(node.y);
+            remaining -= node.width as i64;
+        }
+
+        if y.checked_add(height).is_none_or(|top| top > self.height) {
+            return None;
+        }
+
+        Some(y)
+    }
+
+    fn add_level(&mut self, index: usize, x: u32, y: u32, width: u32, height: u32) {
+        self.skyline.insert(
+            index,
+            SkylineNode {
+                x,
+                y: y + height,
+                width,
+            },
+        );
+
+        // Shrink or remove the nodes that are now covered by the new one
+        let right = x + width;
+        let next = index + 1;
+        while next < self.skyline.len() {
+            let node = self.skyline[next];
+            if node.x >= right {
+                break;
+            }
+
+            let overlap = right - node.x;
+            if overlap >= node.width {
+                self.skyline.remove(next);
+            } else {
+                self.skyline[next].x += overlap;
+                self.skyline[next].width -= overlap;
+                break;
+            }
+        }
+
+        // Merge neighbouring nodes at the same height
+        let mut i = 0;
+        while i + 1 < self.skyline.len() {
+            if self.skyline[i].y == self.skyline[i + 1].y {
+                self.skyline[i].width += self.skyline[i + 1].width;
+                self.skyline.remove(i + 1);
+            } else {
+                i += 1;
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AtlasError {
+    DuplicateName(String),
+    ImageTooLarge(String),
+    Texture(TextureError),
+}
+
+impl Display for AtlasError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::DuplicateName(name) => write!(f, "atlas already has an image named `{}`", name),
+            Self::ImageTooLarge(name) => {
+                write!(f, "image `{}` is too large to fit on an atlas page", name)
+            }
+            Self::Texture(_) => write!(f, "failed to add image to the atlas"),
+        }
+    }
+}
+
+impl Error for AtlasError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Texture(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<TextureError> for AtlasError {
+    fn from(error: TextureError) -> Self {
+        Self::Texture(error)
+    }
+}
+
+/// The area of an atlas page that an image was packed into.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AtlasRegion {
+    /// The index of the page the image is on.
+    pub page: usize,
+    /// The position of the image on the page in pixels.
+    pub position: (u32, u32),
+    /// The size of the image in pixels.
+    pub size: (u32, u32),
+    /// The texture coordinate of the image's first pixel.
+    pub uv_min: Vector2<f32>,
+    /// The texture coordinate of the image's last pixel's far corner.
+    pub uv_max: Vector2<f32>,
+}
+
+/// A single page of packed images stored as tightly packed RGBA bytes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AtlasPage {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl AtlasPage {
+    fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![0; width as usize * height as usize * 4],
+        }
+    }
+
+    /// Copies an image onto the page with its top left corner at the
+    /// provided position, extending its edge pixels out into the padding
+    /// around it.
+    fn blit(&mut self, position: (u32, u32), image: &AtlasImage, padding: u32) {
+        if image.width == 0 || image.height == 0 {
+            return;
+        }
+
+        let row_length = image.width as usize * 4;
+        let padded_width = image.width + padding * 2;
+        for y in 0..image.height + padding * 2 {
+            let src_row = y.saturating_sub(padding).min(image.height - 1) as usize;
+            let src = &image.pixels[src_row * row_length..(src_row + 1) * row_length];
+
+            let page_y = (position.1 + y - padding) as usize;
+            for x in 0..padded_width {
+                let src_x = x.saturating_sub(padding).min(image.width - 1) as usize * 4;
+                let start =
+                    (page_y * self.width as usize + (position.0 + x - padding) as usize) * 4;
+                self.pixels[start..start + 4].copy_from_slice(&src[src_x..src_x + 4]);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct AtlasImage {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+/// Collects images and packs them into as few atlas pages as possible.
+#[derive(Debug, Clone)]
+pub struct AtlasBuilder {
+    page_size: (u32, u32),
+    padding: u32,
+    images: Vec<(String, AtlasImage)>,
+}
+
+impl AtlasBuilder {
+    pub fn new(page_width: u32, page_height: u32) -> Self {
+        Self {
+            page_size: (page_width, page_height),
+            padding: 1,
+            images: Vec::new(),
+        }
+    }
+
+    /// Sets the number of pixels of padding around each image, so
+    /// neighbouring images are twice this far apart. The padding repeats the
+    /// pixels on the image's edges, so that filtering near an edge blends in
+    /// copies of the edge rather than neighbouring images or transparent
+    /// pixels. Mipmaps halve the padding at each level, so the padding needs
+    /// to be at least `2^N` pixels to protect the first `N` levels.
+    pub fn with_padding(mut self, padding: u32) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Adds an image from tightly packed RGBA bytes.
+    pub fn add_rgba(
+        &mut self,
+        name: &str,
+        width: u32,
+        height: u32,
+        pixels: Vec<u8>,
+    ) -> Result<(), AtlasError> {
+        let expected = width as usize * height as usize * 4;
+        if pixels.len() != expected {
+            return Err(TextureError::InvalidDataLength {
+                expected,
+                found: pixels.len(),
+            }
+            .into());
+        }
+        if self.images.iter().any(|(existing, _)| existing == name) {
+            return Err(AtlasError::DuplicateName(name.to_owned()));
+        }
+
+        self.images.push((
+            name.to_owned(),
+            AtlasImage {
+                width,
+                height,
+                pixels,
+            },
+        ));
+        Ok(())
+    }
+
+    /// Loads an image file (such as a PNG or JPEG) and adds it.
+    pub fn add_file<P: AsRef<Path>>(&mut self, name: &str, path: P) -> Result<(), AtlasError> {
+        let surface = Surface::from_file(path).map_err(TextureError::LoadFailed)?;
+        let (width, height) = surface.size();
+
+        self.add_rgba(name, width, height, surface_to_rgba(&surface)?)
+    }
+
+    /// Packs all of the added images into pages without touching OpenGL.
+    pub fn pack(&self) -> Result<PackedAtlas, AtlasError> {
+        let (page_width, page_height) = self.page_size;
+        let padding = self.padding;
+
+        // Placing the tallest images first wastes the least space
+        let mut order: Vec<usize> = (0..self.images.len()).collect();
+        order.sort_by(|&a, &b| {
+            let (a, b) = (&self.images[a].1, &self.images[b].1);
+            b.height.cmp(&a.height).then(b.width.cmp(&a.width))
+        });
+
+        let mut packers: Vec<SkylinePacker> = Vec::new();
+        let mut pages: Vec<AtlasPage> = Vec::new();
+        let mut regions = HashMap::new();
+
+        for index in order {
+            let (name, image) = &self.images[index];
+            let pad = |size: u32| {
+                padding
+                    .checked_mul(2)
+                    .and_then(|padding| size.checked_add(padding))
+            };
+            let padded = match (pad(image.width), pad(image.height)) {
+                (Some(width), Some(height)) if width <= page_width && height <= page_height => {
+                    (width, height)
+                }
+                _ => return Err(AtlasError::ImageTooLarge(name.clone())),
+            };
+
+            // Try every existing page before starting a new one
+            let placement = packers
+                .iter_mut()
+                .enumerate()
+                .find_map(|(page, packer)| packer.insert(padded.0, padded.1).map(|p| (page, p)));
+            let (page, (x, y)) = match placement {
+                Some(placement) => placement,
+                None => {
+                    let mut packer = SkylinePacker::new(page_width, page_height);
+                    let position = packer
+                        .insert(padded.0, padded.1)
+                        .expect("image should fit on an empty page");
+                    packers.push(packer);
+                    pages.push(AtlasPage::new(page_width, page_height));
+                    (pages.len() - 1, position)
+                }
+            };
+
+            let position = (x + padding, y + padding);
+            pages[page].blit(position, image, padding);
+            regions.insert(
+                name.clone(),
+                AtlasRegion {
+                    page,
+                    position,
+                    size: (image.width, image.height),
+                    uv_min: Vector2::new(
+                        position.0 as f32 / page_width as f32,
+                        position.1 as f32 / page_height as f32,
+                    ),
+                    uv_max: Vector2::new(
+                        (position.0 + image.width) as f32 / page_width as f32,
+                        (position.1 + image.height) as f32 / page_height as f32,
+                    ),
+                },
+            );
+        }
+
+        Ok(PackedAtlas { pages, regions })
+    }
+}
+
+/// The result of packing an atlas on the CPU.
+#[derive(Debug, Clone)]
+pub struct PackedAtlas {
+    pub pages: Vec<AtlasPage>,
+    pub regions: HashMap<String, AtlasRegion>,
+}
+
+impl PackedAtlas {
+    pub fn region(&self, name: &str) -> Option<&AtlasRegion> {
+        self.regions.get(name)
+    }
+
+    /// Uploads every page into its own texture.
+    pub fn upload<RHType: RenderHandler>(
+        self,
+        render_handler: &mut RHType,
+        options: TextureOptions,
+    ) -> Result<TextureAtlas<RHType>, TextureError> {
+        let pages = self
+            .pages
+            .iter()
+            .map(|page| {
+                Texture2D::from_data(
+                    render_handler,
+                    TextureFormat::Rgba8,
+                    page.width,
+                    page.height,
+                    &page.pixels,
+                    options,
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(TextureAtlas {
+            pages,
+            regions: self.regions,
+        })
+    }
+}
+
+/// A packed atlas that has been uploaded to textures.
+pub struct TextureAtlas<RHType: RenderHandler> {
+    pages: Vec<Texture2D<RHType>>,
+    regions: HashMap<String, AtlasRegion>,
+}
+
+impl<RHType: RenderHandler> TextureAtlas<RHType> {
+    pub fn region(&self, name: &str) -> Option<&AtlasRegion> {
+        self.regions.get(name)
+    }
+
+    pub fn page(&self, page: usize) -> Option<&Texture2D<RHType>> {
+        self.pages.get(page)
+    }
+
+    pub fn page_mut(&mut self, page: usize) -> Option<&mut Texture2D<RHType>> {
+        self.pages.get_mut(page)
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+}
@@ -1,4 +1,6 @@
-use std::fmt::Debug;
+use crate::render::leak::{self, ResourceKind};
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
 use std::marker::PhantomData;
 
 // Handler for creating and managing buffers
@@ -7,6 +9,13 @@ pub trait BufferHandler: Clone {
 
     fn bind_buffer(&mut self, buffer_type: BufferType, handle: u32);
 
+    /// Binds a buffer to an indexed binding point of the provided type, such
+    /// as the binding point a uniform block reads from. This also binds the
+    /// buffer as if by `bind_buffer`.
+    fn bind_buffer_base(&mut self, buffer_type: BufferType, index: u32, handle: u32);
+
+    /// Allocates storage for the bound buffer and fills it with the
+    /// provided data.
     fn buffer_data<DataType: Sized>(
         &mut self,
         buffer_type: BufferType,
@@ -14,6 +23,19 @@ pub trait BufferHandler: Clone {
         data: &[DataType],
     );
 
+    /// Allocates uninitialized storage for the bound buffer, discarding its
+    /// previous contents.
+    fn allocate_buffer(&mut self, buffer_type: BufferType, usage: BufferUsage, size: usize);
+
+    /// Replaces part of the bound buffer's contents, starting at a byte
+    /// offset, without reallocating it.
+    fn buffer_sub_data<DataType: Sized>(
+        &mut self,
+        buffer_type: BufferType,
+        offset: usize,
+        data: &[DataType],
+    );
+
     fn delete_buffer(&mut self, handle: u32);
 }
 
@@ -21,49 +43,207 @@ pub trait BufferHandler: Clone {
 pub enum BufferType {
     ArrayBuffer,
     ElementArrayBuffer,
+    /// Holds the data of uniform blocks, which can be shared by programs.
+    UniformBuffer,
 }
 
+/// A hint about how often a buffer's contents change (static, dynamic or
+/// stream) and how they are used (drawn from, read back, or copied between
+/// buffers).
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum BufferUsage {
+    /// Set once and drawn many times.
     StaticDraw,
+    StaticRead,
+    StaticCopy,
+    /// Changed occasionally and drawn many times, such as per-tick
+    /// simulation data.
+    DynamicDraw,
+    DynamicRead,
+    DynamicCopy,
+    /// Changed every time it is drawn.
+    StreamDraw,
+    StreamRead,
+    StreamCopy,
+}
+
+impl BufferUsage {
+    /// Whether the contents are replaced about as often as they are used.
+    pub fn is_stream(self) -> bool {
+        matches!(self, Self::StreamDraw | Self::StreamRead | Self::StreamCopy)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum BufferError {
+    OutOfBounds {
+        offset: usize,
+        count: usize,
+        len: usize,
+    },
 }
 
+impl Display for BufferError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::OutOfBounds { offset, count, len } => write!(
+                f,
+                "cannot update {} elements at offset {} of a buffer holding {}",
+                count, offset, len
+            ),
+        }
+    }
+}
+
+impl Error for BufferError {}
+
 pub struct Buffer<BHType: Clone + BufferHandler, DataType: Sized> {
     buffer_handler: BHType,
     buffer_type: BufferType,
     handle: u32,
+    usage: BufferUsage,
+    len: usize,
+    capacity: usize,
     _phantom: PhantomData<DataType>,
 }
 
 impl<BHType: BufferHandler, DataType: Sized> Buffer<BHType, DataType> {
     pub fn new(buffer_handler: &mut BHType, buffer_type: BufferType) -> Self {
+        let handle = buffer_handler.gen_buffer();
+        leak::track(ResourceKind::Buffer, handle);
+
         Self {
-            handle: buffer_handler.gen_buffer(),
+            handle,
             buffer_handler: buffer_handler.clone(),
             buffer_type,
+            usage: BufferUsage::StaticDraw,
+            len: 0,
+            capacity: 0,
             _phantom: PhantomData,
         }
     }
 
+    /// Creates an empty buffer with room for `capacity` elements.
+    pub fn with_capacity(
+        buffer_handler: &mut BHType,
+        buffer_type: BufferType,
+        usage: BufferUsage,
+        capacity: usize,
+    ) -> Self {
+        let mut buffer = Self::new(buffer_handler, buffer_type);
+        buffer.usage = usage;
+        buffer.reallocate(capacity);
+        buffer
+    }
+
     pub fn bind(&mut self) {
         self.buffer_handler
             .bind_buffer(self.buffer_type, self.handle);
     }
 
+    /// Replaces the contents of this buffer. The buffer's storage is only
+    /// reallocated if the data doesn't fit or the usage has changed, growing
+    /// to at least double its capacity to keep frequent updates cheap.
     pub fn buffer_data(&mut self, data: Vec<DataType>, usage: BufferUsage) {
         self.bind();
-        self.buffer_handler
-            .buffer_data(self.buffer_type, usage, &data)
+
+        if usage != self.usage || data.len() > self.capacity {
+            let grown = if usage == self.usage {
+                data.len().max(self.capacity * 2)
+            } else {
+                data.len()
+            };
+            self.usage = usage;
+
+            if grown == data.len() {
+                self.buffer_handler
+                    .buffer_data(self.buffer_type, usage, &data);
+                self.capacity = grown;
+            } else {
+                self.reallocate(grown);
+                self.buffer_handler
+                    .buffer_sub_data(self.buffer_type, 0, &data);
+            }
+        } else {
+            // Orphan streamed buffers so the driver doesn't have to wait for
+            // draws using the old contents to finish
+            if usage.is_stream() {
+                self.reallocate(self.capacity);
+            }
+            self.buffer_handler
+                .buffer_sub_data(self.buffer_type, 0, &data);
+        }
+
+        self.len = data.len();
+    }
+
+    /// Replaces a range of this buffer's contents, starting at the element at
+    /// `offset`, without reallocating it.
+    pub fn update(&mut self, offset: usize, data: &[DataType]) -> Result<(), BufferError> {
+        if offset
+            .checked_add(data.len())
+            .is_none_or(|end| end > self.len)
+        {
+            return Err(BufferError::OutOfBounds {
+                offset,
+                count: data.len(),
+                len: self.len,
+            });
+        }
+
+        self.bind();
+        self.buffer_handler.buffer_sub_data(
+            self.buffer_type,
+            offset * std::mem::size_of::<DataType>(),
+            data,
+        );
+
+        Ok(())
+    }
+
+    fn reallocate(&mut self, capacity: usize) {
+        self.bind();
+        self.buffer_handler.allocate_buffer(
+            self.buffer_type,
+            self.usage,
+            capacity * std::mem::size_of::<DataType>(),
+        );
+        self.capacity = capacity;
+    }
+
+    pub fn handle(&self) -> u32 {
+        self.handle
+    }
+
+    pub fn usage(&self) -> BufferUsage {
+        self.usage
+    }
+
+    /// The number of elements that were most recently buffered.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The number of elements the buffer's storage can hold without being
+    /// reallocated.
+    pub fn capacity(&self) -> usize {
+        self.capacity
     }
 }
 
 impl<BHType: BufferHandler, DataType: Sized> Drop for Buffer<BHType, DataType> {
     fn drop(&mut self) {
-        println!(
+        log::debug!(
             "Dropping buffer {} of type {:?}",
-            self.handle, self.buffer_type
+            self.handle,
+            self.buffer_type
         );
 
         self.buffer_handler.delete_buffer(self.handle);
+        leak::untrack(ResourceKind::Buffer, self.handle);
     }
 }
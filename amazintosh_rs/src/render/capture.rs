@@ -0,0 +1,211 @@
+use crate::render::framebuffer::Framebuffer;
+use crate::render::texture::TextureFormat;
+use crate::render::RenderHandler;
+use crate::window::SdlError;
+use sdl2::image::SaveSurface;
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::surface::Surface;
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug)]
+pub enum CaptureError {
+    CreateDirectoryFailed { path: PathBuf, error: io::Error },
+    SaveFailed { path: PathBuf, error: SdlError },
+}
+
+impl Display for CaptureError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::CreateDirectoryFailed { path, .. } => {
+                write!(f, "failed to create capture directory {}", path.display())
+            }
+            Self::SaveFailed { path, .. } => write!(f, "failed to save capture {}", path.display()),
+        }
+    }
+}
+
+impl Error for CaptureError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::CreateDirectoryFailed { error, .. } => Some(error),
+            Self::SaveFailed { error, .. } => Some(error),
+        }
+    }
+}
+
+/// The RGBA contents of a framebuffer, stored top to bottom.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Screenshot {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Screenshot {
+    /// Reads the contents of the window's framebuffer, which is always
+    /// opaque.
+    pub fn from_window<RHType: RenderHandler>(render_handler: &mut RHType) -> Self {
+        let size = render_handler.default_framebuffer_size();
+        render_handler.bind_framebuffer(0);
+        let mut pixels = render_handler.read_pixels(TextureFormat::Rgba8, (0, 0), size);
+
+        // Whatever was drawn to the back buffer's alpha isn't shown, so it
+        // would only make the screenshot see-through
+        for pixel in pixels.chunks_exact_mut(4) {
+            pixel[3] = 255;
+        }
+
+        Self::from_gl_pixels(size, pixels)
+    }
+
+    /// Reads the contents of an offscreen framebuffer's first color
+    /// attachment, which must be stored as RGBA.
+    pub fn from_framebuffer<RHType: RenderHandler>(framebuffer: &mut Framebuffer<RHType>) -> Self {
+        let size = framebuffer.size();
+        let pixels = framebuffer.read_pixels(TextureFormat::Rgba8, (0, 0), size);
+
+        Self::from_gl_pixels(size, pixels)
+    }
+
+    fn from_gl_pixels(size: (u32, u32), pixels: Vec<u8>) -> Self {
+        let mut screenshot = Self {
+            width: size.0,
+            height: size.1,
+            pixels,
+        };
+
+        // OpenGL stores the bottom row first, but images start at the top
+        screenshot.flip_vertical();
+        screenshot
+    }
+
+    /// Reverses the order of the rows of this screenshot.
+    pub fn flip_vertical(&mut self) {
+        let row_length = self.width as usize * 4;
+        let height = self.height as usize;
+
+        for row in 0..height / 2 {
+            let (top, bottom) = self.pixels.split_at_mut((height - row - 1) * row_length);
+            top[row * row_length..(row + 1) * row_length]
+                .swap_with_slice(&mut bottom[..row_length]);
+        }
+    }
+
+    /// Writes this screenshot to a PNG file.
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> Result<(), CaptureError> {
+        let path = path.as_ref();
+        let save_failed = |e| CaptureError::SaveFailed {
+            path: path.to_owned(),
+            error: SdlError(e),
+        };
+
+        let mut pixels = self.pixels.clone();
+        let surface = Surface::from_data(
+            &mut pixels,
+            self.width,
+            self.height,
+            self.width * 4,
+            PixelFormatEnum::RGBA32,
+        )
+        .map_err(save_failed)?;
+
+        surface.save(path).map_err(save_failed)
+    }
+}
+
+/// Saves every N-th frame to a numbered sequence of PNG files, such as for
+/// making a timelapse.
+#[derive(Debug, Clone)]
+pub struct FrameCapture {
+    directory: PathBuf,
+    prefix: String,
+    interval: u32,
+    frame: u64,
+    saved: u32,
+}
+
+impl FrameCapture {
+    /// Creates a capture that saves every `interval`-th frame into the
+    /// provided directory, which is created if it doesn't exist.
+    pub fn new<P: Into<PathBuf>>(directory: P, interval: u32) -> Result<Self, CaptureError> {
+        let directory = directory.into();
+        std::fs::create_dir_all(&directory).map_err(|error| {
+            CaptureError::CreateDirectoryFailed {
+                path: directory.clone(),
+                error,
+            }
+        })?;
+
+        Ok(Self {
+            directory,
+            prefix: "frame".to_owned(),
+            interval: interval.max(1),
+            frame: 0,
+            saved: 0,
+        })
+    }
+
+    pub fn with_prefix(mut self, prefix: &str) -> Self {
+        self.prefix = prefix.to_owned();
+        self
+    }
+
+    /// Counts a rendered frame, capturing the window's framebuffer if it is
+    /// due. Returns the path of the file that was written, if any.
+    pub fn capture_window<RHType: RenderHandler>(
+        &mut self,
+        render_handler: &mut RHType,
+    ) -> Result<Option<PathBuf>, CaptureError> {
+        match self.next_frame() {
+            Some(path) => Screenshot::from_window(render_handler)
+                .save_png(&path)
+                .map(|_| Some(path)),
+            None => Ok(None),
+        }
+    }
+
+    /// Counts a rendered frame, capturing an offscreen framebuffer if it is
+    /// due. Returns the path of the file that was written, if any.
+    pub fn capture_framebuffer<RHType: RenderHandler>(
+        &mut self,
+        framebuffer: &mut Framebuffer<RHType>,
+    ) -> Result<Option<PathBuf>, CaptureError> {
+        match self.next_frame() {
+            Some(path) => Screenshot::from_framebuffer(framebuffer)
+                .save_png(&path)
+                .map(|_| Some(path)),
+            None => Ok(None),
+        }
+    }
+
+    /// Counts a rendered frame, returning the path to save it to if it should
+    /// be captured.
+    // `is_multiple_of` is too new to rely on
+    #[allow(clippy::manual_is_multiple_of)]
+    pub fn next_frame(&mut self) -> Option<PathBuf> {
+        let frame = self.frame;
+        self.frame += 1;
+        if frame % self.interval as u64 != 0 {
+            return None;
+        }
+
+        let path = self
+            .directory
+            .join(format!("{}_{:06}.png", self.prefix, self.saved));
+        self.saved += 1;
+        Some(path)
+    }
+
+    /// The number of frames that have been counted.
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// The number of frames that have been saved.
+    pub fn saved(&self) -> u32 {
+        self.saved
+    }
+}
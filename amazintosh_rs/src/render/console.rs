@@ -0,0 +1,91 @@
+use crate::logging::LogHistory;
+use crate::render::mesh::Mesh;
+use crate::render::text::{GlyphCache, TextError, TextOptions, TextRenderer, TextVertex};
+use crate::render::RenderHandler;
+use nalgebra::Vector2;
+
+/// An overlay that shows the most recent warnings and errors from a log
+/// history in the top left corner of the screen, so problems such as shaders
+/// failing to reload are noticed without watching the terminal.
+pub struct LogConsole<RHType: RenderHandler> {
+    history: LogHistory,
+    lines: usize,
+    color: [u8; 4],
+    mesh: Mesh<RHType, TextVertex, u32>,
+    /// The generation of the history and the screen width the mesh was last
+    /// built for.
+    built_for: Option<(u64, usize)>,
+    visible: bool,
+}
+
+impl<RHType: RenderHandler> LogConsole<RHType> {
+    /// Creates a console showing up to `lines` of the most recent messages.
+    pub fn new(render_handler: &mut RHType, history: LogHistory, lines: usize) -> Self {
+        Self {
+            history,
+            lines,
+            color: [255, 220, 100, 255],
+            mesh: Mesh::new(render_handler),
+            built_for: None,
+            visible: true,
+        }
+    }
+
+    pub fn with_color(mut self, color: [u8; 4]) -> Self {
+        self.color = color;
+        self.built_for = None;
+        self
+    }
+
+    pub fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// The text the console shows, with one message per line and the newest
+    /// message last.
+    pub fn text(&self) -> String {
+        self.history
+            .recent(self.lines)
+            .iter()
+            .map(|entry| format!("{} {}: {}", entry.level, entry.target, entry.message))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Draws the console, rebuilding its text if anything has been logged
+    /// since it was last drawn.
+    pub fn render(
+        &mut self,
+        glyph_cache: &mut GlyphCache<'_, RHType>,
+        text_renderer: &mut TextRenderer<RHType>,
+        screen_size: (usize, usize),
+    ) -> Result<(), TextError> {
+        if !self.visible {
+            return Ok(());
+        }
+
+        const MARGIN: f32 = 8.0;
+        let built_for = Some((self.history.generation(), screen_size.0));
+        if self.built_for != built_for {
+            let options = TextOptions {
+                max_width: Some((screen_size.0 as f32 - MARGIN * 2.0).max(0.0)),
+                ..TextOptions::default()
+            };
+            let text = self.text();
+            glyph_cache.update_text_mesh(&mut self.mesh, &text, &options, self.color)?;
+            self.built_for = built_for;
+        }
+
+        text_renderer.render(
+            glyph_cache,
+            &mut self.mesh,
+            Vector2::new(MARGIN, MARGIN),
+            screen_size,
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,62 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+// Handler for finding out whether rendering calls have failed
+pub trait DebugHandler: Clone {
+    /// Takes every error raised since this was last called, oldest first.
+    /// Errors are only collected in debug builds, since checking for them can
+    /// stall the driver.
+    fn take_errors(&mut self) -> Vec<GlError>;
+
+    /// Returns the first error raised since errors were last checked. Any
+    /// later errors are logged instead, since they are usually caused by the
+    /// first one.
+    fn check_errors(&mut self) -> Result<(), GlError> {
+        let mut errors = self.take_errors().into_iter();
+        match errors.next() {
+            Some(first) => {
+                for error in errors {
+                    log::error!("Another OpenGL error was raised: {}", error);
+                }
+                Err(first)
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+/// An error raised by OpenGL, either as an error code from `glGetError` or
+/// as a message from the driver's debug output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GlError {
+    InvalidEnum,
+    InvalidValue,
+    InvalidOperation,
+    InvalidFramebufferOperation,
+    OutOfMemory,
+    /// An error code that isn't part of OpenGL 3.3.
+    Unknown(u32),
+    /// An error reported through the driver's debug output, which usually
+    /// explains what went wrong better than an error code.
+    DebugMessage(String),
+}
+
+impl Display for GlError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidEnum => write!(f, "an invalid enum was passed to OpenGL"),
+            Self::InvalidValue => write!(f, "an invalid value was passed to OpenGL"),
+            Self::InvalidOperation => {
+                write!(f, "an OpenGL call isn't allowed in the current state")
+            }
+            Self::InvalidFramebufferOperation => {
+                write!(f, "the bound framebuffer is incomplete")
+            }
+            Self::OutOfMemory => write!(f, "OpenGL ran out of memory"),
+            Self::Unknown(code) => write!(f, "unknown OpenGL error 0x{:x}", code),
+            Self::DebugMessage(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl Error for GlError {}
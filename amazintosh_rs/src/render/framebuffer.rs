@@ -0,0 +1,425 @@
+use crate::render::leak::{self, ResourceKind};
+use crate::render::texture::{Texture2D, TextureFormat, TextureOptions};
+use crate::render::RenderHandler;
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+use std::ops::{Deref, DerefMut};
+
+// Handler for creating and managing framebuffers and renderbuffers
+pub trait FramebufferHandler: Clone {
+    fn gen_framebuffer(&mut self) -> u32;
+
+    /// Binds a framebuffer as the target for drawing and reading. Binding 0
+    /// binds the window's default framebuffer.
+    fn bind_framebuffer(&mut self, handle: u32);
+
+    /// Attaches a 2D texture to the bound framebuffer.
+    fn framebuffer_texture(&mut self, attachment: FramebufferAttachment, texture: u32);
+
+    /// Attaches a renderbuffer to the bound framebuffer.
+    fn framebuffer_renderbuffer(&mut self, attachment: FramebufferAttachment, renderbuffer: u32);
+
+    /// Sets which color attachments of the bound framebuffer are drawn to,
+    /// with the first fragment shader output going to the first attachment
+    /// and so on. Nothing is drawn if no attachments are provided.
+    fn draw_buffers(&mut self, attachments: &[FramebufferAttachment]);
+
+    /// Gets the framebuffer that is bound, which is 0 for the window's.
+    fn bound_framebuffer(&self) -> u32;
+
+    /// Checks whether the bound framebuffer can be drawn to.
+    fn check_framebuffer_status(&mut self) -> Result<(), FramebufferError>;
+
+    fn delete_framebuffer(&mut self, handle: u32);
+
+    fn gen_renderbuffer(&mut self) -> u32;
+
+    fn bind_renderbuffer(&mut self, handle: u32);
+
+    /// Allocates storage for the bound renderbuffer.
+    fn renderbuffer_storage(&mut self, format: TextureFormat, width: u32, height: u32);
+
+    fn delete_renderbuffer(&mut self, handle: u32);
+
+    fn viewport(&mut self, x: i32, y: i32, width: u32, height: u32);
+
+    /// Gets the viewport as `(x, y, width, height)`.
+    fn current_viewport(&self) -> (i32, i32, u32, u32);
+
+    /// Reads a region of the bound framebuffer's first color attachment (or
+    /// the window, if 0 is bound) into tightly packed pixel data. Rows are
+    /// returned bottom to top, as OpenGL stores them.
+    fn read_pixels(
+        &mut self,
+        format: TextureFormat,
+        offset: (u32, u32),
+        size: (u32, u32),
+    ) -> Vec<u8>;
+
+    /// Gets the size of the window's default framebuffer in pixels.
+    fn default_framebuffer_size(&self) -> (u32, u32);
+
+    /// Records the size of the window's default framebuffer, such as after
+    /// the window has been resized.
+    fn set_default_framebuffer_size(&mut self, size: (u32, u32));
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum FramebufferAttachment {
+    Color(u32),
+    Depth,
+    DepthStencil,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FramebufferError {
+    CreateFramebufferFailed,
+    Incomplete(String),
+}
+
+impl Display for FramebufferError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::CreateFramebufferFailed => write!(f, "failed to create a framebuffer"),
+            Self::Incomplete(reason) => write!(f, "framebuffer is incomplete: {}", reason),
+        }
+    }
+}
+
+impl Error for FramebufferError {}
+
+/// Determines the size of a framebuffer's attachments.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FramebufferSize {
+    Fixed(u32, u32),
+    /// Follows the size of the window, scaled by some amount, such as 0.5 to
+    /// render at half resolution.
+    Window(f32),
+}
+
+impl FramebufferSize {
+    fn resolve(self, window_size: (u32, u32)) -> (u32, u32) {
+        match self {
+            Self::Fixed(width, height) => (width, height),
+            Self::Window(scale) => (
+                ((window_size.0 as f32 * scale) as u32).max(1),
+                ((window_size.1 as f32 * scale) as u32).max(1),
+            ),
+        }
+    }
+}
+
+/// Describes how the contents of an attachment are stored.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum AttachmentStorage {
+    /// Stored in a texture so it can be sampled after rendering.
+    Texture(TextureFormat, TextureOptions),
+    /// Stored in a renderbuffer, which can't be sampled but may be faster.
+    Renderbuffer(TextureFormat),
+}
+
+/// A renderbuffer that is deleted when dropped.
+pub struct Renderbuffer<RHType: RenderHandler> {
+    render_handler: RHType,
+    handle: u32,
+    format: TextureFormat,
+    size: (u32, u32),
+}
+
+impl<RHType: RenderHandler> Renderbuffer<RHType> {
+    pub fn new(
+        render_handler: &mut RHType,
+        format: TextureFormat,
+        width: u32,
+        height: u32,
+    ) -> Self {
+        let handle = render_handler.gen_renderbuffer();
+        leak::track(ResourceKind::Renderbuffer, handle);
+
+        let mut renderbuffer = Self {
+            handle,
+            render_handler: render_handler.clone(),
+            format,
+            size: (0, 0),
+        };
+        renderbuffer.resize(width, height);
+        renderbuffer
+    }
+
+    /// Reallocates the storage of this renderbuffer, discarding its contents.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.size = (width, height);
+        self.render_handler.bind_renderbuffer(self.handle);
+        self.render_handler
+            .renderbuffer_storage(self.format, width, height);
+    }
+
+    pub fn handle(&self) -> u32 {
+        self.handle
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        self.size
+    }
+}
+
+impl<RHType: RenderHandler> Drop for Renderbuffer<RHType> {
+    fn drop(&mut self) {
+        log::debug!("Dropping renderbuffer {}", self.handle);
+
+        self.render_handler.delete_renderbuffer(self.handle);
+        leak::untrack(ResourceKind::Renderbuffer, self.handle);
+    }
+}
+
+/// The storage of a single framebuffer attachment.
+pub enum Attachment<RHType: RenderHandler> {
+    Texture(Texture2D<RHType>),
+    Renderbuffer(Renderbuffer<RHType>),
+}
+
+impl<RHType: RenderHandler> Attachment<RHType> {
+    fn new(render_handler: &mut RHType, storage: AttachmentStorage, size: (u32, u32)) -> Self {
+        match storage {
+            AttachmentStorage::Texture(format, options) => Self::Texture(Texture2D::new(
+                render_handler,
+                format,
+                size.0,
+                size.1,
+                options,
+            )),
+            AttachmentStorage::Renderbuffer(format) => {
+                Self::Renderbuffer(Renderbuffer::new(render_handler, format, size.0, size.1))
+            }
+        }
+    }
+
+    fn attach(&self, render_handler: &mut RHType, attachment: FramebufferAttachment) {
+        match self {
+            Self::Texture(texture) => {
+                render_handler.framebuffer_texture(attachment, texture.handle())
+            }
+            Self::Renderbuffer(renderbuffer) => {
+                render_handler.framebuffer_renderbuffer(attachment, renderbuffer.handle())
+            }
+        }
+    }
+
+    fn resize(&mut self, size: (u32, u32)) {
+        match self {
+            Self::Texture(texture) => texture.resize(size.0, size.1),
+            Self::Renderbuffer(renderbuffer) => renderbuffer.resize(size.0, size.1),
+        }
+    }
+
+    pub fn texture(&self) -> Option<&Texture2D<RHType>> {
+        match self {
+            Self::Texture(texture) => Some(texture),
+            Self::Renderbuffer(_) => None,
+        }
+    }
+
+    pub fn texture_mut(&mut self) -> Option<&mut Texture2D<RHType>> {
+        match self {
+            Self::Texture(texture) => Some(texture),
+            Self::Renderbuffer(_) => None,
+        }
+    }
+}
+
+/// An offscreen render target that is deleted, along with its attachments,
+/// when dropped.
+pub struct Framebuffer<RHType: RenderHandler> {
+    render_handler: RHType,
+    handle: u32,
+    size_mode: FramebufferSize,
+    size: (u32, u32),
+    color: Vec<Attachment<RHType>>,
+    depth: Option<(FramebufferAttachment, Attachment<RHType>)>,
+}
+
+impl<RHType: RenderHandler> Framebuffer<RHType> {
+    /// Creates a framebuffer with a color attachment for each provided
+    /// storage and an optional depth (or depth and stencil) attachment.
+    pub fn new(
+        render_handler: &mut RHType,
+        size_mode: FramebufferSize,
+        color: &[AttachmentStorage],
+        depth: Option<AttachmentStorage>,
+    ) -> Result<Self, FramebufferError> {
+        let size = size_mode.resolve(render_handler.default_framebuffer_size());
+
+        let handle = render_handler.gen_framebuffer();
+        if handle == 0 {
+            return Err(FramebufferError::CreateFramebufferFailed);
+        }
+        leak::track(ResourceKind::Framebuffer, handle);
+
+        let framebuffer = Self {
+            render_handler: render_handler.clone(),
+            handle,
+            size_mode,
+            size,
+            color: color
+                .iter()
+                .map(|storage| Attachment::new(render_handler, *storage, size))
+                .collect(),
+            depth: depth.map(|storage| {
+                let attachment = match storage {
+                    AttachmentStorage::Texture(TextureFormat::Depth24Stencil8, _)
+                    | AttachmentStorage::Renderbuffer(TextureFormat::Depth24Stencil8) => {
+                        FramebufferAttachment::DepthStencil
+                    }
+                    _ => FramebufferAttachment::Depth,
+                };
+                (attachment, Attachment::new(render_handler, storage, size))
+            }),
+        };
+
+        // Attach everything and make sure the result can be drawn to
+        let previous = render_handler.bound_framebuffer();
+        render_handler.bind_framebuffer(framebuffer.handle);
+        let color_attachments: Vec<FramebufferAttachment> = (0..framebuffer.color.len())
+            .map(|index| FramebufferAttachment::Color(index as u32))
+            .collect();
+        for (attachment, kind) in framebuffer.color.iter().zip(&color_attachments) {
+            attachment.attach(render_handler, *kind);
+        }
+        // Only the first color attachment is drawn to unless told otherwise
+        render_handler.draw_buffers(&color_attachments);
+        if let Some((kind, attachment)) = &framebuffer.depth {
+            attachment.attach(render_handler, *kind);
+        }
+        let status = render_handler.check_framebuffer_status();
+        render_handler.bind_framebuffer(previous);
+        status?;
+
+        Ok(framebuffer)
+    }
+
+    /// Binds this framebuffer for drawing until the returned binding is
+    /// dropped, at which point whatever framebuffer and viewport were in use
+    /// before are restored, so bindings can be nested. Framebuffers that follow the window's size are resized first if the
+    /// window has changed size since they were last bound.
+    pub fn bind(&mut self) -> FramebufferBinding<'_, RHType> {
+        let window_size = self.render_handler.default_framebuffer_size();
+        let size = self.size_mode.resolve(window_size);
+        if size != self.size {
+            self.resize_attachments(size);
+        }
+
+        let previous_framebuffer = self.render_handler.bound_framebuffer();
+        let previous_viewport = self.render_handler.current_viewport();
+        self.render_handler.bind_framebuffer(self.handle);
+        self.render_handler.viewport(0, 0, self.size.0, self.size.1);
+
+        FramebufferBinding {
+            framebuffer: self,
+            previous_framebuffer,
+            previous_viewport,
+        }
+    }
+
+    /// Changes how this framebuffer is sized, resizing its attachments if
+    /// needed.
+    pub fn set_size(&mut self, size_mode: FramebufferSize) {
+        self.size_mode = size_mode;
+
+        let size = size_mode.resolve(self.render_handler.default_framebuffer_size());
+        if size != self.size {
+            self.resize_attachments(size);
+        }
+    }
+
+    fn resize_attachments(&mut self, size: (u32, u32)) {
+        self.size = size;
+        for attachment in &mut self.color {
+            attachment.resize(size);
+        }
+        if let Some((_, attachment)) = &mut self.depth {
+            attachment.resize(size);
+        }
+    }
+
+    /// Reads a region of the first color attachment into tightly packed
+    /// pixel data, with rows stored bottom to top.
+    pub fn read_pixels(
+        &mut self,
+        format: TextureFormat,
+        offset: (u32, u32),
+        size: (u32, u32),
+    ) -> Vec<u8> {
+        let mut binding = self.bind();
+        binding.render_handler.read_pixels(format, offset, size)
+    }
+
+    pub fn handle(&self) -> u32 {
+        self.handle
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    pub fn color_attachment(&self, index: usize) -> Option<&Attachment<RHType>> {
+        self.color.get(index)
+    }
+
+    pub fn color_attachment_mut(&mut self, index: usize) -> Option<&mut Attachment<RHType>> {
+        self.color.get_mut(index)
+    }
+
+    /// Gets the texture of a color attachment if it is stored in a texture.
+    pub fn color_texture(&self, index: usize) -> Option<&Texture2D<RHType>> {
+        self.color.get(index).and_then(Attachment::texture)
+    }
+
+    pub fn color_texture_mut(&mut self, index: usize) -> Option<&mut Texture2D<RHType>> {
+        self.color.get_mut(index).and_then(Attachment::texture_mut)
+    }
+
+    pub fn depth_attachment(&self) -> Option<&Attachment<RHType>> {
+        self.depth.as_ref().map(|(_, attachment)| attachment)
+    }
+}
+
+impl<RHType: RenderHandler> Drop for Framebuffer<RHType> {
+    fn drop(&mut self) {
+        log::debug!("Dropping framebuffer {}", self.handle);
+
+        self.render_handler.delete_framebuffer(self.handle);
+        leak::untrack(ResourceKind::Framebuffer, self.handle);
+    }
+}
+
+/// Keeps a framebuffer bound for drawing while it is alive.
+pub struct FramebufferBinding<'a, RHType: RenderHandler> {
+    framebuffer: &'a mut Framebuffer<RHType>,
+    /// What to restore when the binding is dropped.
+    previous_framebuffer: u32,
+    previous_viewport: (i32, i32, u32, u32),
+}
+
+impl<'a, RHType: RenderHandler> Deref for FramebufferBinding<'a, RHType> {
+    type Target = Framebuffer<RHType>;
+
+    fn deref(&self) -> &Self::Target {
+        self.framebuffer
+    }
+}
+
+impl<'a, RHType: RenderHandler> DerefMut for FramebufferBinding<'a, RHType> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.framebuffer
+    }
+}
+
+impl<'a, RHType: RenderHandler> Drop for FramebufferBinding<'a, RHType> {
+    fn drop(&mut self) {
+        let render_handler = &mut self.framebuffer.render_handler;
+        let (x, y, width, height) = self.previous_viewport;
+
+        render_handler.bind_framebuffer(self.previous_framebuffer);
+        render_handler.viewport(x, y, width, height);
+    }
+}
@@ -0,0 +1,62 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+/// The kinds of OpenGL objects the engine's wrappers own.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ResourceKind {
+    Buffer,
+    VertexArray,
+    Shader,
+    ShaderProgram,
+    Texture,
+    Framebuffer,
+    Renderbuffer,
+}
+
+thread_local! {
+    // OpenGL contexts belong to a single thread, so their objects are tracked
+    // per thread. Handles are counted because separate contexts, such as the
+    // mock handlers in tests, can reuse the same handle.
+    static LIVE_RESOURCES: RefCell<BTreeMap<(ResourceKind, u32), usize>> =
+        const { RefCell::new(BTreeMap::new()) };
+}
+
+/// Records that a wrapper has created an object.
+pub fn track(kind: ResourceKind, handle: u32) {
+    LIVE_RESOURCES.with(|live| *live.borrow_mut().entry((kind, handle)).or_insert(0) += 1);
+}
+
+/// Records that a wrapper has deleted an object.
+pub fn untrack(kind: ResourceKind, handle: u32) {
+    LIVE_RESOURCES.with(|live| {
+        let mut live = live.borrow_mut();
+        if let Some(count) = live.get_mut(&(kind, handle)) {
+            *count -= 1;
+            if *count == 0 {
+                live.remove(&(kind, handle));
+            }
+        }
+    });
+}
+
+/// The objects created on this thread that haven't been deleted, sorted by
+/// kind and then handle.
+pub fn live_resources() -> Vec<(ResourceKind, u32)> {
+    LIVE_RESOURCES.with(|live| {
+        live.borrow()
+            .iter()
+            .flat_map(|(&resource, &count)| std::iter::repeat_n(resource, count))
+            .collect()
+    })
+}
+
+/// Logs a warning for every object that is still alive, returning how many
+/// there are. This should be called once everything that renders has been
+/// dropped, such as at shutdown, where anything still alive has leaked.
+pub fn report_leaks() -> usize {
+    let live = live_resources();
+    for (kind, handle) in &live {
+        log::warn!("Leaked {:?} {}", kind, handle);
+    }
+    live.len()
+}
@@ -1,13 +1,26 @@
-use super::buffer::{Buffer, BufferType, BufferUsage};
-use super::vertex::Vertex;
+use super::buffer::{Buffer, BufferError, BufferType, BufferUsage};
+use super::vertex::{InstanceData, Vertex};
+use crate::render::leak::{self, ResourceKind};
 use crate::render::vertex::VertexAttribPointer;
-use crate::render::{GlDataType, RenderHandler};
+use crate::render::{GlIndexType, RenderHandler};
 use std::error::Error;
-use std::fmt::{Debug, Display, Formatter};
+use std::fmt::{Display, Formatter};
 
+/// The primitive that vertices are assembled into when drawn.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum MeshMode {
+    Points,
+    /// Every pair of vertices is a separate line.
+    Lines,
+    /// Each vertex continues the line from the previous one.
+    LineStrip,
+    /// A line strip that is closed back to its first vertex.
+    LineLoop,
     Triangles,
+    /// Each vertex forms a triangle with the two before it.
+    TriangleStrip,
+    /// Each vertex forms a triangle with the one before it and the first.
+    TriangleFan,
 }
 
 pub trait MeshHandler: Clone {
@@ -15,7 +28,13 @@ pub trait MeshHandler: Clone {
 
     fn delete_vertex_array(&mut self, handle: u32);
 
-    fn vertex_attrib_pointer<VertexType: Vertex>(&mut self, pointer: &VertexAttribPointer);
+    /// Describes an attribute stored in the bound array buffer, which holds
+    /// tightly packed values of `DataType`, such as vertices or instance data.
+    fn vertex_attrib_pointer<DataType: Sized>(&mut self, pointer: &VertexAttribPointer);
+
+    /// Sets how many instances are drawn before an attribute advances to its
+    /// next value, with 0 advancing it every vertex instead.
+    fn vertex_attrib_divisor(&mut self, index: usize, divisor: u32);
 
     fn enable_attrib_array(&mut self, index: usize);
 
@@ -23,50 +42,96 @@ pub trait MeshHandler: Clone {
 
     fn bind_vertex_array(&mut self, handle: u32);
 
-    fn draw_elements<IndexType: GlDataType>(&mut self, mode: MeshMode, indices: usize);
+    fn draw_elements<IndexType: GlIndexType>(&mut self, mode: MeshMode, indices: usize);
+
+    /// Draws vertices from the bound vertex array in order, without indices.
+    fn draw_arrays(&mut self, mode: MeshMode, first: usize, count: usize);
+
+    /// Draws the bound elements once for each instance.
+    fn draw_elements_instanced<IndexType: GlIndexType>(
+        &mut self,
+        mode: MeshMode,
+        indices: usize,
+        instances: usize,
+    );
+
+    /// Draws vertices in order once for each instance.
+    fn draw_arrays_instanced(
+        &mut self,
+        mode: MeshMode,
+        first: usize,
+        count: usize,
+        instances: usize,
+    );
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum MeshError {
-    FailedToUpdateData,
-    FailedToBufferData,
-    FailedToSetupAttribs,
+    /// Part of the vertices, indices or instances couldn't be replaced.
+    FailedToUpdateData(BufferError),
 }
 
 impl Display for MeshError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        Debug::fmt(self, f)
+        match self {
+            Self::FailedToUpdateData(_) => write!(f, "failed to update mesh data"),
+        }
     }
 }
 
-impl Error for MeshError {}
+impl Error for MeshError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::FailedToUpdateData(error) => Some(error),
+        }
+    }
+}
 
-pub struct Mesh<RHType: RenderHandler, VertexType: Vertex, IndexType: GlDataType> {
+impl From<BufferError> for MeshError {
+    fn from(error: BufferError) -> Self {
+        Self::FailedToUpdateData(error)
+    }
+}
+
+pub struct Mesh<RHType: RenderHandler, VertexType: Vertex, IndexType: GlIndexType> {
     render_handler: RHType,
     vao: u32,
     vbo: Buffer<RHType, VertexType>,
     ebo: Buffer<RHType, IndexType>,
     elements: usize,
+    /// Whether indices have been set, after which the mesh is always drawn
+    /// with them, even if there are none.
+    indexed: bool,
+    mode: MeshMode,
 }
 
-impl<RHType: RenderHandler, VertexType: Vertex, IndexType: GlDataType>
+impl<RHType: RenderHandler, VertexType: Vertex, IndexType: GlIndexType>
     Mesh<RHType, VertexType, IndexType>
 {
     pub fn new(render_handler: &mut RHType) -> Self {
+        let vao = render_handler.gen_vertex_array();
+        leak::track(ResourceKind::VertexArray, vao);
+
         let mut mesh = Self {
-            vao: render_handler.gen_vertex_array(),
+            vao,
             vbo: Buffer::new(render_handler, BufferType::ArrayBuffer),
             ebo: Buffer::new(render_handler, BufferType::ElementArrayBuffer),
             render_handler: render_handler.clone(),
             elements: 0,
+            indexed: false,
+            mode: MeshMode::Triangles,
         };
 
         mesh.bind();
         mesh.vbo.bind();
 
-        VertexType::attrib_pointers()
-            .iter()
-            .for_each(|ptr| mesh.render_handler.vertex_attrib_pointer::<VertexType>(ptr));
+        // The vertex array remembers which attributes are enabled, so they
+        // only need to be enabled once
+        for ptr in VertexType::attrib_pointers() {
+            mesh.render_handler
+                .vertex_attrib_pointer::<VertexType>(&ptr);
+            mesh.render_handler.enable_attrib_array(ptr.index);
+        }
 
         mesh
     }
@@ -85,18 +150,77 @@ impl<RHType: RenderHandler, VertexType: Vertex, IndexType: GlDataType>
         self.ebo.buffer_data(indices, usage);
 
         self.elements = element_count;
+        self.indexed = true;
     }
 
+    /// Replaces some vertices, starting at the vertex at `offset`, without
+    /// reallocating the vertex buffer.
+    pub fn update_vertices(
+        &mut self,
+        offset: usize,
+        vertices: &[VertexType],
+    ) -> Result<(), MeshError> {
+        self.bind();
+
+        Ok(self.vbo.update(offset, vertices)?)
+    }
+
+    /// Replaces some indices, starting at the index at `offset`, without
+    /// changing how many are drawn.
+    pub fn update_indices(
+        &mut self,
+        offset: usize,
+        indices: &[IndexType],
+    ) -> Result<(), MeshError> {
+        // The element buffer can only be bound with its vertex array
+        self.bind();
+
+        Ok(self.ebo.update(offset, indices)?)
+    }
+
+    /// Sets the primitive the vertices are drawn as. Meshes draw triangles
+    /// by default.
+    pub fn set_mode(&mut self, mode: MeshMode) {
+        self.mode = mode;
+    }
+
+    pub fn mode(&self) -> MeshMode {
+        self.mode
+    }
+
+    /// The number of vertices that were most recently set.
+    pub fn vertex_count(&self) -> usize {
+        self.vbo.len()
+    }
+
+    /// The number of vertices or indices that are drawn.
+    fn draw_count(&self) -> usize {
+        if self.indexed {
+            self.elements
+        } else {
+            self.vbo.len()
+        }
+    }
+
+    /// Draws the mesh. Meshes that have been given indices are drawn with
+    /// them, so nothing is drawn while they are empty, otherwise every
+    /// vertex is drawn in order.
     pub fn render(&mut self) {
         // No need to try to render
-        if self.elements < 1 {
+        if self.draw_count() < 1 {
             return;
         }
 
+        // The element buffer is bound as part of the vertex array
         self.bind();
-        self.ebo.bind();
 
-        VertexType::render(&mut self.render_handler, self.elements);
+        if self.indexed {
+            self.render_handler
+                .draw_elements::<IndexType>(self.mode, self.elements);
+        } else {
+            self.render_handler
+                .draw_arrays(self.mode, 0, self.vbo.len());
+        }
     }
 
     pub fn bind(&mut self) {
@@ -104,12 +228,121 @@ impl<RHType: RenderHandler, VertexType: Vertex, IndexType: GlDataType>
     }
 }
 
-impl<RHType: RenderHandler, VertexType: Vertex, IndexType: GlDataType> Drop
+impl<RHType: RenderHandler, VertexType: Vertex, IndexType: GlIndexType> Drop
     for Mesh<RHType, VertexType, IndexType>
 {
     fn drop(&mut self) {
-        println!("Dropped VAO: {}", self.vao);
+        log::debug!("Dropping vertex array {}", self.vao);
+
+        self.render_handler.delete_vertex_array(self.vao);
+        leak::untrack(ResourceKind::VertexArray, self.vao);
+    }
+}
+
+/// A mesh that is drawn many times in one draw call, with per-instance data,
+/// such as a model matrix, stored in a separate buffer.
+pub struct InstancedMesh<
+    RHType: RenderHandler,
+    VertexType: Vertex,
+    InstanceType: InstanceData,
+    IndexType: GlIndexType,
+> {
+    mesh: Mesh<RHType, VertexType, IndexType>,
+    instance_buffer: Buffer<RHType, InstanceType>,
+}
+
+impl<
+        RHType: RenderHandler,
+        VertexType: Vertex,
+        InstanceType: InstanceData,
+        IndexType: GlIndexType,
+    > InstancedMesh<RHType, VertexType, InstanceType, IndexType>
+{
+    pub fn new(render_handler: &mut RHType) -> Self {
+        let mut mesh = Mesh::new(render_handler);
+        let mut instance_buffer = Buffer::new(render_handler, BufferType::ArrayBuffer);
+
+        // Instance attributes come after every location the vertices use
+        let first_location = VertexType::attrib_pointers()
+            .iter()
+            .map(|ptr| ptr.index + 1)
+            .max()
+            .unwrap_or(0);
+
+        mesh.bind();
+        instance_buffer.bind();
+        for ptr in InstanceType::attrib_pointers(first_location) {
+            mesh.render_handler
+                .vertex_attrib_pointer::<InstanceType>(&ptr);
+            mesh.render_handler.vertex_attrib_divisor(ptr.index, 1);
+            mesh.render_handler.enable_attrib_array(ptr.index);
+        }
+
+        Self {
+            mesh,
+            instance_buffer,
+        }
+    }
+
+    pub fn set_vertices(&mut self, vertices: Vec<VertexType>, usage: BufferUsage) {
+        self.mesh.set_vertices(vertices, usage);
+    }
+
+    pub fn set_indices(&mut self, indices: Vec<IndexType>, usage: BufferUsage) {
+        self.mesh.set_indices(indices, usage);
+    }
+
+    /// Replaces the data of every instance, which also sets how many
+    /// instances are drawn.
+    pub fn set_instances(&mut self, instances: Vec<InstanceType>, usage: BufferUsage) {
+        self.instance_buffer.buffer_data(instances, usage);
+    }
+
+    /// Replaces the data of some instances, starting at the instance at
+    /// `offset`.
+    pub fn update_instances(
+        &mut self,
+        offset: usize,
+        instances: &[InstanceType],
+    ) -> Result<(), MeshError> {
+        Ok(self.instance_buffer.update(offset, instances)?)
+    }
+
+    pub fn instance_count(&self) -> usize {
+        self.instance_buffer.len()
+    }
+
+    pub fn set_mode(&mut self, mode: MeshMode) {
+        self.mesh.set_mode(mode);
+    }
+
+    /// Draws every instance of the mesh in a single draw call.
+    pub fn render(&mut self) {
+        // No need to try to render
+        let mesh = &mut self.mesh;
+        let instances = self.instance_buffer.len();
+        if mesh.draw_count() < 1 || instances < 1 {
+            return;
+        }
+
+        mesh.bind();
+        if mesh.indexed {
+            mesh.render_handler.draw_elements_instanced::<IndexType>(
+                mesh.mode,
+                mesh.elements,
+                instances,
+            );
+        } else {
+            mesh.render_handler
+                .draw_arrays_instanced(mesh.mode, 0, mesh.vbo.len(), instances);
+        }
+    }
+
+    pub fn mesh(&self) -> &Mesh<RHType, VertexType, IndexType> {
+        &self.mesh
+    }
 
-        self.render_handler.delete_vertex_array(self.vao)
+    pub fn mesh_mut(&mut self) -> &mut Mesh<RHType, VertexType, IndexType> {
+        &mut self.mesh
     }
 }
@@ -0,0 +1,1281 @@
+use super::buffer::{BufferHandler, BufferType, BufferUsage};
+use super::debug::{DebugHandler, GlError};
+use super::framebuffer::{FramebufferAttachment, FramebufferError, FramebufferHandler};
+use super::inner_gl::types::GLenum;
+use super::mesh::{MeshHandler, MeshMode};
+use super::shader::{
+    GlslType, ShaderError, ShaderHandler, ShaderType, ShaderUniformBlock, ShaderVariable,
+};
+use super::texture::{TextureFilter, TextureFormat, TextureHandler, TextureWrap};
+use super::uniform_block::UniformBindings;
+use super::vertex::VertexAttribPointer;
+use super::{GlIndexType, RenderHandler};
+use nalgebra::{Matrix2, Matrix3, Matrix4, Vector2, Vector3, Vector4};
+use std::cell::{Ref, RefCell};
+use std::collections::{BTreeSet, HashMap};
+use std::rc::Rc;
+
+/// A buffer that has been generated by the mock render handler.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockBuffer {
+    /// The type the buffer was last bound as, if it has been bound.
+    pub buffer_type: Option<BufferType>,
+    /// The usage hint passed with the most recent upload.
+    pub usage: Option<BufferUsage>,
+    /// The raw bytes currently stored in the buffer.
+    pub data: Vec<u8>,
+    /// The number of times storage has been allocated for the buffer.
+    pub allocations: usize,
+}
+
+/// An attribute pointer recorded inside of a vertex array.
+#[derive(Debug, Clone)]
+pub struct MockAttribPointer {
+    pub pointer: VertexAttribPointer,
+    pub stride: usize,
+    /// The array buffer that was bound when the pointer was set.
+    pub buffer: u32,
+}
+
+/// A vertex array that has been generated by the mock render handler.
+#[derive(Debug, Clone, Default)]
+pub struct MockVertexArray {
+    pub attrib_pointers: Vec<MockAttribPointer>,
+    pub enabled_attribs: BTreeSet<usize>,
+    /// The divisor of each attribute that advances per instance.
+    pub divisors: HashMap<usize, u32>,
+    pub element_buffer: Option<u32>,
+}
+
+/// A single draw call that would have been sent to the GPU.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockDrawCall {
+    pub mode: MeshMode,
+    /// The first vertex drawn, for draws without indices.
+    pub first: usize,
+    pub count: usize,
+    /// The type of the indices, or `None` if vertices were drawn in order.
+    pub index_type: Option<GLenum>,
+    pub vertex_array: u32,
+    pub element_buffer: Option<u32>,
+    /// The attribute arrays that were enabled at the time of the draw.
+    pub enabled_attribs: Vec<usize>,
+    /// The number of instances drawn, if the draw was instanced.
+    pub instances: Option<usize>,
+}
+
+/// A uniform value that was set through the mock render handler.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MockUniform {
+    Int(i32),
+    Float(f32),
+    Vec2(Vector2<f32>),
+    Vec3(Vector3<f32>),
+    Vec4(Vector4<f32>),
+    Mat2(Matrix2<f32>),
+    Mat3(Matrix3<f32>),
+    Mat4(Matrix4<f32>),
+    /// Values set through `uniform_iv`, with the number of components of
+    /// each element.
+    IntArray(usize, Vec<i32>),
+    UIntArray(usize, Vec<u32>),
+    FloatArray(usize, Vec<f32>),
+    /// Column-major matrices, with the number of columns of each matrix.
+    MatrixArray(usize, Vec<f32>),
+}
+
+/// A shader that has been created by the mock render handler.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockShader {
+    pub shader_type: ShaderType,
+    pub source: Option<String>,
+    pub compiled: bool,
+}
+
+/// A program that has been created by the mock render handler.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MockProgram {
+    pub attached_shaders: Vec<u32>,
+    pub linked: bool,
+    /// The uniforms declared in the linked sources, where each uniform's
+    /// location is its index.
+    pub uniforms: Vec<ShaderVariable>,
+    /// The inputs declared in the linked vertex shader, located by their
+    /// `layout(location = N)` qualifier or otherwise their index.
+    pub attributes: Vec<ShaderVariable>,
+    /// The uniform blocks declared in the linked sources, where each block's
+    /// index is its position.
+    pub uniform_blocks: Vec<ShaderUniformBlock>,
+    /// The binding point of each uniform block, by block index.
+    pub block_bindings: HashMap<u32, u32>,
+    /// The type and source of every shader the program was linked from.
+    pub linked_sources: Vec<(ShaderType, String)>,
+    pub binary_retrievable: bool,
+    /// Whether the program was loaded from a binary rather than linked.
+    pub from_binary: bool,
+}
+
+/// The format of the binaries the mock render handler creates.
+const MOCK_BINARY_FORMAT: u32 = 0x4d4f_434b;
+
+impl MockProgram {
+    /// Marks this program as linked from the provided sources, finding the
+    /// variables they declare.
+    fn link(&mut self, sources: Vec<(ShaderType, String)>) {
+        let mut uniforms: Vec<ShaderVariable> = Vec::new();
+        let mut attributes: Vec<ShaderVariable> = Vec::new();
+        let mut uniform_blocks: Vec<ShaderUniformBlock> = Vec::new();
+        for (shader_type, source) in &sources {
+            for (_, mut uniform) in declared_variables(source, "uniform") {
+                if !uniforms
+                    .iter()
+                    .any(|declared| declared.name == uniform.name)
+                {
+                    uniform.location = uniforms.len() as i32;
+                    uniforms.push(uniform);
+                }
+            }
+
+            for (name, size) in declared_blocks(source) {
+                if !uniform_blocks.iter().any(|declared| declared.name == name) {
+                    uniform_blocks.push(ShaderUniformBlock {
+                        name,
+                        index: uniform_blocks.len() as u32,
+                        size,
+                    });
+                }
+            }
+
+            if *shader_type == ShaderType::Vertex {
+                for (location, mut attribute) in declared_variables(source, "in") {
+                    attribute.location = location.unwrap_or(attributes.len() as i32);
+                    attributes.push(attribute);
+                }
+            }
+        }
+
+        self.linked = true;
+        self.uniforms = uniforms;
+        self.attributes = attributes;
+        self.uniform_blocks = uniform_blocks;
+        self.linked_sources = sources;
+    }
+}
+
+/// A texture that has been generated by the mock render handler.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockTexture {
+    pub format: Option<TextureFormat>,
+    pub size: (u32, u32),
+    /// The tightly packed pixels of the texture, or zeroes if it was never
+    /// filled.
+    pub data: Vec<u8>,
+    pub filter: Option<(TextureFilter, TextureFilter, Option<TextureFilter>)>,
+    pub wrap: Option<(TextureWrap, TextureWrap)>,
+    pub mipmaps_generated: bool,
+}
+
+/// What is stored in a framebuffer attachment.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MockAttachment {
+    Texture(u32),
+    Renderbuffer(u32),
+}
+
+/// A renderbuffer that has been generated by the mock render handler.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MockRenderbuffer {
+    pub format: Option<TextureFormat>,
+    pub size: (u32, u32),
+}
+
+/// Finds the names of the uniforms declared in some GLSL source, in order.
+/// Finds declarations such as `uniform mat4 view;` or
+/// `layout(location = 1) in vec2 uv;` that start with the provided keyword,
+/// returning each variable with the location from its layout qualifier.
+fn declared_variables<'a>(
+    source: &'a str,
+    keyword: &'a str,
+) -> impl Iterator<Item = (Option<i32>, ShaderVariable)> + 'a {
+    source.lines().filter_map(move |line| {
+        let line = line.trim();
+        let (location, line) = match line.strip_prefix("layout") {
+            Some(rest) => {
+                let end = rest.find(')')?;
+                let location = rest[..end]
+                    .split('=')
+                    .nth(1)
+                    .and_then(|location| location.trim().parse().ok());
+                (location, &rest[end + 1..])
+            }
+            None => (None, line),
+        };
+
+        // Uniform blocks are found by `declared_blocks` instead
+        if line.contains('{') {
+            return None;
+        }
+
+        let mut tokens = line.split_whitespace();
+        if tokens.next() != Some(keyword) {
+            return None;
+        }
+        let glsl_type = parse_glsl_type(tokens.next()?);
+        let declaration = tokens.next()?.trim_end_matches(';');
+        let mut parts = declaration.split('[');
+        let name = parts.next()?.to_owned();
+        let size = parts
+            .next()
+            .and_then(|size| size.trim_end_matches(']').parse().ok())
+            .unwrap_or(1);
+
+        Some((
+            location,
+            ShaderVariable {
+                name,
+                location: -1,
+                glsl_type,
+                size,
+            },
+        ))
+    })
+}
+
+/// Finds uniform blocks such as `layout(std140) uniform Camera {`, returning
+/// the name of each block and the size of its members in the std140 layout.
+fn declared_blocks(source: &str) -> Vec<(String, usize)> {
+    let round_up = |value: usize, alignment: usize| value.div_ceil(alignment) * alignment;
+
+    let mut blocks = Vec::new();
+    let mut current: Option<(String, usize)> = None;
+    for line in source.lines().map(str::trim) {
+        match &mut current {
+            Some((name, size)) => {
+                if line.starts_with('}') {
+                    blocks.push((name.clone(), round_up(*size, 16)));
+                    current = None;
+                    continue;
+                }
+
+                let mut tokens = line.split_whitespace();
+                let (glsl_type, declaration) = match (tokens.next(), tokens.next()) {
+                    (Some(glsl_type), Some(declaration)) => (glsl_type, declaration),
+                    _ => continue,
+                };
+                let (alignment, member_size) = match glsl_type {
+                    "vec2" | "ivec2" | "uvec2" => (8, 8),
+                    "vec3" | "ivec3" | "uvec3" => (16, 12),
+                    "vec4" | "ivec4" | "uvec4" => (16, 16),
+                    "mat2" => (16, 32),
+                    "mat3" => (16, 48),
+                    "mat4" => (16, 64),
+                    _ => (4, 4),
+                };
+
+                // Array elements are each rounded up to 16 bytes
+                *size = match declaration.split('[').nth(1) {
+                    Some(count) => {
+                        let count: usize = count.trim_end_matches("];").parse().unwrap_or(1);
+                        round_up(*size, 16) + count * round_up(member_size, 16)
+                    }
+                    None => round_up(*size, alignment) + member_size,
+                };
+            }
+            None => {
+                let declaration = match line.find("uniform") {
+                    Some(start) if line.ends_with('{') => &line[start..],
+                    _ => continue,
+                };
+                if let Some(name) = declaration.split_whitespace().nth(1) {
+                    current = Some((name.trim_end_matches('{').to_owned(), 0));
+                }
+            }
+        }
+    }
+
+    blocks
+}
+
+fn parse_glsl_type(name: &str) -> GlslType {
+    match name {
+        "float" => GlslType::Float,
+        "vec2" => GlslType::Vec2,
+        "vec3" => GlslType::Vec3,
+        "vec4" => GlslType::Vec4,
+        "int" => GlslType::Int,
+        "ivec2" => GlslType::IVec2,
+        "ivec3" => GlslType::IVec3,
+        "ivec4" => GlslType::IVec4,
+        "uint" => GlslType::UInt,
+        "uvec2" => GlslType::UVec2,
+        "uvec3" => GlslType::UVec3,
+        "uvec4" => GlslType::UVec4,
+        "bool" => GlslType::Bool,
+        "bvec2" => GlslType::BVec2,
+        "bvec3" => GlslType::BVec3,
+        "bvec4" => GlslType::BVec4,
+        "mat2" => GlslType::Mat2,
+        "mat3" => GlslType::Mat3,
+        "mat4" => GlslType::Mat4,
+        "sampler2D" => GlslType::Sampler2D,
+        "sampler2DArray" => GlslType::Sampler2DArray,
+        "samplerCube" => GlslType::SamplerCube,
+        _ => GlslType::Other(0),
+    }
+}
+
+/// Everything the mock render handler has recorded so far.
+#[derive(Debug, Default)]
+pub struct MockState {
+    next_handle: u32,
+    pub buffers: HashMap<u32, MockBuffer>,
+    pub bound_buffers: HashMap<BufferType, u32>,
+    pub vertex_arrays: HashMap<u32, MockVertexArray>,
+    pub bound_vertex_array: u32,
+    pub shaders: HashMap<u32, MockShader>,
+    /// The number of times any shader has been compiled.
+    pub shader_compiles: usize,
+    pub programs: HashMap<u32, MockProgram>,
+    pub used_program: u32,
+    pub textures: HashMap<u32, MockTexture>,
+    pub active_texture_unit: u32,
+    /// The texture bound to each texture unit.
+    pub bound_textures: HashMap<u32, u32>,
+    pub framebuffers: HashMap<u32, HashMap<FramebufferAttachment, MockAttachment>>,
+    pub bound_framebuffer: u32,
+    /// The color attachments each framebuffer draws to.
+    pub draw_buffers: HashMap<u32, Vec<FramebufferAttachment>>,
+    pub renderbuffers: HashMap<u32, MockRenderbuffer>,
+    pub bound_renderbuffer: u32,
+    /// The most recently set viewport as `(x, y, width, height)`.
+    pub viewport: (i32, i32, u32, u32),
+    pub default_framebuffer_size: (u32, u32),
+    pub uniforms: HashMap<i32, MockUniform>,
+    /// The buffer bound to each indexed binding point, such as the binding
+    /// points of uniform blocks.
+    pub indexed_buffers: HashMap<(BufferType, u32), u32>,
+    pub uniform_bindings: UniformBindings,
+    pub draw_calls: Vec<MockDrawCall>,
+    /// Errors waiting to be taken by `take_errors`.
+    pub errors: Vec<GlError>,
+    /// The version reported in the driver's name, which can be changed to
+    /// simulate a driver update.
+    pub driver_version: u32,
+}
+
+impl MockState {
+    fn next_handle(&mut self) -> u32 {
+        // Zero is never a valid OpenGL object name
+        self.next_handle += 1;
+        self.next_handle
+    }
+
+    fn bound_vertex_array_mut(&mut self) -> &mut MockVertexArray {
+        let handle = self.bound_vertex_array;
+        self.vertex_arrays
+            .get_mut(&handle)
+            .unwrap_or_else(|| panic!("no valid vertex array is bound (bound: {})", handle))
+    }
+
+    fn bound_texture_mut(&mut self) -> &mut MockTexture {
+        let handle = self
+            .bound_textures
+            .get(&self.active_texture_unit)
+            .copied()
+            .unwrap_or(0);
+        self.textures
+            .get_mut(&handle)
+            .unwrap_or_else(|| panic!("no valid texture is bound (bound: {})", handle))
+    }
+
+    fn bound_framebuffer_mut(&mut self) -> &mut HashMap<FramebufferAttachment, MockAttachment> {
+        let handle = self.bound_framebuffer;
+        self.framebuffers
+            .get_mut(&handle)
+            .unwrap_or_else(|| panic!("no valid framebuffer is bound (bound: {})", handle))
+    }
+
+    fn record_draw(
+        &mut self,
+        mode: MeshMode,
+        first: usize,
+        count: usize,
+        index_type: Option<GLenum>,
+        instances: Option<usize>,
+    ) {
+        let vertex_array = self.bound_vertex_array;
+        let vao = self.bound_vertex_array_mut().clone();
+        if index_type.is_some() && vao.element_buffer.is_none() {
+            panic!("drew elements with no element array buffer bound");
+        }
+        self.draw_calls.push(MockDrawCall {
+            mode,
+            first,
+            count,
+            index_type,
+            vertex_array,
+            element_buffer: vao.element_buffer.filter(|_| index_type.is_some()),
+            enabled_attribs: vao.enabled_attribs.into_iter().collect(),
+            instances,
+        });
+    }
+
+    /// Gets the size of the storage behind a framebuffer attachment.
+    pub fn attachment_size(&self, attachment: MockAttachment) -> (u32, u32) {
+        match attachment {
+            MockAttachment::Texture(handle) => self.textures[&handle].size,
+            MockAttachment::Renderbuffer(handle) => self.renderbuffers[&handle].size,
+        }
+    }
+
+    /// Gets the handle of the buffer bound to the provided target, if any.
+    pub fn bound_buffer(&self, buffer_type: BufferType) -> Option<u32> {
+        match self.bound_buffers.get(&buffer_type) {
+            Some(&handle) if handle != 0 => Some(handle),
+            _ => None,
+        }
+    }
+}
+
+/// A render handler that records every call in memory rather than talking to
+/// a GPU. Clones share the same state, just like clones of `Gl` share the
+/// same context, so tests can hold onto one copy and inspect what the rest of
+/// the renderer did with the others.
+///
+/// Invalid usage, such as deleting a handle twice or drawing with no vertex
+/// array bound, panics so it fails the test that caused it. Shader sources
+/// containing an `#error` directive fail to compile with the rest of that
+/// line as the error, just like they would with a real GLSL compiler.
+#[derive(Debug, Clone, Default)]
+pub struct MockRenderHandler(Rc<RefCell<MockState>>);
+
+impl MockRenderHandler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Borrows the state recorded so far.
+    pub fn state(&self) -> Ref<'_, MockState> {
+        self.0.borrow()
+    }
+
+    /// Forgets all of the draw calls recorded so far.
+    pub fn clear_draw_calls(&mut self) {
+        self.0.borrow_mut().draw_calls.clear();
+    }
+
+    /// Simulates OpenGL raising an error, since the mock panics on invalid
+    /// usage rather than raising errors itself.
+    pub fn raise_error(&mut self, error: GlError) {
+        self.0.borrow_mut().errors.push(error);
+    }
+
+    /// Simulates updating the driver, which changes its name.
+    pub fn set_driver_version(&mut self, version: u32) {
+        self.0.borrow_mut().driver_version = version;
+    }
+}
+
+impl BufferHandler for MockRenderHandler {
+    fn gen_buffer(&mut self) -> u32 {
+        let mut state = self.0.borrow_mut();
+        let handle = state.next_handle();
+        state.buffers.insert(
+            handle,
+            MockBuffer {
+                buffer_type: None,
+                usage: None,
+                data: Vec::new(),
+                allocations: 0,
+            },
+        );
+        handle
+    }
+
+    fn bind_buffer(&mut self, buffer_type: BufferType, handle: u32) {
+        let mut state = self.0.borrow_mut();
+        if handle != 0 {
+            state
+                .buffers
+                .get_mut(&handle)
+                .unwrap_or_else(|| panic!("bound unknown buffer {}", handle))
+                .buffer_type = Some(buffer_type);
+        }
+        state.bound_buffers.insert(buffer_type, handle);
+
+        // The element array buffer binding is stored in the bound vertex array
+        if buffer_type == BufferType::ElementArrayBuffer && state.bound_vertex_array != 0 {
+            state.bound_vertex_array_mut().element_buffer = Some(handle).filter(|&h| h != 0);
+        }
+    }
+
+    fn bind_buffer_base(&mut self, buffer_type: BufferType, index: u32, handle: u32) {
+        self.bind_buffer(buffer_type, handle);
+        self.0
+            .borrow_mut()
+            .indexed_buffers
+            .insert((buffer_type, index), handle);
+    }
+
+    fn buffer_data<DataType: Sized>(
+        &mut self,
+        buffer_type: BufferType,
+        usage: BufferUsage,
+        data: &[DataType],
+    ) {
+        let bytes = unsafe {
+            std::slice::from_raw_parts(data.as_ptr() as *const u8, std::mem::size_of_val(data))
+        };
+
+        let mut state = self.0.borrow_mut();
+        let handle = state
+            .bound_buffer(buffer_type)
+            .unwrap_or_else(|| panic!("buffered data with no {:?} bound", buffer_type));
+        let buffer = state.buffers.get_mut(&handle).unwrap();
+        buffer.usage = Some(usage);
+        buffer.data = bytes.to_vec();
+        buffer.allocations += 1;
+    }
+
+    fn allocate_buffer(&mut self, buffer_type: BufferType, usage: BufferUsage, size: usize) {
+        let mut state = self.0.borrow_mut();
+        let handle = state
+            .bound_buffer(buffer_type)
+            .unwrap_or_else(|| panic!("allocated a buffer with no {:?} bound", buffer_type));
+        let buffer = state.buffers.get_mut(&handle).unwrap();
+        buffer.usage = Some(usage);
+        buffer.data = vec![0; size];
+        buffer.allocations += 1;
+    }
+
+    fn buffer_sub_data<DataType: Sized>(
+        &mut self,
+        buffer_type: BufferType,
+        offset: usize,
+        data: &[DataType],
+    ) {
+        let bytes = unsafe {
+            std::slice::from_raw_parts(data.as_ptr() as *const u8, std::mem::size_of_val(data))
+        };
+
+        let mut state = self.0.borrow_mut();
+        let handle = state
+            .bound_buffer(buffer_type)
+            .unwrap_or_else(|| panic!("buffered sub data with no {:?} bound", buffer_type));
+        let buffer = state.buffers.get_mut(&handle).unwrap();
+        assert!(
+            offset + bytes.len() <= buffer.data.len(),
+            "buffered sub data outside of the buffer's storage"
+        );
+        buffer.data[offset..offset + bytes.len()].copy_from_slice(bytes);
+    }
+
+    fn delete_buffer(&mut self, handle: u32) {
+        let mut state = self.0.borrow_mut();
+        if state.buffers.remove(&handle).is_none() {
+            panic!("deleted unknown buffer {}", handle);
+        }
+        state.bound_buffers.retain(|_, bound| *bound != handle);
+        state.indexed_buffers.retain(|_, bound| *bound != handle);
+    }
+}
+
+impl MeshHandler for MockRenderHandler {
+    fn gen_vertex_array(&mut self) -> u32 {
+        let mut state = self.0.borrow_mut();
+        let handle = state.next_handle();
+        state
+            .vertex_arrays
+            .insert(handle, MockVertexArray::default());
+        handle
+    }
+
+    fn delete_vertex_array(&mut self, handle: u32) {
+        let mut state = self.0.borrow_mut();
+        if state.vertex_arrays.remove(&handle).is_none() {
+            panic!("deleted unknown vertex array {}", handle);
+        }
+        if state.bound_vertex_array == handle {
+            state.bound_vertex_array = 0;
+        }
+    }
+
+    fn vertex_attrib_pointer<DataType: Sized>(&mut self, pointer: &VertexAttribPointer) {
+        let mut state = self.0.borrow_mut();
+        let buffer = state
+            .bound_buffer(BufferType::ArrayBuffer)
+            .expect("set an attribute pointer with no array buffer bound");
+        state
+            .bound_vertex_array_mut()
+            .attrib_pointers
+            .push(MockAttribPointer {
+                pointer: pointer.clone(),
+                stride: std::mem::size_of::<DataType>(),
+                buffer,
+            });
+    }
+
+    fn vertex_attrib_divisor(&mut self, index: usize, divisor: u32) {
+        let mut state = self.0.borrow_mut();
+        let divisors = &mut state.bound_vertex_array_mut().divisors;
+        if divisor == 0 {
+            divisors.remove(&index);
+        } else {
+            divisors.insert(index, divisor);
+        }
+    }
+
+    fn enable_attrib_array(&mut self, index: usize) {
+        self.0
+            .borrow_mut()
+            .bound_vertex_array_mut()
+            .enabled_attribs
+            .insert(index);
+    }
+
+    fn disable_attrib_array(&mut self, index: usize) {
+        self.0
+            .borrow_mut()
+            .bound_vertex_array_mut()
+            .enabled_attribs
+            .remove(&index);
+    }
+
+    fn bind_vertex_array(&mut self, handle: u32) {
+        let mut state = self.0.borrow_mut();
+        if handle != 0 && !state.vertex_arrays.contains_key(&handle) {
+            panic!("bound unknown vertex array {}", handle);
+        }
+        state.bound_vertex_array = handle;
+    }
+
+    fn draw_elements<IndexType: GlIndexType>(&mut self, mode: MeshMode, indices: usize) {
+        self.0
+            .borrow_mut()
+            .record_draw(mode, 0, indices, Some(IndexType::gl_data_type()), None);
+    }
+
+    fn draw_elements_instanced<IndexType: GlIndexType>(
+        &mut self,
+        mode: MeshMode,
+        indices: usize,
+        instances: usize,
+    ) {
+        self.0.borrow_mut().record_draw(
+            mode,
+            0,
+            indices,
+            Some(IndexType::gl_data_type()),
+            Some(instances),
+        );
+    }
+
+    fn draw_arrays(&mut self, mode: MeshMode, first: usize, count: usize) {
+        self.0
+            .borrow_mut()
+            .record_draw(mode, first, count, None, None);
+    }
+
+    fn draw_arrays_instanced(
+        &mut self,
+        mode: MeshMode,
+        first: usize,
+        count: usize,
+        instances: usize,
+    ) {
+        self.0
+            .borrow_mut()
+            .record_draw(mode, first, count, None, Some(instances));
+    }
+}
+
+impl TextureHandler for MockRenderHandler {
+    fn gen_texture(&mut self) -> u32 {
+        let mut state = self.0.borrow_mut();
+        let handle = state.next_handle();
+        state.textures.insert(
+            handle,
+            MockTexture {
+                format: None,
+                size: (0, 0),
+                data: Vec::new(),
+                filter: None,
+                wrap: None,
+                mipmaps_generated: false,
+            },
+        );
+        handle
+    }
+
+    fn active_texture(&mut self, unit: u32) {
+        self.0.borrow_mut().active_texture_unit = unit;
+    }
+
+    fn bind_texture(&mut self, handle: u32) {
+        let mut state = self.0.borrow_mut();
+        if handle != 0 && !state.textures.contains_key(&handle) {
+            panic!("bound unknown texture {}", handle);
+        }
+        let unit = state.active_texture_unit;
+        state.bound_textures.insert(unit, handle);
+    }
+
+    fn tex_image_2d(
+        &mut self,
+        format: TextureFormat,
+        width: u32,
+        height: u32,
+        data: Option<&[u8]>,
+    ) {
+        let mut state = self.0.borrow_mut();
+        let texture = state.bound_texture_mut();
+        let length = width as usize * height as usize * format.bytes_per_pixel();
+
+        texture.format = Some(format);
+        texture.size = (width, height);
+        texture.data = data.map_or_else(|| vec![0; length], |data| data.to_vec());
+        texture.mipmaps_generated = false;
+    }
+
+    fn tex_sub_image_2d(
+        &mut self,
+        format: TextureFormat,
+        offset: (u32, u32),
+        size: (u32, u32),
+        data: &[u8],
+    ) {
+        let mut state = self.0.borrow_mut();
+        let texture = state.bound_texture_mut();
+        assert_eq!(texture.format, Some(format), "texture format mismatch");
+
+        let pixel = format.bytes_per_pixel();
+        let row_length = size.0 as usize * pixel;
+        for (row, src) in data.chunks(row_length).enumerate() {
+            let start =
+                ((offset.1 as usize + row) * texture.size.0 as usize + offset.0 as usize) * pixel;
+            texture.data[start..start + row_length].copy_from_slice(src);
+        }
+        // The mipmaps still show the old contents
+        texture.mipmaps_generated = false;
+    }
+
+    fn set_texture_filter(
+        &mut self,
+        min_filter: TextureFilter,
+        mag_filter: TextureFilter,
+        mipmap_filter: Option<TextureFilter>,
+    ) {
+        self.0.borrow_mut().bound_texture_mut().filter =
+            Some((min_filter, mag_filter, mipmap_filter));
+    }
+
+    fn set_texture_wrap(&mut self, wrap_s: TextureWrap, wrap_t: TextureWrap) {
+        self.0.borrow_mut().bound_texture_mut().wrap = Some((wrap_s, wrap_t));
+    }
+
+    fn generate_mipmap(&mut self) {
+        self.0.borrow_mut().bound_texture_mut().mipmaps_generated = true;
+    }
+
+    fn delete_texture(&mut self, handle: u32) {
+        let mut state = self.0.borrow_mut();
+        if state.textures.remove(&handle).is_none() {
+            panic!("deleted unknown texture {}", handle);
+        }
+        state.bound_textures.retain(|_, bound| *bound != handle);
+    }
+}
+
+impl FramebufferHandler for MockRenderHandler {
+    fn gen_framebuffer(&mut self) -> u32 {
+        let mut state = self.0.borrow_mut();
+        let handle = state.next_handle();
+        state.framebuffers.insert(handle, HashMap::new());
+        handle
+    }
+
+    fn bind_framebuffer(&mut self, handle: u32) {
+        let mut state = self.0.borrow_mut();
+        if handle != 0 && !state.framebuffers.contains_key(&handle) {
+            panic!("bound unknown framebuffer {}", handle);
+        }
+        state.bound_framebuffer = handle;
+    }
+
+    fn framebuffer_texture(&mut self, attachment: FramebufferAttachment, texture: u32) {
+        let mut state = self.0.borrow_mut();
+        if !state.textures.contains_key(&texture) {
+            panic!("attached unknown texture {}", texture);
+        }
+        state
+            .bound_framebuffer_mut()
+            .insert(attachment, MockAttachment::Texture(texture));
+    }
+
+    fn framebuffer_renderbuffer(&mut self, attachment: FramebufferAttachment, renderbuffer: u32) {
+        let mut state = self.0.borrow_mut();
+        if !state.renderbuffers.contains_key(&renderbuffer) {
+            panic!("attached unknown renderbuffer {}", renderbuffer);
+        }
+        state
+            .bound_framebuffer_mut()
+            .insert(attachment, MockAttachment::Renderbuffer(renderbuffer));
+    }
+
+    fn draw_buffers(&mut self, attachments: &[FramebufferAttachment]) {
+        let mut state = self.0.borrow_mut();
+        let handle = state.bound_framebuffer;
+        if !state.framebuffers.contains_key(&handle) {
+            panic!("no valid framebuffer is bound (bound: {})", handle);
+        }
+        state.draw_buffers.insert(handle, attachments.to_vec());
+    }
+
+    fn bound_framebuffer(&self) -> u32 {
+        self.0.borrow().bound_framebuffer
+    }
+
+    fn check_framebuffer_status(&mut self) -> Result<(), FramebufferError> {
+        let mut state = self.0.borrow_mut();
+        let attachments: Vec<MockAttachment> =
+            state.bound_framebuffer_mut().values().copied().collect();
+
+        if attachments.is_empty() {
+            return Err(FramebufferError::Incomplete(
+                "there are no attachments".to_owned(),
+            ));
+        }
+        let size = state.attachment_size(attachments[0]);
+        if attachments
+            .iter()
+            .any(|&attachment| state.attachment_size(attachment) != size)
+        {
+            return Err(FramebufferError::Incomplete(
+                "the attachments have different sizes".to_owned(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn delete_framebuffer(&mut self, handle: u32) {
+        let mut state = self.0.borrow_mut();
+        if state.framebuffers.remove(&handle).is_none() {
+            panic!("deleted unknown framebuffer {}", handle);
+        }
+        state.draw_buffers.remove(&handle);
+        if state.bound_framebuffer == handle {
+            state.bound_framebuffer = 0;
+        }
+    }
+
+    fn gen_renderbuffer(&mut self) -> u32 {
+        let mut state = self.0.borrow_mut();
+        let handle = state.next_handle();
+        state
+            .renderbuffers
+            .insert(handle, MockRenderbuffer::default());
+        handle
+    }
+
+    fn bind_renderbuffer(&mut self, handle: u32) {
+        let mut state = self.0.borrow_mut();
+        if handle != 0 && !state.renderbuffers.contains_key(&handle) {
+            panic!("bound unknown renderbuffer {}", handle);
+        }
+        state.bound_renderbuffer = handle;
+    }
+
+    fn renderbuffer_storage(&mut self, format: TextureFormat, width: u32, height: u32) {
+        let mut state = self.0.borrow_mut();
+        let handle = state.bound_renderbuffer;
+        let renderbuffer = state
+            .renderbuffers
+            .get_mut(&handle)
+            .unwrap_or_else(|| panic!("no valid renderbuffer is bound (bound: {})", handle));
+        renderbuffer.format = Some(format);
+        renderbuffer.size = (width, height);
+    }
+
+    fn delete_renderbuffer(&mut self, handle: u32) {
+        let mut state = self.0.borrow_mut();
+        if state.renderbuffers.remove(&handle).is_none() {
+            panic!("deleted unknown renderbuffer {}", handle);
+        }
+        if state.bound_renderbuffer == handle {
+            state.bound_renderbuffer = 0;
+        }
+    }
+
+    fn viewport(&mut self, x: i32, y: i32, width: u32, height: u32) {
+        self.0.borrow_mut().viewport = (x, y, width, height);
+    }
+
+    fn current_viewport(&self) -> (i32, i32, u32, u32) {
+        self.0.borrow().viewport
+    }
+
+    fn read_pixels(
+        &mut self,
+        format: TextureFormat,
+        offset: (u32, u32),
+        size: (u32, u32),
+    ) -> Vec<u8> {
+        let mut state = self.0.borrow_mut();
+        let pixel = format.bytes_per_pixel();
+        let row_length = size.0 as usize * pixel;
+        let mut pixels = vec![0; row_length * size.1 as usize];
+
+        // Only texture attachments have contents, everything else reads as
+        // zeroes
+        if state.bound_framebuffer == 0 {
+            return pixels;
+        }
+        let texture = match state
+            .bound_framebuffer_mut()
+            .get(&FramebufferAttachment::Color(0))
+        {
+            Some(&MockAttachment::Texture(texture)) => &state.textures[&texture],
+            _ => return pixels,
+        };
+        assert_eq!(texture.format, Some(format), "read pixel format mismatch");
+        assert!(
+            offset.0 + size.0 <= texture.size.0 && offset.1 + size.1 <= texture.size.1,
+            "read pixels outside of the framebuffer"
+        );
+
+        for (row, dst) in pixels.chunks_mut(row_length).enumerate() {
+            let start =
+                ((offset.1 as usize + row) * texture.size.0 as usize + offset.0 as usize) * pixel;
+            dst.copy_from_slice(&texture.data[start..start + row_length]);
+        }
+
+        pixels
+    }
+
+    fn default_framebuffer_size(&self) -> (u32, u32) {
+        self.0.borrow().default_framebuffer_size
+    }
+
+    fn set_default_framebuffer_size(&mut self, size: (u32, u32)) {
+        self.0.borrow_mut().default_framebuffer_size = size;
+    }
+}
+
+impl ShaderHandler for MockRenderHandler {
+    fn create_shader(&mut self, shader_type: ShaderType) -> u32 {
+        let mut state = self.0.borrow_mut();
+        let handle = state.next_handle();
+        state.shaders.insert(
+            handle,
+            MockShader {
+                shader_type,
+                source: None,
+                compiled: false,
+            },
+        );
+        handle
+    }
+
+    fn compile_shader(&mut self, handle: u32, source: &str) -> Result<Option<String>, ShaderError> {
+        let mut state = self.0.borrow_mut();
+        state.shader_compiles += 1;
+        let shader = state
+            .shaders
+            .get_mut(&handle)
+            .unwrap_or_else(|| panic!("compiled unknown shader {}", handle));
+        shader.source = Some(source.to_owned());
+
+        if let Some(line) = source.lines().find(|l| l.trim().starts_with("#error")) {
+            shader.compiled = false;
+            return Err(ShaderError::CompileError(
+                line.trim()["#error".len()..].trim().to_owned(),
+            ));
+        }
+
+        shader.compiled = true;
+
+        // `#warning` lines succeed but are reported like a driver's warnings
+        let warnings: Vec<&str> = source
+            .lines()
+            .filter_map(|l| l.trim().strip_prefix("#warning"))
+            .map(str::trim)
+            .collect();
+        Ok(Some(warnings.join("\n")).filter(|warnings| !warnings.is_empty()))
+    }
+
+    fn delete_shader(&mut self, handle: u32) {
+        if self.0.borrow_mut().shaders.remove(&handle).is_none() {
+            panic!("deleted unknown shader {}", handle);
+        }
+    }
+
+    fn create_program(&mut self) -> u32 {
+        let mut state = self.0.borrow_mut();
+        let handle = state.next_handle();
+        state.programs.insert(handle, MockProgram::default());
+        handle
+    }
+
+    fn attach_shader(&mut self, program: u32, shader: u32) {
+        let mut state = self.0.borrow_mut();
+        if !state.shaders.contains_key(&shader) {
+            panic!("attached unknown shader {}", shader);
+        }
+        state
+            .programs
+            .get_mut(&program)
+            .unwrap_or_else(|| panic!("attached shader to unknown program {}", program))
+            .attached_shaders
+            .push(shader);
+    }
+
+    fn detach_shader(&mut self, program: u32, shader: u32) {
+        self.0
+            .borrow_mut()
+            .programs
+            .get_mut(&program)
+            .unwrap_or_else(|| panic!("detached shader from unknown program {}", program))
+            .attached_shaders
+            .retain(|&attached| attached != shader);
+    }
+
+    fn link_program(&mut self, program: u32) -> Result<Option<String>, ShaderError> {
+        let mut state = self.0.borrow_mut();
+        let attached = state.programs[&program].attached_shaders.clone();
+
+        let mut sources = Vec::new();
+        for handle in attached {
+            let shader = &state.shaders[&handle];
+            if !shader.compiled {
+                return Err(ShaderError::LinkError(format!(
+                    "shader {} is not compiled",
+                    handle
+                )));
+            }
+            sources.push((
+                shader.shader_type,
+                shader.source.clone().unwrap_or_default(),
+            ));
+        }
+
+        state.programs.get_mut(&program).unwrap().link(sources);
+        Ok(None)
+    }
+
+    fn validate_program(&mut self, program: u32) -> Result<(), ShaderError> {
+        match self.0.borrow().programs.get(&program) {
+            Some(program) if program.linked => Ok(()),
+            _ => Err(ShaderError::ValidateError(format!(
+                "program {} is not linked",
+                program
+            ))),
+        }
+    }
+
+    fn set_program_binary_retrievable(&mut self, program: u32) {
+        self.0
+            .borrow_mut()
+            .programs
+            .get_mut(&program)
+            .unwrap_or_else(|| panic!("set hint of unknown program {}", program))
+            .binary_retrievable = true;
+    }
+
+    fn driver_name(&mut self) -> String {
+        format!("Mock renderer {}", self.0.borrow().driver_version)
+    }
+
+    fn program_binary(&mut self, program: u32) -> Option<(u32, Vec<u8>)> {
+        let state = self.0.borrow();
+        let program = &state.programs[&program];
+        if !program.linked || !program.binary_retrievable {
+            return None;
+        }
+
+        // Each source is stored as its type followed by its text
+        let mut binary = Vec::new();
+        for (shader_type, source) in &program.linked_sources {
+            binary.push(match shader_type {
+                ShaderType::Vertex => b'v',
+                ShaderType::Geometry => b'g',
+                ShaderType::Fragment => b'f',
+            });
+            binary.extend_from_slice(source.as_bytes());
+            binary.push(0);
+        }
+
+        Some((MOCK_BINARY_FORMAT, binary))
+    }
+
+    fn load_program_binary(
+        &mut self,
+        program: u32,
+        format: u32,
+        binary: &[u8],
+    ) -> Result<(), ShaderError> {
+        let invalid = || ShaderError::LinkError("invalid program binary".to_owned());
+        if format != MOCK_BINARY_FORMAT {
+            return Err(invalid());
+        }
+
+        let mut sources = Vec::new();
+        for entry in binary.split(|&b| b == 0).filter(|entry| !entry.is_empty()) {
+            let shader_type = match entry[0] {
+                b'v' => ShaderType::Vertex,
+                b'g' => ShaderType::Geometry,
+                b'f' => ShaderType::Fragment,
+                _ => return Err(invalid()),
+            };
+            let source = String::from_utf8(entry[1..].to_vec()).map_err(|_| invalid())?;
+            sources.push((shader_type, source));
+        }
+
+        let mut state = self.0.borrow_mut();
+        let program = state
+            .programs
+            .get_mut(&program)
+            .unwrap_or_else(|| panic!("loaded binary into unknown program {}", program));
+        program.link(sources);
+        program.from_binary = true;
+        Ok(())
+    }
+
+    fn active_uniforms(&mut self, program: u32) -> Vec<ShaderVariable> {
+        self.0.borrow().programs[&program].uniforms.clone()
+    }
+
+    fn active_attributes(&mut self, program: u32) -> Vec<ShaderVariable> {
+        self.0.borrow().programs[&program].attributes.clone()
+    }
+
+    fn active_uniform_blocks(&mut self, program: u32) -> Vec<ShaderUniformBlock> {
+        self.0.borrow().programs[&program].uniform_blocks.clone()
+    }
+
+    fn uniform_block_binding(&mut self, program: u32, block_index: u32, binding: u32) {
+        let mut state = self.0.borrow_mut();
+        let program = state
+            .programs
+            .get_mut(&program)
+            .unwrap_or_else(|| panic!("bound block of unknown program {}", program));
+        if block_index as usize >= program.uniform_blocks.len() {
+            panic!("bound unknown uniform block {}", block_index);
+        }
+        program.block_bindings.insert(block_index, binding);
+    }
+
+    fn uniform_block_binding_point(&mut self, name: &str) -> u32 {
+        self.0.borrow_mut().uniform_bindings.binding_point(name)
+    }
+
+    fn use_program(&mut self, program: u32) {
+        let mut state = self.0.borrow_mut();
+        if program != 0 && !state.programs.contains_key(&program) {
+            panic!("used unknown program {}", program);
+        }
+        state.used_program = program;
+    }
+
+    fn delete_program(&mut self, handle: u32) {
+        let mut state = self.0.borrow_mut();
+        if state.programs.remove(&handle).is_none() {
+            panic!("deleted unknown program {}", handle);
+        }
+        if state.used_program == handle {
+            state.used_program = 0;
+        }
+    }
+
+    fn uniform1i(&mut self, location: i32, value: i32) {
+        self.0
+            .borrow_mut()
+            .uniforms
+            .insert(location, MockUniform::Int(value));
+    }
+
+    fn uniform1f(&mut self, location: i32, value: f32) {
+        self.0
+            .borrow_mut()
+            .uniforms
+            .insert(location, MockUniform::Float(value));
+    }
+
+    fn uniform2f(&mut self, location: i32, value: Vector2<f32>) {
+        self.0
+            .borrow_mut()
+            .uniforms
+            .insert(location, MockUniform::Vec2(value));
+    }
+
+    fn uniform3f(&mut self, location: i32, value: Vector3<f32>) {
+        self.0
+            .borrow_mut()
+            .uniforms
+            .insert(location, MockUniform::Vec3(value));
+    }
+
+    fn uniform4f(&mut self, location: i32, value: Vector4<f32>) {
+        self.0
+            .borrow_mut()
+            .uniforms
+            .insert(location, MockUniform::Vec4(value));
+    }
+
+    fn uniform2x2f(&mut self, location: i32, value: Matrix2<f32>) {
+        self.0
+            .borrow_mut()
+            .uniforms
+            .insert(location, MockUniform::Mat2(value));
+    }
+
+    fn uniform3x3f(&mut self, location: i32, value: Matrix3<f32>) {
+        self.0
+            .borrow_mut()
+            .uniforms
+            .insert(location, MockUniform::Mat3(value));
+    }
+
+    fn uniform4x4f(&mut self, location: i32, value: Matrix4<f32>) {
+        self.0
+            .borrow_mut()
+            .uniforms
+            .insert(location, MockUniform::Mat4(value));
+    }
+
+    fn uniform_iv(&mut self, location: i32, components: usize, values: &[i32]) {
+        self.0
+            .borrow_mut()
+            .uniforms
+            .insert(location, MockUniform::IntArray(components, values.to_vec()));
+    }
+
+    fn uniform_uiv(&mut self, location: i32, components: usize, values: &[u32]) {
+        self.0.borrow_mut().uniforms.insert(
+            location,
+            MockUniform::UIntArray(components, values.to_vec()),
+        );
+    }
+
+    fn uniform_fv(&mut self, location: i32, components: usize, values: &[f32]) {
+        self.0.borrow_mut().uniforms.insert(
+            location,
+            MockUniform::FloatArray(components, values.to_vec()),
+        );
+    }
+
+    fn uniform_matrix_fv(&mut self, location: i32, columns: usize, values: &[f32]) {
+        self.0
+            .borrow_mut()
+            .uniforms
+            .insert(location, MockUniform::MatrixArray(columns, values.to_vec()));
+    }
+}
+
+impl DebugHandler for MockRenderHandler {
+    fn take_errors(&mut self) -> Vec<GlError> {
+        std::mem::take(&mut self.0.borrow_mut().errors)
+    }
+}
+
+impl RenderHandler for MockRenderHandler {}
@@ -4,6 +4,24 @@ pub mod types;
 /// Contains safer implementations to allow OpenGL shader management.
 pub mod shader;
 
+/// Expands includes and defines in GLSL sources
+pub mod preprocessor;
+
+/// Errors raised by OpenGL and the driver's debug output
+pub mod debug;
+
+/// Keeps track of OpenGL objects to report the ones that leak
+pub mod leak;
+
+/// Saves linked shader programs to disk to speed up loading them
+pub mod program_cache;
+
+/// Uniform buffers shared between shader programs
+pub mod uniform_block;
+
+/// Shader programs loaded from files that are rebuilt when the files change
+pub mod shader_asset;
+
 /// OpenGL mesh data
 pub mod mesh;
 
@@ -13,21 +31,160 @@ pub mod buffer;
 /// OpenGL vertex buffers
 pub mod vertex;
 
+/// OpenGL textures
+pub mod texture;
+
+/// TrueType text layout and rendering
+pub mod text;
+
+/// An overlay showing recent warnings and errors
+pub mod console;
+
+/// Packs many small images into shared textures
+pub mod atlas;
+
+/// Offscreen render targets
+pub mod framebuffer;
+
+/// Screenshots and frame sequences read back from framebuffers
+pub mod capture;
+
+/// A render handler that records calls in memory for testing without a GPU
+pub mod mock;
+
 /// Contains the raw OpenGL calls that this renderer needs to use.
 pub mod inner_gl {
     include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
 }
 
+use crate::render::debug::{DebugHandler, GlError};
 use crate::render::mesh::{MeshHandler, MeshMode};
-use crate::render::shader::{ShaderHandler, ShaderUniformValue};
-use crate::render::vertex::{Vertex, VertexAttribPointer};
+use crate::render::shader::{
+    GlslType, ShaderError, ShaderHandler, ShaderType, ShaderUniformBlock, ShaderUniformValue,
+    ShaderVariable, UniformArrayElement,
+};
+use crate::render::uniform_block::UniformBindings;
+use crate::render::vertex::VertexAttribPointer;
 use buffer::{BufferHandler, BufferType, BufferUsage};
-use inner_gl::types::{GLenum, GLsizei, GLuint, GLvoid};
+use framebuffer::{FramebufferAttachment, FramebufferError, FramebufferHandler};
+use inner_gl::types::{GLchar, GLenum, GLint, GLsizei, GLuint, GLvoid};
 use nalgebra::{Matrix2, Matrix3, Matrix4, Vector2, Vector3, Vector4};
+use std::cell::RefCell;
+use std::ffi::CString;
 use std::rc::Rc;
-use types::RGBAColor;
+use texture::{TextureFilter, TextureFormat, TextureHandler, TextureWrap};
+use types::{Half, RGBAColor};
+
+macro_rules! gl_error_check {
+    ($gl:expr, $handle:expr, $get_shader_iv:ident, $status:ident, $get_shader_info_log:ident, $error:ident) => {{
+        // Drivers also write warnings to the info log, so only the status
+        // decides whether the operation failed
+        let status: GLint = {
+            let mut v: GLint = 0;
+            unsafe {
+                $gl.0.$get_shader_iv($handle, inner_gl::$status, &mut v);
+            }
+            v
+        };
+
+        // Get the number of characters in the info log to prevent
+        // unnecessary allocation of a bigger buffer.
+        let info_log_length: GLint = {
+            let mut v: GLint = 0;
+            unsafe {
+                $gl.0
+                    .$get_shader_iv($handle, inner_gl::INFO_LOG_LENGTH, &mut v);
+            }
+            v
+        };
 
-pub trait RenderHandler: BufferHandler + MeshHandler + ShaderHandler {}
+        let log = if info_log_length > 0 {
+            let log = unsafe {
+                // Create a vector with the required length (including the ending null byte)
+                let mut buffer: Vec<u8> = Vec::with_capacity(info_log_length as usize + 1);
+                // Fill it with spaces
+                buffer.extend([b' '].iter().cycle().take(info_log_length as usize));
+
+                // Get the log from OpenGL into the CString
+                $gl.0.$get_shader_info_log(
+                    $handle,
+                    info_log_length,
+                    std::ptr::null_mut(),
+                    buffer.as_mut_ptr() as *mut GLchar,
+                );
+
+                // Convert the pointer back into a CString and then into an
+                // owned string
+                CString::from_vec_with_nul(buffer)
+                    .map_err(|_| ShaderError::Unknown)?
+                    .to_str()
+                    .map_err(|_| ShaderError::Unknown)?
+                    .trim()
+                    .to_owned()
+            };
+            Some(log).filter(|log| !log.is_empty())
+        } else {
+            None
+        };
+
+        if status == inner_gl::FALSE as GLint {
+            Err(ShaderError::$error(log.unwrap_or_default()))
+        } else {
+            Ok(log)
+        }
+    }};
+}
+
+macro_rules! gl_active_variables {
+    ($gl:expr, $program:expr, $count:ident, $max_length:ident, $get_active:ident, $get_location:ident) => {{
+        let mut count: GLint = 0;
+        let mut max_length: GLint = 0;
+        unsafe {
+            $gl.0.GetProgramiv($program, inner_gl::$count, &mut count);
+            $gl.0
+                .GetProgramiv($program, inner_gl::$max_length, &mut max_length);
+        }
+
+        (0..count.max(0) as GLuint)
+            .filter_map(|index| {
+                let mut buffer: Vec<u8> = vec![0; max_length.max(1) as usize];
+                let mut length: GLsizei = 0;
+                let mut size: GLint = 0;
+                let mut gl_type: GLenum = 0;
+                unsafe {
+                    $gl.0.$get_active(
+                        $program,
+                        index,
+                        max_length,
+                        &mut length,
+                        &mut size,
+                        &mut gl_type,
+                        buffer.as_mut_ptr() as *mut GLchar,
+                    );
+                }
+                buffer.truncate(length.max(0) as usize);
+
+                // The location is looked up with the full name, since arrays
+                // are reported with an `[0]` suffix
+                let c_name = CString::new(buffer).ok()?;
+                let location = unsafe { $gl.0.$get_location($program, c_name.as_ptr()) };
+                let name = c_name.into_string().ok()?;
+
+                Some(ShaderVariable {
+                    name: name.trim_end_matches("[0]").to_owned(),
+                    location,
+                    glsl_type: glsl_type(gl_type),
+                    size: size.max(1) as usize,
+                })
+            })
+            .collect()
+    }};
+}
+
+pub trait RenderHandler:
+    BufferHandler + MeshHandler + ShaderHandler + TextureHandler + FramebufferHandler + DebugHandler
+{
+}
 
 pub trait GlType {
     fn gl_type(&self) -> GLenum;
@@ -38,6 +195,7 @@ impl GlType for BufferType {
         match *self {
             Self::ArrayBuffer => inner_gl::ARRAY_BUFFER,
             Self::ElementArrayBuffer => inner_gl::ELEMENT_ARRAY_BUFFER,
+            Self::UniformBuffer => inner_gl::UNIFORM_BUFFER,
         }
     }
 }
@@ -46,6 +204,14 @@ impl GlType for BufferUsage {
     fn gl_type(&self) -> GLenum {
         match *self {
             Self::StaticDraw => inner_gl::STATIC_DRAW,
+            Self::StaticRead => inner_gl::STATIC_READ,
+            Self::StaticCopy => inner_gl::STATIC_COPY,
+            Self::DynamicDraw => inner_gl::DYNAMIC_DRAW,
+            Self::DynamicRead => inner_gl::DYNAMIC_READ,
+            Self::DynamicCopy => inner_gl::DYNAMIC_COPY,
+            Self::StreamDraw => inner_gl::STREAM_DRAW,
+            Self::StreamRead => inner_gl::STREAM_READ,
+            Self::StreamCopy => inner_gl::STREAM_COPY,
         }
     }
 }
@@ -53,7 +219,65 @@ impl GlType for BufferUsage {
 impl GlType for MeshMode {
     fn gl_type(&self) -> GLenum {
         match *self {
+            Self::Points => inner_gl::POINTS,
+            Self::Lines => inner_gl::LINES,
+            Self::LineStrip => inner_gl::LINE_STRIP,
+            Self::LineLoop => inner_gl::LINE_LOOP,
             Self::Triangles => inner_gl::TRIANGLES,
+            Self::TriangleStrip => inner_gl::TRIANGLE_STRIP,
+            Self::TriangleFan => inner_gl::TRIANGLE_FAN,
+        }
+    }
+}
+
+impl GlType for ShaderType {
+    fn gl_type(&self) -> GLenum {
+        match *self {
+            Self::Vertex => inner_gl::VERTEX_SHADER,
+            Self::Geometry => inner_gl::GEOMETRY_SHADER,
+            Self::Fragment => inner_gl::FRAGMENT_SHADER,
+        }
+    }
+}
+
+impl GlType for TextureFilter {
+    fn gl_type(&self) -> GLenum {
+        match *self {
+            Self::Nearest => inner_gl::NEAREST,
+            Self::Linear => inner_gl::LINEAR,
+        }
+    }
+}
+
+impl GlType for TextureWrap {
+    fn gl_type(&self) -> GLenum {
+        match *self {
+            Self::Repeat => inner_gl::REPEAT,
+            Self::MirroredRepeat => inner_gl::MIRRORED_REPEAT,
+            Self::ClampToEdge => inner_gl::CLAMP_TO_EDGE,
+            Self::ClampToBorder => inner_gl::CLAMP_TO_BORDER,
+        }
+    }
+}
+
+impl TextureFormat {
+    /// Gets the internal format, pixel format, and pixel data type used to
+    /// store and upload textures of this format.
+    fn gl_formats(self) -> (GLenum, GLenum, GLenum) {
+        match self {
+            Self::R8 => (inner_gl::R8, inner_gl::RED, inner_gl::UNSIGNED_BYTE),
+            Self::Rgb8 => (inner_gl::RGB8, inner_gl::RGB, inner_gl::UNSIGNED_BYTE),
+            Self::Rgba8 => (inner_gl::RGBA8, inner_gl::RGBA, inner_gl::UNSIGNED_BYTE),
+            Self::Depth24 => (
+                inner_gl::DEPTH_COMPONENT24,
+                inner_gl::DEPTH_COMPONENT,
+                inner_gl::UNSIGNED_INT,
+            ),
+            Self::Depth24Stencil8 => (
+                inner_gl::DEPTH24_STENCIL8,
+                inner_gl::DEPTH_STENCIL,
+                inner_gl::UNSIGNED_INT_24_8,
+            ),
         }
     }
 }
@@ -62,27 +286,152 @@ pub trait GlDataType {
     fn gl_data_type() -> GLenum;
 }
 
+/// A data type that can be used for the indices of a mesh.
+pub trait GlIndexType: GlDataType {}
+
+impl GlIndexType for u8 {}
+impl GlIndexType for u16 {}
+impl GlIndexType for u32 {}
+
 impl GlDataType for f32 {
     fn gl_data_type() -> GLenum {
         inner_gl::FLOAT
     }
 }
 
+impl GlDataType for Half {
+    fn gl_data_type() -> GLenum {
+        inner_gl::HALF_FLOAT
+    }
+}
+
+impl GlDataType for i8 {
+    fn gl_data_type() -> GLenum {
+        inner_gl::BYTE
+    }
+}
+
+impl GlDataType for u8 {
+    fn gl_data_type() -> GLenum {
+        inner_gl::UNSIGNED_BYTE
+    }
+}
+
+impl GlDataType for i16 {
+    fn gl_data_type() -> GLenum {
+        inner_gl::SHORT
+    }
+}
+
 impl GlDataType for u16 {
     fn gl_data_type() -> GLenum {
         inner_gl::UNSIGNED_SHORT
     }
 }
 
+impl GlDataType for i32 {
+    fn gl_data_type() -> GLenum {
+        inner_gl::INT
+    }
+}
+
+impl GlDataType for u32 {
+    fn gl_data_type() -> GLenum {
+        inner_gl::UNSIGNED_INT
+    }
+}
+
+impl GlType for FramebufferAttachment {
+    fn gl_type(&self) -> GLenum {
+        match *self {
+            Self::Color(index) => inner_gl::COLOR_ATTACHMENT0 + index,
+            Self::Depth => inner_gl::DEPTH_ATTACHMENT,
+            Self::DepthStencil => inner_gl::DEPTH_STENCIL_ATTACHMENT,
+        }
+    }
+}
+
+/// State shared between clones of the OpenGL wrapper that can't be queried
+/// from OpenGL itself.
+#[derive(Default)]
+struct GlState {
+    default_framebuffer_size: (u32, u32),
+    uniform_bindings: UniformBindings,
+    /// Errors reported through debug output that haven't been taken yet.
+    debug_errors: Vec<GlError>,
+    /// The functions used to remove the debug output callback, if one was
+    /// installed.
+    debug_output: Option<Rc<inner_gl::Gl>>,
+    /// Whether the context has been destroyed, after which nothing is
+    /// deleted, since everything was deleted along with it.
+    context_destroyed: bool,
+}
+
+impl Drop for GlState {
+    // The debug output callback is given a pointer to this state, so it must
+    // be removed before the state is freed, unless the context (and the
+    // callback with it) is already gone
+    fn drop(&mut self) {
+        if self.context_destroyed {
+            return;
+        }
+        if let Some(gl) = &self.debug_output {
+            unsafe {
+                gl.DebugMessageCallback(None, std::ptr::null());
+            }
+        }
+    }
+}
+
 /// Acts as a safer wrapper around the OpenGL api.
 #[derive(Clone)]
-pub struct Gl(pub Rc<inner_gl::Gl>);
+pub struct Gl(pub Rc<inner_gl::Gl>, Rc<RefCell<GlState>>);
 
 impl Gl {
     /// Creates a new OpenGL wrapper from a closure that returns a pointer to a
     /// function based on its name.
     pub fn new<F: FnMut(&'static str) -> *const std::os::raw::c_void>(f: F) -> Self {
-        Self(Rc::new(inner_gl::Gl::load_with(f)))
+        let gl = Self(
+            Rc::new(inner_gl::Gl::load_with(f)),
+            Rc::new(RefCell::new(GlState::default())),
+        );
+
+        // Debug output explains errors far better than error codes, but it
+        // slows every call down, so it is only used in debug builds
+        if cfg!(debug_assertions) && gl.0.DebugMessageCallback.is_loaded() {
+            unsafe {
+                gl.0.Enable(inner_gl::DEBUG_OUTPUT);
+                // Messages are reported during the call that caused them
+                gl.0.Enable(inner_gl::DEBUG_OUTPUT_SYNCHRONOUS);
+                // The state removes the callback when the last clone of the
+                // wrapper is dropped, so the pointer is valid while it is
+                // installed
+                gl.0.DebugMessageCallback(
+                    Some(debug_message_callback),
+                    Rc::as_ptr(&gl.1) as *const GLvoid,
+                );
+            }
+            gl.1.borrow_mut().debug_output = Some(gl.0.clone());
+        }
+
+        gl
+    }
+
+    /// Records that the context these functions belong to is about to be
+    /// destroyed, which deletes every object in it. Wrappers that are
+    /// dropped afterwards, such as textures that outlive their window, then
+    /// skip deleting their objects rather than calling into a context that
+    /// no longer exists.
+    ///
+    /// Windows do this themselves, so this is only needed when the context
+    /// is managed elsewhere.
+    pub fn destroy_context(&mut self) {
+        self.1.borrow_mut().context_destroyed = true;
+    }
+
+    /// Whether the context these functions belong to still exists.
+    pub fn is_context_alive(&self) -> bool {
+        !self.1.borrow().context_destroyed
     }
 
     /// Sets the color used to clear the screen.
@@ -93,6 +442,21 @@ impl Gl {
         }
     }
 
+    /// Enables or disables blending colors with what has already been drawn
+    /// based on their alpha, which is needed to draw transparent things such
+    /// as text.
+    pub fn set_alpha_blending(&mut self, enabled: bool) {
+        unsafe {
+            if enabled {
+                self.0.Enable(inner_gl::BLEND);
+                self.0
+                    .BlendFunc(inner_gl::SRC_ALPHA, inner_gl::ONE_MINUS_SRC_ALPHA);
+            } else {
+                self.0.Disable(inner_gl::BLEND);
+            }
+        }
+    }
+
     /// Clears the color buffer and/or the depth buffer.
     pub fn clear(&mut self, color: bool, depth: bool) {
         // Nothing needs to be updated, just skip this call.
@@ -130,6 +494,12 @@ impl BufferHandler for Gl {
         }
     }
 
+    fn bind_buffer_base(&mut self, buffer_type: BufferType, index: u32, handle: u32) {
+        unsafe {
+            self.0.BindBufferBase(buffer_type.gl_type(), index, handle);
+        }
+    }
+
     fn buffer_data<DataType: Sized>(
         &mut self,
         buffer_type: BufferType,
@@ -137,28 +507,49 @@ impl BufferHandler for Gl {
         data: &[DataType],
     ) {
         // Get the buffer size in bytes
-        let buffer_size = (data.len() * std::mem::size_of::<DataType>()) as isize;
+        let buffer_size = std::mem::size_of_val(data) as isize;
 
         unsafe {
-            // Invalidate the buffer
             self.0.BufferData(
                 buffer_type.gl_type(),
                 buffer_size,
-                std::ptr::null(),
+                data.as_ptr() as *const GLvoid,
                 usage.gl_type(),
             );
+        }
+    }
 
-            // Buffer the new data
+    fn allocate_buffer(&mut self, buffer_type: BufferType, usage: BufferUsage, size: usize) {
+        unsafe {
             self.0.BufferData(
                 buffer_type.gl_type(),
-                buffer_size,
-                data.as_ptr() as *const GLvoid,
+                size as isize,
+                std::ptr::null(),
                 usage.gl_type(),
             );
         }
     }
 
+    fn buffer_sub_data<DataType: Sized>(
+        &mut self,
+        buffer_type: BufferType,
+        offset: usize,
+        data: &[DataType],
+    ) {
+        unsafe {
+            self.0.BufferSubData(
+                buffer_type.gl_type(),
+                offset as isize,
+                std::mem::size_of_val(data) as isize,
+                data.as_ptr() as *const GLvoid,
+            );
+        }
+    }
+
     fn delete_buffer(&mut self, handle: u32) {
+        if !self.is_context_alive() {
+            return;
+        }
         unsafe {
             self.0.DeleteBuffers(1, &handle);
         }
@@ -175,12 +566,30 @@ impl MeshHandler for Gl {
     }
 
     fn delete_vertex_array(&mut self, handle: u32) {
+        if !self.is_context_alive() {
+            return;
+        }
         unsafe {
             self.0.DeleteVertexArrays(1, &handle);
         }
     }
 
-    fn vertex_attrib_pointer<VertexType: Vertex>(&mut self, pointer: &VertexAttribPointer) {
+    fn vertex_attrib_pointer<DataType: Sized>(&mut self, pointer: &VertexAttribPointer) {
+        // Integer attributes have to be described with a separate function or
+        // they are converted into floats
+        if pointer.integer {
+            unsafe {
+                self.0.VertexAttribIPointer(
+                    pointer.index as GLuint,
+                    pointer.size as GLsizei,
+                    pointer.data_type,
+                    std::mem::size_of::<DataType>() as GLsizei,
+                    pointer.offset as *const GLvoid,
+                );
+            }
+            return;
+        }
+
         unsafe {
             self.0.VertexAttribPointer(
                 pointer.index as GLuint,
@@ -191,12 +600,18 @@ impl MeshHandler for Gl {
                 } else {
                     inner_gl::FALSE
                 },
-                std::mem::size_of::<VertexType>() as GLsizei,
+                std::mem::size_of::<DataType>() as GLsizei,
                 pointer.offset as *const GLvoid,
             );
         }
     }
 
+    fn vertex_attrib_divisor(&mut self, index: usize, divisor: u32) {
+        unsafe {
+            self.0.VertexAttribDivisor(index as GLuint, divisor);
+        }
+    }
+
     fn enable_attrib_array(&mut self, index: usize) {
         unsafe {
             self.0.EnableVertexAttribArray(index as GLuint);
@@ -215,7 +630,7 @@ impl MeshHandler for Gl {
         }
     }
 
-    fn draw_elements<IndexType: GlDataType>(&mut self, mode: MeshMode, indices: usize) {
+    fn draw_elements<IndexType: GlIndexType>(&mut self, mode: MeshMode, indices: usize) {
         unsafe {
             self.0.DrawElements(
                 mode.gl_type(),
@@ -225,9 +640,331 @@ impl MeshHandler for Gl {
             );
         }
     }
+
+    fn draw_elements_instanced<IndexType: GlIndexType>(
+        &mut self,
+        mode: MeshMode,
+        indices: usize,
+        instances: usize,
+    ) {
+        unsafe {
+            self.0.DrawElementsInstanced(
+                mode.gl_type(),
+                indices as GLsizei,
+                IndexType::gl_data_type(),
+                std::ptr::null(),
+                instances as GLsizei,
+            );
+        }
+    }
+
+    fn draw_arrays(&mut self, mode: MeshMode, first: usize, count: usize) {
+        unsafe {
+            self.0
+                .DrawArrays(mode.gl_type(), first as GLint, count as GLsizei);
+        }
+    }
+
+    fn draw_arrays_instanced(
+        &mut self,
+        mode: MeshMode,
+        first: usize,
+        count: usize,
+        instances: usize,
+    ) {
+        unsafe {
+            self.0.DrawArraysInstanced(
+                mode.gl_type(),
+                first as GLint,
+                count as GLsizei,
+                instances as GLsizei,
+            );
+        }
+    }
 }
 
 impl ShaderHandler for Gl {
+    fn create_shader(&mut self, shader_type: ShaderType) -> u32 {
+        unsafe { self.0.CreateShader(shader_type.gl_type()) }
+    }
+
+    fn compile_shader(&mut self, handle: u32, source: &str) -> Result<Option<String>, ShaderError> {
+        // Convert the input source into a format that the C OpenGL api can
+        // understand
+        let c_str = CString::new(source).map_err(|_| ShaderError::InvalidSourceString)?;
+
+        unsafe {
+            // Load the shader source into OpenGL
+            self.0
+                .ShaderSource(handle, 1, &c_str.as_ptr(), std::ptr::null());
+
+            // Try to compile the shader from the provided source
+            self.0.CompileShader(handle);
+        }
+
+        // Check for compilation errors
+        gl_error_check!(
+            self,
+            handle,
+            GetShaderiv,
+            COMPILE_STATUS,
+            GetShaderInfoLog,
+            CompileError
+        )
+    }
+
+    fn delete_shader(&mut self, handle: u32) {
+        if !self.is_context_alive() {
+            return;
+        }
+        unsafe {
+            self.0.DeleteShader(handle);
+        }
+    }
+
+    fn create_program(&mut self) -> u32 {
+        unsafe { self.0.CreateProgram() }
+    }
+
+    fn attach_shader(&mut self, program: u32, shader: u32) {
+        unsafe {
+            self.0.AttachShader(program, shader);
+        }
+    }
+
+    fn detach_shader(&mut self, program: u32, shader: u32) {
+        unsafe {
+            self.0.DetachShader(program, shader);
+        }
+    }
+
+    fn link_program(&mut self, program: u32) -> Result<Option<String>, ShaderError> {
+        unsafe {
+            self.0.LinkProgram(program);
+        }
+
+        // Check for link errors
+        gl_error_check!(
+            self,
+            program,
+            GetProgramiv,
+            LINK_STATUS,
+            GetProgramInfoLog,
+            LinkError
+        )
+    }
+
+    fn validate_program(&mut self, program: u32) -> Result<(), ShaderError> {
+        unsafe {
+            self.0.ValidateProgram(program);
+        }
+
+        // Check for validation errors
+        gl_error_check!(
+            self,
+            program,
+            GetProgramiv,
+            VALIDATE_STATUS,
+            GetProgramInfoLog,
+            ValidateError
+        )
+        .map(|_| ())
+    }
+
+    fn set_program_binary_retrievable(&mut self, program: u32) {
+        if self.0.ProgramParameteri.is_loaded() {
+            unsafe {
+                self.0.ProgramParameteri(
+                    program,
+                    inner_gl::PROGRAM_BINARY_RETRIEVABLE_HINT,
+                    inner_gl::TRUE as GLint,
+                );
+            }
+        }
+    }
+
+    fn driver_name(&mut self) -> String {
+        let string = |name| unsafe {
+            let string = self.0.GetString(name);
+            if string.is_null() {
+                String::new()
+            } else {
+                std::ffi::CStr::from_ptr(string as *const std::os::raw::c_char)
+                    .to_string_lossy()
+                    .into_owned()
+            }
+        };
+
+        format!(
+            "{} {} {}",
+            string(inner_gl::VENDOR),
+            string(inner_gl::RENDERER),
+            string(inner_gl::VERSION)
+        )
+    }
+
+    fn program_binary(&mut self, program: u32) -> Option<(u32, Vec<u8>)> {
+        if !self.0.GetProgramBinary.is_loaded() {
+            return None;
+        }
+
+        let mut length: GLint = 0;
+        unsafe {
+            self.0
+                .GetProgramiv(program, inner_gl::PROGRAM_BINARY_LENGTH, &mut length);
+        }
+        if length <= 0 {
+            return None;
+        }
+
+        let mut binary: Vec<u8> = vec![0; length as usize];
+        let mut written: GLsizei = 0;
+        let mut format: GLenum = 0;
+        unsafe {
+            self.0.GetProgramBinary(
+                program,
+                length,
+                &mut written,
+                &mut format,
+                binary.as_mut_ptr() as *mut GLvoid,
+            );
+        }
+        binary.truncate(written.max(0) as usize);
+
+        Some((format, binary)).filter(|(_, binary)| !binary.is_empty())
+    }
+
+    fn load_program_binary(
+        &mut self,
+        program: u32,
+        format: u32,
+        binary: &[u8],
+    ) -> Result<(), ShaderError> {
+        if !self.0.ProgramBinary.is_loaded() {
+            return Err(ShaderError::LinkError(
+                "program binaries are not supported".to_owned(),
+            ));
+        }
+
+        unsafe {
+            self.0.ProgramBinary(
+                program,
+                format,
+                binary.as_ptr() as *const GLvoid,
+                binary.len() as GLsizei,
+            );
+        }
+
+        // Binaries are rejected if the driver has changed since they were
+        // saved
+        gl_error_check!(
+            self,
+            program,
+            GetProgramiv,
+            LINK_STATUS,
+            GetProgramInfoLog,
+            LinkError
+        )
+        .map(|_| ())
+    }
+
+    fn active_uniforms(&mut self, program: u32) -> Vec<ShaderVariable> {
+        gl_active_variables!(
+            self,
+            program,
+            ACTIVE_UNIFORMS,
+            ACTIVE_UNIFORM_MAX_LENGTH,
+            GetActiveUniform,
+            GetUniformLocation
+        )
+    }
+
+    fn active_attributes(&mut self, program: u32) -> Vec<ShaderVariable> {
+        gl_active_variables!(
+            self,
+            program,
+            ACTIVE_ATTRIBUTES,
+            ACTIVE_ATTRIBUTE_MAX_LENGTH,
+            GetActiveAttrib,
+            GetAttribLocation
+        )
+    }
+
+    fn active_uniform_blocks(&mut self, program: u32) -> Vec<ShaderUniformBlock> {
+        let mut count: GLint = 0;
+        let mut max_length: GLint = 0;
+        unsafe {
+            self.0
+                .GetProgramiv(program, inner_gl::ACTIVE_UNIFORM_BLOCKS, &mut count);
+            self.0.GetProgramiv(
+                program,
+                inner_gl::ACTIVE_UNIFORM_BLOCK_MAX_NAME_LENGTH,
+                &mut max_length,
+            );
+        }
+
+        (0..count.max(0) as GLuint)
+            .filter_map(|index| {
+                let mut buffer: Vec<u8> = vec![0; max_length.max(1) as usize];
+                let mut length: GLsizei = 0;
+                let mut size: GLint = 0;
+                unsafe {
+                    self.0.GetActiveUniformBlockName(
+                        program,
+                        index,
+                        max_length,
+                        &mut length,
+                        buffer.as_mut_ptr() as *mut GLchar,
+                    );
+                    self.0.GetActiveUniformBlockiv(
+                        program,
+                        index,
+                        inner_gl::UNIFORM_BLOCK_DATA_SIZE,
+                        &mut size,
+                    );
+                }
+                buffer.truncate(length.max(0) as usize);
+
+                Some(ShaderUniformBlock {
+                    name: String::from_utf8(buffer).ok()?,
+                    index,
+                    size: size.max(0) as usize,
+                })
+            })
+            .collect()
+    }
+
+    fn uniform_block_binding(&mut self, program: u32, block_index: u32, binding: u32) {
+        unsafe {
+            self.0.UniformBlockBinding(program, block_index, binding);
+        }
+    }
+
+    fn uniform_block_binding_point(&mut self, name: &str) -> u32 {
+        self.1.borrow_mut().uniform_bindings.binding_point(name)
+    }
+
+    fn use_program(&mut self, program: u32) {
+        unsafe {
+            self.0.UseProgram(program);
+        }
+    }
+
+    fn delete_program(&mut self, handle: u32) {
+        if !self.is_context_alive() {
+            return;
+        }
+        unsafe {
+            self.0.DeleteProgram(handle);
+        }
+    }
+
+    fn uniform1i(&mut self, location: i32, value: i32) {
+        unsafe {
+            self.0.Uniform1i(location, value);
+        }
+    }
+
     fn uniform1f(&mut self, location: i32, value: f32) {
         unsafe {
             self.0.Uniform1f(location, value);
@@ -273,48 +1010,682 @@ impl ShaderHandler for Gl {
                 .UniformMatrix4fv(location, 1, inner_gl::FALSE, value.as_ptr());
         }
     }
+
+    fn uniform_iv(&mut self, location: i32, components: usize, values: &[i32]) {
+        let count = (values.len() / components) as GLsizei;
+        unsafe {
+            match components {
+                1 => self.0.Uniform1iv(location, count, values.as_ptr()),
+                2 => self.0.Uniform2iv(location, count, values.as_ptr()),
+                3 => self.0.Uniform3iv(location, count, values.as_ptr()),
+                4 => self.0.Uniform4iv(location, count, values.as_ptr()),
+                _ => panic!("uniforms can't have {} components", components),
+            }
+        }
+    }
+
+    fn uniform_uiv(&mut self, location: i32, components: usize, values: &[u32]) {
+        let count = (values.len() / components) as GLsizei;
+        unsafe {
+            match components {
+                1 => self.0.Uniform1uiv(location, count, values.as_ptr()),
+                2 => self.0.Uniform2uiv(location, count, values.as_ptr()),
+                3 => self.0.Uniform3uiv(location, count, values.as_ptr()),
+                4 => self.0.Uniform4uiv(location, count, values.as_ptr()),
+                _ => panic!("uniforms can't have {} components", components),
+            }
+        }
+    }
+
+    fn uniform_fv(&mut self, location: i32, components: usize, values: &[f32]) {
+        let count = (values.len() / components) as GLsizei;
+        unsafe {
+            match components {
+                1 => self.0.Uniform1fv(location, count, values.as_ptr()),
+                2 => self.0.Uniform2fv(location, count, values.as_ptr()),
+                3 => self.0.Uniform3fv(location, count, values.as_ptr()),
+                4 => self.0.Uniform4fv(location, count, values.as_ptr()),
+                _ => panic!("uniforms can't have {} components", components),
+            }
+        }
+    }
+
+    fn uniform_matrix_fv(&mut self, location: i32, columns: usize, values: &[f32]) {
+        let count = (values.len() / (columns * columns)) as GLsizei;
+        unsafe {
+            match columns {
+                2 => self
+                    .0
+                    .UniformMatrix2fv(location, count, inner_gl::FALSE, values.as_ptr()),
+                3 => self
+                    .0
+                    .UniformMatrix3fv(location, count, inner_gl::FALSE, values.as_ptr()),
+                4 => self
+                    .0
+                    .UniformMatrix4fv(location, count, inner_gl::FALSE, values.as_ptr()),
+                _ => panic!("matrix uniforms can't have {} columns", columns),
+            }
+        }
+    }
+}
+
+impl TextureHandler for Gl {
+    fn gen_texture(&mut self) -> u32 {
+        let mut handle = 0;
+        unsafe {
+            self.0.GenTextures(1, &mut handle);
+        }
+        handle
+    }
+
+    fn active_texture(&mut self, unit: u32) {
+        unsafe {
+            self.0.ActiveTexture(inner_gl::TEXTURE0 + unit);
+        }
+    }
+
+    fn bind_texture(&mut self, handle: u32) {
+        unsafe {
+            self.0.BindTexture(inner_gl::TEXTURE_2D, handle);
+        }
+    }
+
+    fn tex_image_2d(
+        &mut self,
+        format: TextureFormat,
+        width: u32,
+        height: u32,
+        data: Option<&[u8]>,
+    ) {
+        let (internal_format, pixel_format, data_type) = format.gl_formats();
+
+        unsafe {
+            // Rows of tightly packed data aren't necessarily 4-byte aligned
+            self.0.PixelStorei(inner_gl::UNPACK_ALIGNMENT, 1);
+
+            self.0.TexImage2D(
+                inner_gl::TEXTURE_2D,
+                0,
+                internal_format as GLint,
+                width as GLsizei,
+                height as GLsizei,
+                0,
+                pixel_format,
+                data_type,
+                data.map_or(std::ptr::null(), |data| data.as_ptr() as *const GLvoid),
+            );
+        }
+    }
+
+    fn tex_sub_image_2d(
+        &mut self,
+        format: TextureFormat,
+        offset: (u32, u32),
+        size: (u32, u32),
+        data: &[u8],
+    ) {
+        let (_, pixel_format, data_type) = format.gl_formats();
+
+        unsafe {
+            self.0.PixelStorei(inner_gl::UNPACK_ALIGNMENT, 1);
+
+            self.0.TexSubImage2D(
+                inner_gl::TEXTURE_2D,
+                0,
+                offset.0 as GLint,
+                offset.1 as GLint,
+                size.0 as GLsizei,
+                size.1 as GLsizei,
+                pixel_format,
+                data_type,
+                data.as_ptr() as *const GLvoid,
+            );
+        }
+    }
+
+    fn set_texture_filter(
+        &mut self,
+        min_filter: TextureFilter,
+        mag_filter: TextureFilter,
+        mipmap_filter: Option<TextureFilter>,
+    ) {
+        // The minifying filter also determines how mipmaps are sampled
+        let min_filter = match (min_filter, mipmap_filter) {
+            (_, None) => min_filter.gl_type(),
+            (TextureFilter::Nearest, Some(TextureFilter::Nearest)) => {
+                inner_gl::NEAREST_MIPMAP_NEAREST
+            }
+            (TextureFilter::Nearest, Some(TextureFilter::Linear)) => {
+                inner_gl::NEAREST_MIPMAP_LINEAR
+            }
+            (TextureFilter::Linear, Some(TextureFilter::Nearest)) => {
+                inner_gl::LINEAR_MIPMAP_NEAREST
+            }
+            (TextureFilter::Linear, Some(TextureFilter::Linear)) => inner_gl::LINEAR_MIPMAP_LINEAR,
+        };
+
+        unsafe {
+            self.0.TexParameteri(
+                inner_gl::TEXTURE_2D,
+                inner_gl::TEXTURE_MIN_FILTER,
+                min_filter as GLint,
+            );
+            self.0.TexParameteri(
+                inner_gl::TEXTURE_2D,
+                inner_gl::TEXTURE_MAG_FILTER,
+                mag_filter.gl_type() as GLint,
+            );
+        }
+    }
+
+    fn set_texture_wrap(&mut self, wrap_s: TextureWrap, wrap_t: TextureWrap) {
+        unsafe {
+            self.0.TexParameteri(
+                inner_gl::TEXTURE_2D,
+                inner_gl::TEXTURE_WRAP_S,
+                wrap_s.gl_type() as GLint,
+            );
+            self.0.TexParameteri(
+                inner_gl::TEXTURE_2D,
+                inner_gl::TEXTURE_WRAP_T,
+                wrap_t.gl_type() as GLint,
+            );
+        }
+    }
+
+    fn generate_mipmap(&mut self) {
+        unsafe {
+            self.0.GenerateMipmap(inner_gl::TEXTURE_2D);
+        }
+    }
+
+    fn delete_texture(&mut self, handle: u32) {
+        if !self.is_context_alive() {
+            return;
+        }
+        unsafe {
+            self.0.DeleteTextures(1, &handle);
+        }
+    }
+}
+
+impl FramebufferHandler for Gl {
+    fn gen_framebuffer(&mut self) -> u32 {
+        let mut handle = 0;
+        unsafe {
+            self.0.GenFramebuffers(1, &mut handle);
+        }
+        handle
+    }
+
+    fn bind_framebuffer(&mut self, handle: u32) {
+        unsafe {
+            self.0.BindFramebuffer(inner_gl::FRAMEBUFFER, handle);
+        }
+    }
+
+    fn framebuffer_texture(&mut self, attachment: FramebufferAttachment, texture: u32) {
+        unsafe {
+            self.0.FramebufferTexture2D(
+                inner_gl::FRAMEBUFFER,
+                attachment.gl_type(),
+                inner_gl::TEXTURE_2D,
+                texture,
+                0,
+            );
+        }
+    }
+
+    fn framebuffer_renderbuffer(&mut self, attachment: FramebufferAttachment, renderbuffer: u32) {
+        unsafe {
+            self.0.FramebufferRenderbuffer(
+                inner_gl::FRAMEBUFFER,
+                attachment.gl_type(),
+                inner_gl::RENDERBUFFER,
+                renderbuffer,
+            );
+        }
+    }
+
+    fn draw_buffers(&mut self, attachments: &[FramebufferAttachment]) {
+        let mut buffers: Vec<GLenum> = attachments.iter().map(GlType::gl_type).collect();
+        if buffers.is_empty() {
+            buffers.push(inner_gl::NONE);
+        }
+
+        unsafe {
+            self.0
+                .DrawBuffers(buffers.len() as GLsizei, buffers.as_ptr());
+        }
+    }
+
+    fn bound_framebuffer(&self) -> u32 {
+        let mut handle = 0;
+        unsafe {
+            self.0
+                .GetIntegerv(inner_gl::DRAW_FRAMEBUFFER_BINDING, &mut handle);
+        }
+        handle as u32
+    }
+
+    fn check_framebuffer_status(&mut self) -> Result<(), FramebufferError> {
+        let status = unsafe { self.0.CheckFramebufferStatus(inner_gl::FRAMEBUFFER) };
+
+        match status {
+            inner_gl::FRAMEBUFFER_COMPLETE => Ok(()),
+            inner_gl::FRAMEBUFFER_INCOMPLETE_ATTACHMENT => Err(FramebufferError::Incomplete(
+                "an attachment is incomplete".to_owned(),
+            )),
+            inner_gl::FRAMEBUFFER_INCOMPLETE_MISSING_ATTACHMENT => Err(
+                FramebufferError::Incomplete("there are no attachments".to_owned()),
+            ),
+            inner_gl::FRAMEBUFFER_UNSUPPORTED => Err(FramebufferError::Incomplete(
+                "the attachment formats are unsupported".to_owned(),
+            )),
+            status => Err(FramebufferError::Incomplete(format!(
+                "status 0x{:x}",
+                status
+            ))),
+        }
+    }
+
+    fn delete_framebuffer(&mut self, handle: u32) {
+        if !self.is_context_alive() {
+            return;
+        }
+        unsafe {
+            self.0.DeleteFramebuffers(1, &handle);
+        }
+    }
+
+    fn gen_renderbuffer(&mut self) -> u32 {
+        let mut handle = 0;
+        unsafe {
+            self.0.GenRenderbuffers(1, &mut handle);
+        }
+        handle
+    }
+
+    fn bind_renderbuffer(&mut self, handle: u32) {
+        unsafe {
+            self.0.BindRenderbuffer(inner_gl::RENDERBUFFER, handle);
+        }
+    }
+
+    fn renderbuffer_storage(&mut self, format: TextureFormat, width: u32, height: u32) {
+        let (internal_format, _, _) = format.gl_formats();
+
+        unsafe {
+            self.0.RenderbufferStorage(
+                inner_gl::RENDERBUFFER,
+                internal_format,
+                width as GLsizei,
+                height as GLsizei,
+            );
+        }
+    }
+
+    fn delete_renderbuffer(&mut self, handle: u32) {
+        if !self.is_context_alive() {
+            return;
+        }
+        unsafe {
+            self.0.DeleteRenderbuffers(1, &handle);
+        }
+    }
+
+    fn viewport(&mut self, x: i32, y: i32, width: u32, height: u32) {
+        unsafe {
+            self.0.Viewport(x, y, width as GLsizei, height as GLsizei);
+        }
+    }
+
+    fn current_viewport(&self) -> (i32, i32, u32, u32) {
+        let mut viewport = [0; 4];
+        unsafe {
+            self.0
+                .GetIntegerv(inner_gl::VIEWPORT, viewport.as_mut_ptr());
+        }
+        (
+            viewport[0],
+            viewport[1],
+            viewport[2] as u32,
+            viewport[3] as u32,
+        )
+    }
+
+    fn read_pixels(
+        &mut self,
+        format: TextureFormat,
+        offset: (u32, u32),
+        size: (u32, u32),
+    ) -> Vec<u8> {
+        let (_, pixel_format, data_type) = format.gl_formats();
+        let mut pixels = vec![0; size.0 as usize * size.1 as usize * format.bytes_per_pixel()];
+
+        unsafe {
+            // Rows are tightly packed rather than aligned to 4 bytes
+            self.0.PixelStorei(inner_gl::PACK_ALIGNMENT, 1);
+            self.0.ReadPixels(
+                offset.0 as GLint,
+                offset.1 as GLint,
+                size.0 as GLsizei,
+                size.1 as GLsizei,
+                pixel_format,
+                data_type,
+                pixels.as_mut_ptr() as *mut GLvoid,
+            );
+        }
+
+        pixels
+    }
+
+    fn default_framebuffer_size(&self) -> (u32, u32) {
+        self.1.borrow().default_framebuffer_size
+    }
+
+    fn set_default_framebuffer_size(&mut self, size: (u32, u32)) {
+        self.1.borrow_mut().default_framebuffer_size = size;
+    }
+}
+
+impl DebugHandler for Gl {
+    fn take_errors(&mut self) -> Vec<GlError> {
+        if !cfg!(debug_assertions) {
+            return Vec::new();
+        }
+
+        let mut errors = std::mem::take(&mut self.1.borrow_mut().debug_errors);
+        loop {
+            let error = match unsafe { self.0.GetError() } {
+                inner_gl::NO_ERROR => break,
+                inner_gl::INVALID_ENUM => GlError::InvalidEnum,
+                inner_gl::INVALID_VALUE => GlError::InvalidValue,
+                inner_gl::INVALID_OPERATION => GlError::InvalidOperation,
+                inner_gl::INVALID_FRAMEBUFFER_OPERATION => GlError::InvalidFramebufferOperation,
+                inner_gl::OUT_OF_MEMORY => GlError::OutOfMemory,
+                code => GlError::Unknown(code),
+            };
+            errors.push(error);
+        }
+
+        errors
+    }
 }
 
 impl RenderHandler for Gl {}
 
+/// Receives messages from the driver's debug output. Errors are stored to be
+/// taken by `take_errors`, and anything more important than a notification is
+/// logged.
+extern "system" fn debug_message_callback(
+    _source: GLenum,
+    message_type: GLenum,
+    _id: GLuint,
+    severity: GLenum,
+    length: GLsizei,
+    message: *const GLchar,
+    state: *mut GLvoid,
+) {
+    let message = unsafe {
+        if length < 0 {
+            std::ffi::CStr::from_ptr(message)
+                .to_string_lossy()
+                .into_owned()
+        } else {
+            let bytes = std::slice::from_raw_parts(message as *const u8, length as usize);
+            String::from_utf8_lossy(bytes).into_owned()
+        }
+    };
+
+    if message_type == inner_gl::DEBUG_TYPE_ERROR {
+        let state = unsafe { &*(state as *const RefCell<GlState>) };
+        // The state may already be borrowed by the call that caused the error
+        match state.try_borrow_mut() {
+            Ok(mut state) => state.debug_errors.push(GlError::DebugMessage(message)),
+            Err(_) => log::error!("{}", message),
+        }
+    } else if severity != inner_gl::DEBUG_SEVERITY_NOTIFICATION {
+        log::warn!("{}", message);
+    }
+}
+
+fn glsl_type(gl_type: GLenum) -> GlslType {
+    match gl_type {
+        inner_gl::FLOAT => GlslType::Float,
+        inner_gl::FLOAT_VEC2 => GlslType::Vec2,
+        inner_gl::FLOAT_VEC3 => GlslType::Vec3,
+        inner_gl::FLOAT_VEC4 => GlslType::Vec4,
+        inner_gl::INT => GlslType::Int,
+        inner_gl::INT_VEC2 => GlslType::IVec2,
+        inner_gl::INT_VEC3 => GlslType::IVec3,
+        inner_gl::INT_VEC4 => GlslType::IVec4,
+        inner_gl::UNSIGNED_INT => GlslType::UInt,
+        inner_gl::UNSIGNED_INT_VEC2 => GlslType::UVec2,
+        inner_gl::UNSIGNED_INT_VEC3 => GlslType::UVec3,
+        inner_gl::UNSIGNED_INT_VEC4 => GlslType::UVec4,
+        inner_gl::BOOL => GlslType::Bool,
+        inner_gl::BOOL_VEC2 => GlslType::BVec2,
+        inner_gl::BOOL_VEC3 => GlslType::BVec3,
+        inner_gl::BOOL_VEC4 => GlslType::BVec4,
+        inner_gl::FLOAT_MAT2 => GlslType::Mat2,
+        inner_gl::FLOAT_MAT3 => GlslType::Mat3,
+        inner_gl::FLOAT_MAT4 => GlslType::Mat4,
+        inner_gl::SAMPLER_2D => GlslType::Sampler2D,
+        inner_gl::SAMPLER_2D_ARRAY => GlslType::Sampler2DArray,
+        inner_gl::SAMPLER_CUBE => GlslType::SamplerCube,
+        other => GlslType::Other(other),
+    }
+}
+
+impl ShaderUniformValue for i32 {
+    fn glsl_type(&self) -> GlslType {
+        GlslType::Int
+    }
+
+    fn uniform<RHType: RenderHandler>(&self, render_handler: &mut RHType, location: i32) {
+        render_handler.uniform1i(location, *self);
+    }
+}
+
 impl ShaderUniformValue for f32 {
+    fn glsl_type(&self) -> GlslType {
+        GlslType::Float
+    }
+
     fn uniform<RHType: RenderHandler>(&self, render_handler: &mut RHType, location: i32) {
         render_handler.uniform1f(location, *self);
     }
 }
 
 impl ShaderUniformValue for Vector2<f32> {
+    fn glsl_type(&self) -> GlslType {
+        GlslType::Vec2
+    }
+
     fn uniform<RHType: RenderHandler>(&self, render_handler: &mut RHType, location: i32) {
         render_handler.uniform2f(location, *self);
     }
 }
 
 impl ShaderUniformValue for Vector3<f32> {
+    fn glsl_type(&self) -> GlslType {
+        GlslType::Vec3
+    }
+
     fn uniform<RHType: RenderHandler>(&self, render_handler: &mut RHType, location: i32) {
         render_handler.uniform3f(location, *self);
     }
 }
 
 impl ShaderUniformValue for Vector4<f32> {
+    fn glsl_type(&self) -> GlslType {
+        GlslType::Vec4
+    }
+
     fn uniform<RHType: RenderHandler>(&self, render_handler: &mut RHType, location: i32) {
         render_handler.uniform4f(location, *self);
     }
 }
 
 impl ShaderUniformValue for Matrix2<f32> {
+    fn glsl_type(&self) -> GlslType {
+        GlslType::Mat2
+    }
+
     fn uniform<RHType: RenderHandler>(&self, render_handler: &mut RHType, location: i32) {
         render_handler.uniform2x2f(location, *self);
     }
 }
 
 impl ShaderUniformValue for Matrix3<f32> {
+    fn glsl_type(&self) -> GlslType {
+        GlslType::Mat3
+    }
+
     fn uniform<RHType: RenderHandler>(&self, render_handler: &mut RHType, location: i32) {
         render_handler.uniform3x3f(location, *self);
     }
 }
 
 impl ShaderUniformValue for Matrix4<f32> {
+    fn glsl_type(&self) -> GlslType {
+        GlslType::Mat4
+    }
+
     fn uniform<RHType: RenderHandler>(&self, render_handler: &mut RHType, location: i32) {
         render_handler.uniform4x4f(location, *self);
     }
 }
+
+// Scalars are passed straight through, while vectors and matrices are
+// flattened into their components
+macro_rules! uniform_array_element {
+    ($type:ty, $glsl_type:ident, $method:ident, scalar) => {
+        impl UniformArrayElement for $type {
+            fn glsl_type() -> GlslType {
+                GlslType::$glsl_type
+            }
+
+            fn uniform_array<RHType: RenderHandler>(
+                values: &[Self],
+                render_handler: &mut RHType,
+                location: i32,
+            ) {
+                render_handler.$method(location, 1, values);
+            }
+        }
+    };
+    ($type:ty, $glsl_type:ident, $method:ident, $components:expr) => {
+        impl UniformArrayElement for $type {
+            fn glsl_type() -> GlslType {
+                GlslType::$glsl_type
+            }
+
+            fn uniform_array<RHType: RenderHandler>(
+                values: &[Self],
+                render_handler: &mut RHType,
+                location: i32,
+            ) {
+                let components: Vec<_> = values
+                    .iter()
+                    .flat_map(|value| value.iter().copied())
+                    .collect();
+                render_handler.$method(location, $components, &components);
+            }
+        }
+    };
+}
+
+uniform_array_element!(f32, Float, uniform_fv, scalar);
+uniform_array_element!(Vector2<f32>, Vec2, uniform_fv, 2);
+uniform_array_element!(Vector3<f32>, Vec3, uniform_fv, 3);
+uniform_array_element!(Vector4<f32>, Vec4, uniform_fv, 4);
+uniform_array_element!(Matrix2<f32>, Mat2, uniform_matrix_fv, 2);
+uniform_array_element!(Matrix3<f32>, Mat3, uniform_matrix_fv, 3);
+uniform_array_element!(Matrix4<f32>, Mat4, uniform_matrix_fv, 4);
+uniform_array_element!(i32, Int, uniform_iv, scalar);
+uniform_array_element!(Vector2<i32>, IVec2, uniform_iv, 2);
+uniform_array_element!(Vector3<i32>, IVec3, uniform_iv, 3);
+uniform_array_element!(Vector4<i32>, IVec4, uniform_iv, 4);
+uniform_array_element!(u32, UInt, uniform_uiv, scalar);
+uniform_array_element!(Vector2<u32>, UVec2, uniform_uiv, 2);
+uniform_array_element!(Vector3<u32>, UVec3, uniform_uiv, 3);
+uniform_array_element!(Vector4<u32>, UVec4, uniform_uiv, 4);
+
+// Booleans are set as integers
+macro_rules! bool_uniform_array_element {
+    ($type:ty, $glsl_type:ident, $components:expr) => {
+        impl UniformArrayElement for $type {
+            fn glsl_type() -> GlslType {
+                GlslType::$glsl_type
+            }
+
+            fn uniform_array<RHType: RenderHandler>(
+                values: &[Self],
+                render_handler: &mut RHType,
+                location: i32,
+            ) {
+                let components: Vec<i32> = values
+                    .iter()
+                    .flat_map(|value| value.iter().map(|&component| component as i32))
+                    .collect();
+                render_handler.uniform_iv(location, $components, &components);
+            }
+        }
+    };
+}
+
+impl UniformArrayElement for bool {
+    fn glsl_type() -> GlslType {
+        GlslType::Bool
+    }
+
+    fn uniform_array<RHType: RenderHandler>(
+        values: &[Self],
+        render_handler: &mut RHType,
+        location: i32,
+    ) {
+        let values: Vec<i32> = values.iter().map(|&value| value as i32).collect();
+        render_handler.uniform_iv(location, 1, &values);
+    }
+}
+
+bool_uniform_array_element!(Vector2<bool>, BVec2, 2);
+bool_uniform_array_element!(Vector3<bool>, BVec3, 3);
+bool_uniform_array_element!(Vector4<bool>, BVec4, 4);
+
+// The remaining single values are set as an array of one element
+macro_rules! uniform_value {
+    ($($type:ty),*) => {
+        $(
+            impl ShaderUniformValue for $type {
+                fn glsl_type(&self) -> GlslType {
+                    <$type as UniformArrayElement>::glsl_type()
+                }
+
+                fn uniform<RHType: RenderHandler>(
+                    &self,
+                    render_handler: &mut RHType,
+                    location: i32,
+                ) {
+                    <$type>::uniform_array(std::slice::from_ref(self), render_handler, location);
+                }
+            }
+        )*
+    };
+}
+
+uniform_value!(
+    Vector2<i32>,
+    Vector3<i32>,
+    Vector4<i32>,
+    u32,
+    Vector2<u32>,
+    Vector3<u32>,
+    Vector4<u32>,
+    bool,
+    Vector2<bool>,
+    Vector3<bool>,
+    Vector4<bool>
+);
@@ -0,0 +1,309 @@
+use crate::render::shader::ShaderError;
+use std::collections::{HashMap, HashSet};
+use std::io::{self, ErrorKind};
+use std::path::PathBuf;
+
+/// A source of files that shaders can include, with paths separated by `/`.
+pub trait VirtualFileSystem {
+    /// Reads the file at the provided path.
+    fn read(&self, path: &str) -> Result<String, io::Error>;
+}
+
+/// Files stored in memory, such as sources embedded with `include_str!`.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryFileSystem {
+    files: HashMap<String, String>,
+}
+
+impl MemoryFileSystem {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, path: &str, source: &str) {
+        self.files.insert(path.to_owned(), source.to_owned());
+    }
+
+    pub fn with_file(mut self, path: &str, source: &str) -> Self {
+        self.insert(path, source);
+        self
+    }
+}
+
+impl VirtualFileSystem for MemoryFileSystem {
+    fn read(&self, path: &str) -> Result<String, io::Error> {
+        self.files
+            .get(path)
+            .cloned()
+            .ok_or_else(|| io::Error::new(ErrorKind::NotFound, "no such file"))
+    }
+}
+
+/// Files stored on disk below a root directory.
+#[derive(Debug, Clone)]
+pub struct DirectoryFileSystem {
+    root: PathBuf,
+}
+
+impl DirectoryFileSystem {
+    pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Gets where a virtual path is stored on disk.
+    pub fn real_path(&self, path: &str) -> PathBuf {
+        self.root.join(path)
+    }
+}
+
+impl VirtualFileSystem for DirectoryFileSystem {
+    fn read(&self, path: &str) -> Result<String, io::Error> {
+        std::fs::read_to_string(self.real_path(path))
+    }
+}
+
+/// The result of preprocessing a shader, with `#line` directives that refer
+/// to the files it was assembled from by their index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreprocessedSource {
+    pub source: String,
+    /// The path of every file that was included, where the index of a path
+    /// is the source string number used for it in `#line` directives.
+    pub files: Vec<String>,
+}
+
+impl PreprocessedSource {
+    /// Rewrites the locations in a driver's info log, such as `0:12(5)` or
+    /// `0(12)`, to refer to file paths instead of source string numbers.
+    pub fn remap_log(&self, log: &str) -> String {
+        log.lines()
+            .map(|line| self.remap_line(line))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn remap_line(&self, line: &str) -> String {
+        // Some drivers put the severity before the location
+        let (prefix, rest) = ["ERROR: ", "WARNING: "]
+            .iter()
+            .find(|prefix| line.starts_with(*prefix))
+            .map_or(("", line), |prefix| line.split_at(prefix.len()));
+
+        let file_end = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let file = match rest[..file_end].parse::<usize>() {
+            Ok(file) if file < self.files.len() => file,
+            _ => return line.to_owned(),
+        };
+
+        // Mesa and AMD use `file:line`, while NVIDIA uses `file(line)`
+        let rest = &rest[file_end..];
+        let (separator, closing) = match rest.chars().next() {
+            Some(':') => (':', None),
+            Some('(') => ('(', Some(')')),
+            _ => return line.to_owned(),
+        };
+        let rest = &rest[separator.len_utf8()..];
+        let line_end = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        if line_end == 0 {
+            return line.to_owned();
+        }
+        let (number, mut rest) = rest.split_at(line_end);
+        if let Some(closing) = closing {
+            match rest.strip_prefix(closing) {
+                Some(stripped) => rest = stripped,
+                None => return line.to_owned(),
+            }
+        }
+
+        format!("{}{}:{}{}", prefix, self.files[file], number, rest)
+    }
+}
+
+/// Expands `#include "file.glsl"` directives and injects `#define`s into
+/// shader sources.
+///
+/// Includes are resolved relative to the including file, and a file can
+/// contain `#pragma once` to be included only once. Defines are inserted
+/// after the `#version` directive, which must stay on the first line.
+#[derive(Debug, Clone, Default)]
+pub struct Preprocessor {
+    defines: Vec<(String, String)>,
+}
+
+impl Preprocessor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a `#define`, such as for selecting a variant of a shader.
+    pub fn with_define(mut self, name: &str, value: &str) -> Self {
+        self.defines.push((name.to_owned(), value.to_owned()));
+        self
+    }
+
+    pub fn defines(&self) -> &[(String, String)] {
+        &self.defines
+    }
+
+    pub fn process<FS: VirtualFileSystem>(
+        &self,
+        file_system: &FS,
+        path: &str,
+    ) -> Result<PreprocessedSource, ShaderError> {
+        let mut state = PreprocessState {
+            file_system,
+            output: PreprocessedSource {
+                source: String::new(),
+                files: Vec::new(),
+            },
+            stack: Vec::new(),
+            once: HashSet::new(),
+        };
+
+        self.process_file(&mut state, path)?;
+        Ok(state.output)
+    }
+
+    fn process_file<FS: VirtualFileSystem>(
+        &self,
+        state: &mut PreprocessState<'_, FS>,
+        path: &str,
+    ) -> Result<(), ShaderError> {
+        if state.stack.iter().any(|file| file == path) {
+            let mut chain = state.stack.clone();
+            chain.push(path.to_owned());
+            return Err(ShaderError::IncludeCycle(chain.join(" -> ")));
+        }
+        if state.once.contains(path) {
+            return Ok(());
+        }
+
+        let source = state.file_system.read(path).map_err(|error| {
+            let path = path.to_owned();
+            if state.stack.is_empty() {
+                ShaderError::ReadSourceFailed { path, error }
+            } else {
+                ShaderError::IncludeNotFound { path, error }
+            }
+        })?;
+        let index = state.output.files.len();
+        state.output.files.push(path.to_owned());
+        state.stack.push(path.to_owned());
+
+        let is_root = index == 0;
+        let has_version = source
+            .lines()
+            .any(|line| line.trim_start().starts_with("#version"));
+        if is_root && !has_version {
+            self.write_defines(&mut state.output.source);
+            push_line(&mut state.output.source, "#line 1 0");
+        } else if !is_root {
+            push_line(&mut state.output.source, &format!("#line 1 {}", index));
+        }
+
+        for (i, line) in source.lines().enumerate() {
+            let number = i + 1;
+            let trimmed = line.trim();
+
+            if trimmed.starts_with("#include") {
+                let include = parse_include(trimmed).ok_or_else(|| {
+                    ShaderError::InvalidInclude(format!("{}:{}: {}", path, number, trimmed))
+                })?;
+                self.process_file(state, &resolve_path(path, include))?;
+                push_line(
+                    &mut state.output.source,
+                    &format!("#line {} {}", number + 1, index),
+                );
+            } else if trimmed.starts_with("#version") {
+                // Included files can't change the version, but the lines are
+                // kept so that line numbers still match
+                if is_root {
+                    push_line(&mut state.output.source, line);
+                    self.write_defines(&mut state.output.source);
+                    push_line(
+                        &mut state.output.source,
+                        &format!("#line {} {}", number + 1, index),
+                    );
+                } else {
+                    push_line(&mut state.output.source, "");
+                }
+            } else if trimmed == "#pragma once" {
+                state.once.insert(path.to_owned());
+                push_line(&mut state.output.source, "");
+            } else {
+                push_line(&mut state.output.source, line);
+            }
+        }
+
+        state.stack.pop();
+        Ok(())
+    }
+
+    fn write_defines(&self, output: &mut String) {
+        for (name, value) in &self.defines {
+            push_line(output, &format!("#define {} {}", name, value));
+        }
+    }
+}
+
+struct PreprocessState<'a, FS: VirtualFileSystem> {
+    file_system: &'a FS,
+    output: PreprocessedSource,
+    /// The files currently being included, used to detect cycles.
+    stack: Vec<String>,
+    once: HashSet<String>,
+}
+
+fn push_line(output: &mut String, line: &str) {
+    output.push_str(line);
+    output.push('\n');
+}
+
+/// Gets the path from an `#include "path"` or `#include <path>` directive.
+fn parse_include(directive: &str) -> Option<&str> {
+    let argument = directive["#include".len()..].trim();
+    let (open, close) = match argument.chars().next()? {
+        '"' => ('"', '"'),
+        '<' => ('<', '>'),
+        _ => return None,
+    };
+
+    let argument = &argument[open.len_utf8()..];
+    let end = argument.find(close)?;
+    if !argument[end + close.len_utf8()..].trim().is_empty() {
+        return None;
+    }
+
+    Some(&argument[..end])
+}
+
+/// Resolves an included path relative to the directory of the file including
+/// it, or relative to the root if it starts with `/`.
+fn resolve_path(current: &str, include: &str) -> String {
+    let mut components: Vec<&str> = if include.starts_with('/') {
+        Vec::new()
+    } else {
+        let mut components: Vec<&str> = current
+            .split('/')
+            .filter(|component| !matches!(*component, "" | "."))
+            .collect();
+        components.pop();
+        components
+    };
+
+    for component in include.split('/') {
+        match component {
+            "" | "." => {}
+            ".." => {
+                components.pop();
+            }
+            component => components.push(component),
+        }
+    }
+
+    components.join("/")
+}
@@ -0,0 +1,110 @@
+use crate::render::shader::ShaderError;
+use std::path::{Path, PathBuf};
+
+/// A directory of linked program binaries, keyed by a hash of the driver and
+/// the sources they were built from.
+///
+/// Binaries are specific to the driver that created them, so a binary that is
+/// rejected after a driver update is simply rebuilt from source and replaced.
+#[derive(Debug, Clone)]
+pub struct ProgramBinaryCache {
+    directory: PathBuf,
+}
+
+impl ProgramBinaryCache {
+    /// Uses the provided directory for the cache, creating it if it doesn't
+    /// exist.
+    pub fn new<P: Into<PathBuf>>(directory: P) -> Result<Self, ShaderError> {
+        let directory = directory.into();
+        std::fs::create_dir_all(&directory).map_err(|error| ShaderError::CacheFailed {
+            path: directory.clone(),
+            error,
+        })?;
+
+        Ok(Self { directory })
+    }
+
+    /// Computes the key of the program built by the driver with the provided
+    /// name from the provided sources. Keys are stored on disk, so they are
+    /// computed with FNV-1a, which unlike the standard library's hashers
+    /// never changes between releases.
+    pub fn key(
+        driver: &str,
+        vertex_source: Option<&str>,
+        geometry_source: Option<&str>,
+        fragment_source: Option<&str>,
+    ) -> u64 {
+        const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+        const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+        let mut hash = OFFSET_BASIS;
+        let mut write = |bytes: &[u8]| {
+            for &byte in bytes {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(PRIME);
+            }
+        };
+
+        // Every part is prefixed with its length, so that moving text from
+        // one part to the next changes the key
+        for part in [
+            Some(driver),
+            vertex_source,
+            geometry_source,
+            fragment_source,
+        ] {
+            match part {
+                Some(part) => {
+                    write(&[1]);
+                    write(&(part.len() as u64).to_le_bytes());
+                    write(part.as_bytes());
+                }
+                None => write(&[0]),
+            }
+        }
+
+        hash
+    }
+
+    /// Reads the binary stored for a key along with its format, if there is
+    /// one.
+    pub fn load(&self, key: u64) -> Option<(u32, Vec<u8>)> {
+        let mut contents = std::fs::read(self.path(key)).ok()?;
+        if contents.len() < 4 {
+            return None;
+        }
+
+        // Files start with the binary's format
+        let binary = contents.split_off(4);
+        let format = u32::from_le_bytes([contents[0], contents[1], contents[2], contents[3]]);
+        Some((format, binary))
+    }
+
+    /// Writes the binary for a key, replacing any binary stored for it. The
+    /// binary is written to a temporary file first, so that a crash while
+    /// writing can't leave a truncated binary behind.
+    pub fn store(&self, key: u64, format: u32, binary: &[u8]) -> Result<(), ShaderError> {
+        let mut contents = Vec::with_capacity(binary.len() + 4);
+        contents.extend_from_slice(&format.to_le_bytes());
+        contents.extend_from_slice(binary);
+
+        let path = self.path(key);
+        let temporary = self
+            .directory
+            .join(format!("{:016x}.{}.tmp", key, std::process::id()));
+        std::fs::write(&temporary, contents)
+            .and_then(|_| std::fs::rename(&temporary, &path))
+            .map_err(|error| {
+                let _ = std::fs::remove_file(&temporary);
+                ShaderError::CacheFailed { path, error }
+            })
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    fn path(&self, key: u64) -> PathBuf {
+        self.directory.join(format!("{:016x}.bin", key))
+    }
+}
@@ -1,58 +1,83 @@
-use super::inner_gl;
-use super::inner_gl::types::{GLchar, GLint, GLuint};
-use crate::render::{Gl, RenderHandler};
+use crate::render::leak::{self, ResourceKind};
+use crate::render::preprocessor::PreprocessedSource;
+use crate::render::program_cache::ProgramBinaryCache;
+use crate::render::uniform_block::Std140;
+use crate::render::RenderHandler;
 use nalgebra::{Matrix2, Matrix3, Matrix4, Vector2, Vector3, Vector4};
-use std::collections::HashMap;
-use std::ffi::CString;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
 use std::fmt::{Debug, Display, Formatter};
+use std::io;
+use std::path::PathBuf;
 
-macro_rules! gl_error_check {
-    ($gl:expr, $handle:expr, $get_shader_iv:ident, $get_shader_info_log:ident, $error:ident) => {{
-        // Get the number of characters in the shader's info log to check if
-        // there is an error and prevent unnecessary allocation of a bigger buffer.
-        let info_log_length: GLint = {
-            let mut v: GLint = 0;
-            unsafe {
-                $gl.0
-                    .$get_shader_iv($handle, inner_gl::INFO_LOG_LENGTH, &mut v);
-            }
-            v
-        };
+pub trait ShaderHandler: Clone {
+    /// Creates a new shader object, returning 0 if it could not be created.
+    fn create_shader(&mut self, shader_type: ShaderType) -> u32;
 
-        // Check if the shader failed to compile
-        if info_log_length > 0 {
-            let error = unsafe {
-                // Create a vector with the required length (including the ending null byte)
-                let mut buffer: Vec<u8> = Vec::with_capacity(info_log_length as usize + 1);
-                // Fill it with spaces
-                buffer.extend([b' '].iter().cycle().take(info_log_length as usize));
-
-                // Get the error from OpenGL into the CString
-                $gl.0.$get_shader_info_log(
-                    $handle,
-                    info_log_length,
-                    std::ptr::null_mut(),
-                    buffer.as_mut_ptr() as *mut GLchar,
-                );
-
-                // Convert the pointer back into a CString and then return a
-                // compiler error with an owned string
-                CString::from_vec_with_nul(buffer)
-                    .map_err(|_| ShaderError::Unknown)?
-                    .to_str()
-                    .map_err(|_| ShaderError::Unknown)?
-                    .to_owned()
-            };
-
-            // Return the error
-            Err(ShaderError::$error(error.trim().to_owned()))
-        } else {
-            Ok(())
-        }
-    }};
-}
+    /// Loads the provided source into the shader and compiles it, returning
+    /// any warnings the compiler reported.
+    fn compile_shader(&mut self, handle: u32, source: &str) -> Result<Option<String>, ShaderError>;
+
+    fn delete_shader(&mut self, handle: u32);
+
+    /// Creates a new program object, returning 0 if it could not be created.
+    fn create_program(&mut self) -> u32;
+
+    fn attach_shader(&mut self, program: u32, shader: u32);
+
+    fn detach_shader(&mut self, program: u32, shader: u32);
+
+    /// Links the attached shaders, returning any warnings the linker
+    /// reported.
+    fn link_program(&mut self, program: u32) -> Result<Option<String>, ShaderError>;
+
+    fn validate_program(&mut self, program: u32) -> Result<(), ShaderError>;
+
+    /// Asks the driver to keep the binary of a program that is about to be
+    /// linked, so it can be retrieved with `program_binary`.
+    fn set_program_binary_retrievable(&mut self, program: u32);
+
+    /// Gets the binary of a linked program along with its format, or `None`
+    /// if the driver doesn't support program binaries.
+    fn program_binary(&mut self, program: u32) -> Option<(u32, Vec<u8>)>;
+
+    /// Describes the driver, such as its vendor, renderer and version, which
+    /// changes whenever the driver's program binaries might.
+    fn driver_name(&mut self) -> String;
+
+    /// Loads a binary from `program_binary` into a program in place of
+    /// linking it. Drivers may reject binaries they didn't create, in which
+    /// case the program has to be linked from source.
+    fn load_program_binary(
+        &mut self,
+        program: u32,
+        format: u32,
+        binary: &[u8],
+    ) -> Result<(), ShaderError>;
+
+    /// Lists the uniforms that are used by a linked program.
+    fn active_uniforms(&mut self, program: u32) -> Vec<ShaderVariable>;
+
+    /// Lists the vertex attributes that are used by a linked program.
+    fn active_attributes(&mut self, program: u32) -> Vec<ShaderVariable>;
+
+    /// Lists the uniform blocks that are used by a linked program.
+    fn active_uniform_blocks(&mut self, program: u32) -> Vec<ShaderUniformBlock>;
+
+    /// Sets which uniform buffer binding point a program's uniform block
+    /// reads from.
+    fn uniform_block_binding(&mut self, program: u32, block_index: u32, binding: u32);
+
+    /// Gets the binding point shared by every uniform block with the provided
+    /// name, assigning one the first time a name is seen.
+    fn uniform_block_binding_point(&mut self, name: &str) -> u32;
+
+    fn use_program(&mut self, program: u32);
+
+    fn delete_program(&mut self, handle: u32);
+
+    fn uniform1i(&mut self, location: i32, value: i32);
 
-pub trait ShaderHandler: Clone {
     fn uniform1f(&mut self, location: i32, value: f32);
 
     fn uniform2f(&mut self, location: i32, value: Vector2<f32>);
@@ -66,6 +91,20 @@ pub trait ShaderHandler: Clone {
     fn uniform3x3f(&mut self, location: i32, value: Matrix3<f32>);
 
     fn uniform4x4f(&mut self, location: i32, value: Matrix4<f32>);
+
+    /// Sets an `int`, `ivec` or `bool` uniform, or an array of them, from
+    /// tightly packed values with the provided number of components each.
+    fn uniform_iv(&mut self, location: i32, components: usize, values: &[i32]);
+
+    /// Sets a `uint` or `uvec` uniform, or an array of them.
+    fn uniform_uiv(&mut self, location: i32, components: usize, values: &[u32]);
+
+    /// Sets a `float` or `vec` uniform, or an array of them.
+    fn uniform_fv(&mut self, location: i32, components: usize, values: &[f32]);
+
+    /// Sets a square matrix uniform, or an array of them, from column-major
+    /// values.
+    fn uniform_matrix_fv(&mut self, location: i32, columns: usize, values: &[f32]);
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -75,203 +114,503 @@ pub enum ShaderType {
     Fragment,
 }
 
-impl ShaderType {
-    fn gl_enum(self) -> inner_gl::types::GLenum {
-        match self {
-            Self::Vertex => inner_gl::VERTEX_SHADER,
-            Self::Geometry => inner_gl::GEOMETRY_SHADER,
-            Self::Fragment => inner_gl::FRAGMENT_SHADER,
-        }
+/// The type of a uniform or attribute as declared in GLSL.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum GlslType {
+    Float,
+    Vec2,
+    Vec3,
+    Vec4,
+    Int,
+    IVec2,
+    IVec3,
+    IVec4,
+    UInt,
+    UVec2,
+    UVec3,
+    UVec4,
+    Bool,
+    BVec2,
+    BVec3,
+    BVec4,
+    Mat2,
+    Mat3,
+    Mat4,
+    Sampler2D,
+    Sampler2DArray,
+    SamplerCube,
+    /// A type that isn't otherwise supported, with its OpenGL type enum.
+    Other(u32),
+}
+
+impl GlslType {
+    /// Whether this is a sampler, which is set to the texture unit it reads.
+    pub fn is_sampler(&self) -> bool {
+        matches!(
+            self,
+            GlslType::Sampler2D | GlslType::Sampler2DArray | GlslType::SamplerCube
+        )
     }
 }
 
+/// An active uniform or attribute of a linked shader program.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShaderVariable {
+    /// The name of the variable, without an `[0]` suffix for arrays.
+    pub name: String,
+    /// The location of the variable, which is negative for uniforms that are
+    /// stored in uniform blocks.
+    pub location: i32,
+    pub glsl_type: GlslType,
+    /// The number of elements if the variable is an array, otherwise 1.
+    pub size: usize,
+}
+
+/// An active uniform block of a linked shader program.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShaderUniformBlock {
+    pub name: String,
+    /// The index the program uses to refer to the block.
+    pub index: u32,
+    /// The minimum number of bytes the buffer bound to the block must hold.
+    pub size: usize,
+}
+
 #[derive(Debug)]
 pub enum ShaderError {
     CreateShaderFailed,
+    ReadSourceFailed {
+        path: String,
+        error: io::Error,
+    },
+    IncludeNotFound {
+        path: String,
+        error: io::Error,
+    },
+    IncludeCycle(String),
+    InvalidInclude(String),
     InvalidSourceString,
     Unknown,
     CompileError(String),
     CreateShaderProgramFailed,
     LinkError(String),
     ValidateError(String),
-    InvalidUniformName(String),
-    DuplicateUniform(String),
+    UnknownUniform(String),
+    UniformTypeMismatch {
+        name: String,
+        expected: GlslType,
+        found: GlslType,
+    },
+    UnknownUniformBlock(String),
+    UniformBlockSizeMismatch {
+        name: String,
+        expected: usize,
+        found: usize,
+    },
+    CacheFailed {
+        path: PathBuf,
+        error: io::Error,
+    },
 }
 
 impl Display for ShaderError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:?}", self)
+        match self {
+            Self::CreateShaderFailed => write!(f, "failed to create a shader object"),
+            Self::ReadSourceFailed { path, .. } => {
+                write!(f, "failed to read shader source {}", path)
+            }
+            Self::IncludeNotFound { path, .. } => {
+                write!(f, "failed to read included shader source {}", path)
+            }
+            Self::IncludeCycle(chain) => write!(f, "shader sources include each other: {}", chain),
+            Self::InvalidInclude(line) => write!(f, "invalid #include directive at {}", line),
+            Self::InvalidSourceString => write!(f, "shader source contains a nul byte"),
+            Self::Unknown => write!(f, "unknown shader error"),
+            Self::CompileError(log) => write!(f, "failed to compile shader:\n{}", log),
+            Self::CreateShaderProgramFailed => write!(f, "failed to create a shader program"),
+            Self::LinkError(log) => write!(f, "failed to link shader program:\n{}", log),
+            Self::ValidateError(log) => write!(f, "shader program is invalid:\n{}", log),
+            Self::UnknownUniform(name) => write!(f, "no active uniform named `{}`", name),
+            Self::UniformTypeMismatch {
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "uniform `{}` is a {:?} but was set with a {:?}",
+                name, expected, found
+            ),
+            Self::UnknownUniformBlock(name) => {
+                write!(f, "no active uniform block named `{}`", name)
+            }
+            Self::UniformBlockSizeMismatch {
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "uniform block `{}` is {} bytes but its Rust type is {} bytes",
+                name, expected, found
+            ),
+            Self::CacheFailed { path, .. } => {
+                write!(f, "failed to cache program binary at {}", path.display())
+            }
+        }
     }
 }
 
-pub struct Shader {
-    gl: Gl,
-    handle: GLuint,
+impl Error for ShaderError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::ReadSourceFailed { error, .. }
+            | Self::IncludeNotFound { error, .. }
+            | Self::CacheFailed { error, .. } => Some(error),
+            _ => None,
+        }
+    }
 }
 
-impl Shader {
+pub struct Shader<RHType: RenderHandler> {
+    render_handler: RHType,
+    handle: u32,
+    warnings: Option<String>,
+}
+
+impl<RHType: RenderHandler> Shader<RHType> {
     pub fn create_shader(
-        gl: &mut Gl,
+        render_handler: &mut RHType,
         shader_type: ShaderType,
         source: &str,
     ) -> Result<Self, ShaderError> {
         // Create an instance of the shader to make sure that no matter what,
         // if this is dropped, it will be cleaned up.
-        let shader = Self {
-            gl: gl.clone(),
+        let mut shader = Self {
+            render_handler: render_handler.clone(),
             handle: {
-                // Create a GL shader and return an error if it fails
-                let handle = unsafe { gl.0.CreateShader(shader_type.gl_enum()) };
+                // Create a shader and return an error if it fails
+                let handle = render_handler.create_shader(shader_type);
                 if handle == 0 {
                     return Err(ShaderError::CreateShaderFailed);
                 }
+                leak::track(ResourceKind::Shader, handle);
                 handle
             },
+            warnings: None,
         };
 
-        // Convert the input source into a format that the C OpenGL api can
-        // understand
-        let c_str = CString::new(source).map_err(|_| ShaderError::InvalidSourceString)?;
-
-        unsafe {
-            // Load the shader source into OpenGL
-            gl.0.ShaderSource(shader.handle, 1, &c_str.as_ptr(), std::ptr::null());
+        // Try to compile the shader from the provided source
+        shader.warnings = render_handler.compile_shader(shader.handle, source)?;
 
-            // Try to compile the shader from the provided source
-            gl.0.CompileShader(shader.handle);
-        }
+        // Return the shader because it was successfully compiled
+        Ok(shader)
+    }
 
-        // Check for compilation errors
-        gl_error_check!(
-            gl,
-            shader.handle,
-            GetShaderiv,
-            GetShaderInfoLog,
-            CompileError
+    /// Compiles preprocessed source, reporting compile errors at their
+    /// location in the original files.
+    pub fn from_preprocessed(
+        render_handler: &mut RHType,
+        shader_type: ShaderType,
+        source: &PreprocessedSource,
+    ) -> Result<Self, ShaderError> {
+        let mut shader = Self::create_shader(render_handler, shader_type, &source.source).map_err(
+            |e| match e {
+                ShaderError::CompileError(log) => ShaderError::CompileError(source.remap_log(&log)),
+                e => e,
+            },
         )?;
+        shader.warnings = shader.warnings.take().map(|log| source.remap_log(&log));
 
-        // Return the shader because it was successfully compiled
         Ok(shader)
     }
+
+    /// The warnings the compiler reported, if there were any.
+    pub fn warnings(&self) -> Option<&str> {
+        self.warnings.as_deref()
+    }
 }
 
-impl Drop for Shader {
+impl<RHType: RenderHandler> Drop for Shader<RHType> {
     // Automatically delete this shader when this struct is dropped.
     fn drop(&mut self) {
-        println!("Dropping shader {}", self.handle);
+        log::debug!("Dropping shader {}", self.handle);
 
-        unsafe {
-            self.gl.0.DeleteShader(self.handle);
-        }
+        self.render_handler.delete_shader(self.handle);
+        leak::untrack(ResourceKind::Shader, self.handle);
     }
 }
 
 pub trait ShaderUniformValue {
+    /// The GLSL type of the uniforms this value can be assigned to.
+    fn glsl_type(&self) -> GlslType;
+
+    /// Whether this value can be assigned to a uniform of the provided type.
+    fn accepts(&self, glsl_type: GlslType) -> bool {
+        glsl_type == self.glsl_type()
+    }
+
     fn uniform<RHType: RenderHandler>(&self, render_handler: &mut RHType, location: i32);
 }
 
-pub struct ShaderProgram {
-    gl: Gl,
-    handle: GLuint,
-    uniforms: HashMap<String, GLint>,
+/// A texture unit, which sampler uniforms are set to in order to read the
+/// texture bound to that unit.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Sampler(pub u32);
+
+impl ShaderUniformValue for Sampler {
+    fn glsl_type(&self) -> GlslType {
+        GlslType::Sampler2D
+    }
+
+    fn accepts(&self, glsl_type: GlslType) -> bool {
+        glsl_type.is_sampler()
+    }
+
+    fn uniform<RHType: RenderHandler>(&self, render_handler: &mut RHType, location: i32) {
+        render_handler.uniform1i(location, self.0 as i32);
+    }
+}
+
+/// A type that can be stored in an array uniform, such as a
+/// `[Vector3<f32>; 8]` for lights or a `&[Matrix4<f32>]` for bones.
+pub trait UniformArrayElement: Sized {
+    /// The GLSL type of each element of the array.
+    fn glsl_type() -> GlslType;
+
+    /// Whether an array of this type can be assigned to an array uniform of
+    /// the provided element type.
+    fn accepts(glsl_type: GlslType) -> bool {
+        glsl_type == Self::glsl_type()
+    }
+
+    /// Sets consecutive elements of an array uniform, starting at the
+    /// provided location.
+    fn uniform_array<RHType: RenderHandler>(
+        values: &[Self],
+        render_handler: &mut RHType,
+        location: i32,
+    );
+}
+
+impl UniformArrayElement for Sampler {
+    fn glsl_type() -> GlslType {
+        GlslType::Sampler2D
+    }
+
+    fn accepts(glsl_type: GlslType) -> bool {
+        glsl_type.is_sampler()
+    }
+
+    fn uniform_array<RHType: RenderHandler>(
+        values: &[Self],
+        render_handler: &mut RHType,
+        location: i32,
+    ) {
+        let units: Vec<i32> = values.iter().map(|sampler| sampler.0 as i32).collect();
+        render_handler.uniform_iv(location, 1, &units);
+    }
 }
 
-impl ShaderProgram {
+impl<Element: UniformArrayElement> ShaderUniformValue for &[Element] {
+    fn glsl_type(&self) -> GlslType {
+        Element::glsl_type()
+    }
+
+    fn accepts(&self, glsl_type: GlslType) -> bool {
+        Element::accepts(glsl_type)
+    }
+
+    fn uniform<RHType: RenderHandler>(&self, render_handler: &mut RHType, location: i32) {
+        Element::uniform_array(self, render_handler, location);
+    }
+}
+
+impl<Element: UniformArrayElement, const N: usize> ShaderUniformValue for [Element; N] {
+    fn glsl_type(&self) -> GlslType {
+        Element::glsl_type()
+    }
+
+    fn accepts(&self, glsl_type: GlslType) -> bool {
+        Element::accepts(glsl_type)
+    }
+
+    fn uniform<RHType: RenderHandler>(&self, render_handler: &mut RHType, location: i32) {
+        Element::uniform_array(self, render_handler, location);
+    }
+}
+
+pub struct ShaderProgram<RHType: RenderHandler> {
+    render_handler: RHType,
+    handle: u32,
+    uniforms: HashMap<String, ShaderVariable>,
+    attributes: Vec<ShaderVariable>,
+    uniform_blocks: HashMap<String, ShaderUniformBlock>,
+    warnings: Option<String>,
+    /// Uniforms that couldn't be set, which are only reported once each.
+    reported_uniforms: HashSet<String>,
+}
+
+impl<RHType: RenderHandler> ShaderProgram<RHType> {
     pub fn from_shaders(
-        gl: &mut Gl,
-        vertex_shader: Option<Shader>,
-        geometry_shader: Option<Shader>,
-        fragment_shader: Option<Shader>,
-        uniforms: Vec<&str>,
+        render_handler: &mut RHType,
+        vertex_shader: Option<Shader<RHType>>,
+        geometry_shader: Option<Shader<RHType>>,
+        fragment_shader: Option<Shader<RHType>>,
     ) -> Result<Self, ShaderError> {
-        let program = Self {
-            gl: gl.clone(),
+        let mut program = Self::create(render_handler)?;
+        program.link([&vertex_shader, &geometry_shader, &fragment_shader])?;
+
+        /* The shaders will be dropped after this as they are no longer needed */
+
+        program.reflect();
+
+        // Return the program
+        Ok(program)
+    }
+
+    /// Builds a program from source, loading it from the cache instead if it
+    /// was built from the same sources before. Programs that are linked from
+    /// source are saved to the cache, unless the driver doesn't support it.
+    pub fn from_sources_cached(
+        render_handler: &mut RHType,
+        cache: &ProgramBinaryCache,
+        vertex_source: Option<&str>,
+        geometry_source: Option<&str>,
+        fragment_source: Option<&str>,
+    ) -> Result<Self, ShaderError> {
+        let key = ProgramBinaryCache::key(
+            &render_handler.driver_name(),
+            vertex_source,
+            geometry_source,
+            fragment_source,
+        );
+        let mut program = Self::create(render_handler)?;
+
+        // Cached binaries are rejected after driver updates, so fall back to
+        // compiling the sources
+        if let Some((format, binary)) = cache.load(key) {
+            if render_handler
+                .load_program_binary(program.handle, format, &binary)
+                .is_ok()
+            {
+                program.reflect();
+                return Ok(program);
+            }
+        }
+
+        let mut compile = |shader_type, source: Option<&str>| {
+            source
+                .map(|source| Shader::create_shader(render_handler, shader_type, source))
+                .transpose()
+        };
+        let vertex_shader = compile(ShaderType::Vertex, vertex_source)?;
+        let geometry_shader = compile(ShaderType::Geometry, geometry_source)?;
+        let fragment_shader = compile(ShaderType::Fragment, fragment_source)?;
+
+        render_handler.set_program_binary_retrievable(program.handle);
+        program.link([&vertex_shader, &geometry_shader, &fragment_shader])?;
+
+        if let Some((format, binary)) = render_handler.program_binary(program.handle) {
+            if let Err(e) = cache.store(key, format, &binary) {
+                log::warn!("Failed to cache program binary: {}", e);
+            }
+        }
+
+        program.reflect();
+        Ok(program)
+    }
+
+    fn create(render_handler: &mut RHType) -> Result<Self, ShaderError> {
+        Ok(Self {
+            render_handler: render_handler.clone(),
             handle: {
-                // Create a GL shader and return an error if it fails
-                let handle = unsafe { gl.0.CreateProgram() };
+                // Create a program and return an error if it fails
+                let handle = render_handler.create_program();
                 if handle == 0 {
                     return Err(ShaderError::CreateShaderProgramFailed);
                 }
+                leak::track(ResourceKind::ShaderProgram, handle);
                 handle
             },
             uniforms: HashMap::new(),
-        };
+            attributes: Vec::new(),
+            uniform_blocks: HashMap::new(),
+            warnings: None,
+            reported_uniforms: HashSet::new(),
+        })
+    }
+
+    /// Links the vertex, geometry and fragment shaders that are provided,
+    /// keeping and logging their compile warnings along with the linker's.
+    fn link(&mut self, shaders: [&Option<Shader<RHType>>; 3]) -> Result<(), ShaderError> {
+        // The shaders are dropped once they are linked, so their warnings are
+        // kept with the program
+        let mut warnings: Vec<String> = ["vertex", "geometry", "fragment"]
+            .iter()
+            .zip(shaders.iter())
+            .filter_map(|(stage, shader)| {
+                let warnings = shader.as_ref()?.warnings()?;
+                Some(format!("{} shader:\n{}", stage, warnings))
+            })
+            .collect();
 
         // Attach the shaders if they are provided
-        macro_rules! attach_shader {
-            ($shader:expr) => {
-                // Use a reference so this macro doesn't consume the shader
-                if let Some(shader) = &$shader {
-                    unsafe {
-                        gl.0.AttachShader(program.handle, shader.handle);
-                    }
-                    Some(shader.handle)
-                } else {
-                    None
-                }
-            };
+        let shaders: Vec<u32> = shaders
+            .iter()
+            .filter_map(|shader| shader.as_ref().map(|shader| shader.handle))
+            .collect();
+        for shader in &shaders {
+            self.render_handler.attach_shader(self.handle, *shader);
         }
-        let vs = attach_shader!(vertex_shader);
-        let gs = attach_shader!(geometry_shader);
-        let fs = attach_shader!(fragment_shader);
 
         // Link the program
-        unsafe {
-            gl.0.LinkProgram(program.handle);
-        }
-
-        // Check for link errors
-        gl_error_check!(
-            gl,
-            program.handle,
-            GetProgramiv,
-            GetProgramInfoLog,
-            ValidateError
-        )?;
+        let linked = self.render_handler.link_program(self.handle);
 
-        // Detach the shaders so they can be deleted when this function
-        // invocation ends
-        macro_rules! detach_shader {
-            ($shader:expr) => {
-                if let Some(shader) = $shader {
-                    unsafe {
-                        gl.0.DetachShader(program.handle, shader);
-                    }
-                }
-            };
+        // Detach the shaders so they can be deleted once they are dropped,
+        // even if linking failed
+        for shader in &shaders {
+            self.render_handler.detach_shader(self.handle, *shader);
         }
-        detach_shader!(vs);
-        detach_shader!(gs);
-        detach_shader!(fs);
-
-        /* The shaders will be dropped after this as they are no longer needed */
-
-        Ok({
-            // Get a mutable reference
-            let mut program = program;
-
-            for uniform in uniforms {
-                let uniform_name = CString::new(uniform.as_bytes())
-                    .map_err(|_| ShaderError::InvalidUniformName(uniform.to_owned()))?;
-                let location: GLint = unsafe {
-                    gl.0.GetUniformLocation(program.handle, uniform_name.as_ptr())
-                };
 
-                if location < 0 {
-                    eprintln!("Unable to locate uniform \"{}\" in shader", uniform);
-                    continue;
-                }
+        if let Some(link_warnings) = linked? {
+            warnings.push(format!("linker:\n{}", link_warnings));
+        }
+        if !warnings.is_empty() {
+            let warnings = warnings.join("\n");
+            log::warn!("Shader program {} has warnings:\n{}", self.handle, warnings);
+            self.warnings = Some(warnings);
+        }
+        Ok(())
+    }
 
-                let owned_key = uniform.to_owned();
-                match program.uniforms.insert(owned_key, location) {
-                    Some(_) => return Err(ShaderError::DuplicateUniform(uniform.to_owned())),
-                    None => {}
-                }
-            }
+    fn reflect(&mut self) {
+        let render_handler = &mut self.render_handler;
+
+        // Find out what the program can be given now that unused variables
+        // have been optimized out
+        self.uniforms = render_handler
+            .active_uniforms(self.handle)
+            .into_iter()
+            .filter(|uniform| uniform.location >= 0)
+            .map(|uniform| (uniform.name.clone(), uniform))
+            .collect();
+        self.attributes = render_handler.active_attributes(self.handle);
+        self.attributes.sort_by_key(|attribute| attribute.location);
+
+        // Blocks with the same name in every program read from the same
+        // binding point, so a uniform buffer only has to be bound once
+        for block in render_handler.active_uniform_blocks(self.handle) {
+            let binding = render_handler.uniform_block_binding_point(&block.name);
+            render_handler.uniform_block_binding(self.handle, block.index, binding);
+            self.uniform_blocks.insert(block.name.clone(), block);
+        }
+    }
 
-            // Return the program
-            program
-        })
+    /// The warnings the compiler reported for each shader and the warnings
+    /// the linker reported, if there were any. These are also logged when
+    /// the program is built.
+    pub fn warnings(&self) -> Option<&str> {
+        self.warnings.as_deref()
     }
 
     /// Checks whether this program could be executed given the current OpenGL
@@ -279,41 +618,109 @@ impl ShaderProgram {
     /// to trace, this method may reveal what the issue is other than just
     /// "Invalid operation."
     pub fn validate(&mut self) -> Result<(), ShaderError> {
-        // Validate the program
-        unsafe {
-            self.gl.0.ValidateProgram(self.handle);
-        }
-
-        // Check for validation errors
-        gl_error_check!(
-            self.gl,
-            self.handle,
-            GetProgramiv,
-            GetProgramInfoLog,
-            ValidateError
-        )
+        self.render_handler.validate_program(self.handle)
     }
 
     pub fn bind(&mut self) {
-        unsafe {
-            self.gl.0.UseProgram(self.handle);
-        }
+        self.render_handler.use_program(self.handle);
+    }
+
+    pub fn handle(&self) -> u32 {
+        self.handle
     }
 
+    /// Sets the value of a uniform, skipping uniforms that aren't active in
+    /// this program or have a different type. A warning is logged the first
+    /// time each uniform is skipped.
     pub fn uniform<Value: ShaderUniformValue>(&mut self, uniform: &str, value: Value) {
-        if let Some(location) = self.uniforms.get(uniform) {
-            value.uniform(&mut self.gl, *location);
+        if let Err(e) = self.try_uniform(uniform, value) {
+            if self.reported_uniforms.insert(uniform.to_owned()) {
+                log::warn!("Skipped uniform of program {}: {}", self.handle, e);
+            }
+        }
+    }
+
+    /// Sets the value of a uniform, returning an error if this program has
+    /// no such active uniform or it has a different type.
+    pub fn try_uniform<Value: ShaderUniformValue>(
+        &mut self,
+        uniform: &str,
+        value: Value,
+    ) -> Result<(), ShaderError> {
+        let variable = self
+            .uniforms
+            .get(uniform)
+            .ok_or_else(|| ShaderError::UnknownUniform(uniform.to_owned()))?;
+
+        if !value.accepts(variable.glsl_type) {
+            return Err(ShaderError::UniformTypeMismatch {
+                name: uniform.to_owned(),
+                expected: variable.glsl_type,
+                found: value.glsl_type(),
+            });
+        }
+
+        value.uniform(&mut self.render_handler, variable.location);
+        Ok(())
+    }
+
+    /// Gets the active uniform with the provided name.
+    pub fn uniform_info(&self, uniform: &str) -> Option<&ShaderVariable> {
+        self.uniforms.get(uniform)
+    }
+
+    /// The uniforms that are active in this program, in no particular order.
+    pub fn uniforms(&self) -> impl Iterator<Item = &ShaderVariable> {
+        self.uniforms.values()
+    }
+
+    /// The vertex attributes that are active in this program, ordered by
+    /// location.
+    pub fn attributes(&self) -> &[ShaderVariable] {
+        &self.attributes
+    }
+
+    /// Gets the active uniform block with the provided name.
+    pub fn uniform_block_info(&self, block: &str) -> Option<&ShaderUniformBlock> {
+        self.uniform_blocks.get(block)
+    }
+
+    /// Checks that this program has a uniform block with the provided name
+    /// that is the same size as `DataType`, so that a `UniformBlock` of that
+    /// type fills exactly all of it.
+    pub fn check_uniform_block<DataType: Std140>(&self, block: &str) -> Result<(), ShaderError> {
+        let info = self
+            .uniform_blocks
+            .get(block)
+            .ok_or_else(|| ShaderError::UnknownUniformBlock(block.to_owned()))?;
+
+        let size = std::mem::size_of::<DataType>();
+        if size != info.size {
+            return Err(ShaderError::UniformBlockSizeMismatch {
+                name: block.to_owned(),
+                expected: info.size,
+                found: size,
+            });
         }
+
+        Ok(())
+    }
+
+    /// Gets the location of the vertex attribute with the provided name.
+    pub fn attribute_location(&self, attribute: &str) -> Option<usize> {
+        self.attributes
+            .iter()
+            .find(|variable| variable.name == attribute)
+            .map(|variable| variable.location as usize)
     }
 }
 
-impl Drop for ShaderProgram {
+impl<RHType: RenderHandler> Drop for ShaderProgram<RHType> {
     // Automatically delete the program
     fn drop(&mut self) {
-        println!("Dropping program {}", self.handle);
+        log::debug!("Dropping program {}", self.handle);
 
-        unsafe {
-            self.gl.0.DeleteProgram(self.handle);
-        }
+        self.render_handler.delete_program(self.handle);
+        leak::untrack(ResourceKind::ShaderProgram, self.handle);
     }
 }
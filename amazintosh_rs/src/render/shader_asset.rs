@@ -0,0 +1,228 @@
+use crate::render::preprocessor::{DirectoryFileSystem, Preprocessor};
+use crate::render::shader::{Shader, ShaderError, ShaderProgram, ShaderType};
+use crate::render::RenderHandler;
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+#[derive(Debug, Clone)]
+struct ShaderFile {
+    shader_type: ShaderType,
+    path: PathBuf,
+    /// Every file the shader was last built from, including the files it
+    /// includes, along with when they were modified at the time.
+    watched: Vec<(PathBuf, Option<SystemTime>)>,
+}
+
+impl ShaderFile {
+    fn new(shader_type: ShaderType, path: &Path) -> Self {
+        Self {
+            shader_type,
+            path: path.to_owned(),
+            watched: Vec::new(),
+        }
+    }
+
+    fn compile<RHType: RenderHandler>(
+        &mut self,
+        render_handler: &mut RHType,
+        preprocessor: &Preprocessor,
+    ) -> Result<Shader<RHType>, ShaderError> {
+        // Includes are resolved relative to the shader's directory
+        let file_system = DirectoryFileSystem::new(self.path.parent().unwrap_or(Path::new("")));
+        let name = self
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let source = preprocessor.process(&file_system, &name);
+
+        // If the includes couldn't be resolved, keep watching the files that
+        // were used last time
+        let watched: Vec<PathBuf> = match &source {
+            Ok(source) => source
+                .files
+                .iter()
+                .map(|file| file_system.real_path(file))
+                .collect(),
+            Err(_) if !self.watched.is_empty() => {
+                self.watched.iter().map(|(path, _)| path.clone()).collect()
+            }
+            Err(_) => vec![self.path.clone()],
+        };
+        self.watched = watched
+            .into_iter()
+            .map(|path| {
+                let modified = modified_time(&path);
+                (path, modified)
+            })
+            .collect();
+
+        Shader::from_preprocessed(render_handler, self.shader_type, &source?)
+    }
+
+    fn changed(&self) -> bool {
+        self.watched
+            .iter()
+            .any(|(path, modified)| modified_time(path) != *modified)
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// A shader program that is loaded from source files and can be rebuilt when
+/// any of them change on disk, so shaders can be tweaked without restarting.
+pub struct ShaderAsset<RHType: RenderHandler> {
+    render_handler: RHType,
+    preprocessor: Preprocessor,
+    files: Vec<ShaderFile>,
+    program: ShaderProgram<RHType>,
+}
+
+impl<RHType: RenderHandler> ShaderAsset<RHType> {
+    pub fn load<P: AsRef<Path>>(
+        render_handler: &mut RHType,
+        vertex_shader: Option<P>,
+        geometry_shader: Option<P>,
+        fragment_shader: Option<P>,
+    ) -> Result<Self, ShaderError> {
+        Self::load_with(
+            render_handler,
+            Preprocessor::new(),
+            vertex_shader,
+            geometry_shader,
+            fragment_shader,
+        )
+    }
+
+    /// Loads the shaders through a preprocessor, such as one that defines
+    /// which variant of the shaders to build.
+    pub fn load_with<P: AsRef<Path>>(
+        render_handler: &mut RHType,
+        preprocessor: Preprocessor,
+        vertex_shader: Option<P>,
+        geometry_shader: Option<P>,
+        fragment_shader: Option<P>,
+    ) -> Result<Self, ShaderError> {
+        let mut files: Vec<ShaderFile> = [
+            (ShaderType::Vertex, vertex_shader),
+            (ShaderType::Geometry, geometry_shader),
+            (ShaderType::Fragment, fragment_shader),
+        ]
+        .iter()
+        .filter_map(|(shader_type, path)| {
+            path.as_ref()
+                .map(|path| ShaderFile::new(*shader_type, path.as_ref()))
+        })
+        .collect();
+
+        Ok(Self {
+            program: build_program(render_handler, &preprocessor, &mut files)?,
+            render_handler: render_handler.clone(),
+            preprocessor,
+            files,
+        })
+    }
+
+    /// Rebuilds the program from the current contents of its files. If any
+    /// shader fails to compile or link the current program is kept.
+    pub fn reload(&mut self) -> Result<(), ShaderError> {
+        self.program = build_program(
+            &mut self.render_handler,
+            &self.preprocessor,
+            &mut self.files,
+        )?;
+        Ok(())
+    }
+
+    /// Rebuilds the program if any of its files, or the files they include,
+    /// have been modified since it was last built, returning whether the
+    /// program was replaced. Errors are logged and the current program is
+    /// kept until the files change again.
+    pub fn reload_if_changed(&mut self) -> bool {
+        if !self.files.iter().any(ShaderFile::changed) {
+            return false;
+        }
+
+        match self.reload() {
+            Ok(()) => {
+                log::info!("Reloaded shader {}", self.describe());
+                true
+            }
+            Err(e) => {
+                log::error!("Failed to reload shader {}: {}", self.describe(), e);
+                false
+            }
+        }
+    }
+
+    fn describe(&self) -> String {
+        self.files
+            .iter()
+            .map(|file| file.path.display().to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    pub fn program(&self) -> &ShaderProgram<RHType> {
+        &self.program
+    }
+
+    pub fn program_mut(&mut self) -> &mut ShaderProgram<RHType> {
+        &mut self.program
+    }
+}
+
+fn build_program<RHType: RenderHandler>(
+    render_handler: &mut RHType,
+    preprocessor: &Preprocessor,
+    files: &mut [ShaderFile],
+) -> Result<ShaderProgram<RHType>, ShaderError> {
+    // Every file is compiled even after one fails, so that they all record
+    // what they were built from and a broken file is only retried once it
+    // changes again
+    let mut shaders = [None, None, None];
+    let mut error = None;
+    for file in files {
+        let slot = match file.shader_type {
+            ShaderType::Vertex => 0,
+            ShaderType::Geometry => 1,
+            ShaderType::Fragment => 2,
+        };
+        match file.compile(render_handler, preprocessor) {
+            Ok(shader) => shaders[slot] = Some(shader),
+            Err(e) => {
+                error.get_or_insert(e);
+            }
+        }
+    }
+    if let Some(error) = error {
+        return Err(error);
+    }
+    let [vertex_shader, geometry_shader, fragment_shader] = shaders;
+
+    ShaderProgram::from_shaders(
+        render_handler,
+        vertex_shader,
+        geometry_shader,
+        fragment_shader,
+    )
+}
+
+impl<RHType: RenderHandler> Deref for ShaderAsset<RHType> {
+    type Target = ShaderProgram<RHType>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.program
+    }
+}
+
+impl<RHType: RenderHandler> DerefMut for ShaderAsset<RHType> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.program
+    }
+}
@@ -0,0 +1,527 @@
+use crate::render::atlas::SkylinePacker;
+use crate::render::buffer::BufferUsage;
+use crate::render::mesh::Mesh;
+use crate::render::shader::{Shader, ShaderError, ShaderProgram, ShaderType};
+use crate::render::texture::{
+    surface_to_rgba, Texture2D, TextureError, TextureFormat, TextureOptions,
+};
+use crate::render::vertex::Vertex;
+use crate::render::RenderHandler;
+use nalgebra::{Orthographic3, Vector2};
+use sdl2::pixels::Color;
+use sdl2::ttf::{Font, Sdl2TtfContext};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+use std::path::Path;
+
+const TEXT_VERTEX_SHADER: &str = "#version 330
+
+layout (location = 0) in vec2 vert_pos;
+layout (location = 1) in vec2 vert_uv;
+layout (location = 2) in vec4 vert_col;
+
+uniform mat4 projection;
+uniform vec2 offset;
+
+out vec2 frag_uv;
+out vec4 frag_col;
+
+void main() {
+    gl_Position = projection * vec4(vert_pos + offset, 0.0, 1.0);
+
+    frag_uv = vert_uv;
+    frag_col = vert_col;
+}
+";
+
+const TEXT_FRAGMENT_SHADER: &str = "#version 330
+
+in vec2 frag_uv;
+in vec4 frag_col;
+
+uniform sampler2D glyphs;
+
+layout (location = 0) out vec4 frag_color;
+
+void main() {
+    frag_color = frag_col * texture(glyphs, frag_uv);
+}
+";
+
+#[derive(Debug)]
+pub enum TextError {
+    FontLoadFailed(String),
+    RenderGlyphFailed(char, String),
+    GlyphCacheFull(char),
+    Texture(TextureError),
+    Shader(ShaderError),
+}
+
+impl Display for TextError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::FontLoadFailed(message) => write!(f, "failed to load font: {}", message),
+            Self::RenderGlyphFailed(ch, message) => {
+                write!(f, "failed to render glyph {:?}: {}", ch, message)
+            }
+            Self::GlyphCacheFull(ch) => write!(f, "no room in the glyph cache for {:?}", ch),
+            Self::Texture(_) => write!(f, "failed to upload glyphs"),
+            Self::Shader(_) => write!(f, "failed to build text shader"),
+        }
+    }
+}
+
+impl Error for TextError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Texture(error) => Some(error),
+            Self::Shader(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<TextureError> for TextError {
+    fn from(error: TextureError) -> Self {
+        Self::Texture(error)
+    }
+}
+
+impl From<ShaderError> for TextError {
+    fn from(error: ShaderError) -> Self {
+        Self::Shader(error)
+    }
+}
+
+/// Provides the measurements needed to lay out text in pixels.
+pub trait FontMetrics {
+    /// How far the pen moves after drawing the provided character.
+    fn advance(&self, ch: char) -> i32;
+
+    /// The adjustment to the distance between a pair of characters.
+    fn kerning(&self, _left: char, _right: char) -> i32 {
+        0
+    }
+
+    /// The distance between the tops of consecutive lines.
+    fn line_height(&self) -> i32;
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum TextAlign {
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TextOptions {
+    /// Lines longer than this are wrapped at the last space, or between
+    /// characters if a single word doesn't fit.
+    pub max_width: Option<f32>,
+    pub align: TextAlign,
+}
+
+impl Default for TextOptions {
+    fn default() -> Self {
+        Self {
+            max_width: None,
+            align: TextAlign::Left,
+        }
+    }
+}
+
+/// A character positioned relative to the top left of a block of text.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct LaidOutGlyph {
+    pub ch: char,
+    pub position: Vector2<f32>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextLayout {
+    pub glyphs: Vec<LaidOutGlyph>,
+    pub line_count: usize,
+    pub width: f32,
+    pub height: f32,
+}
+
+fn line_width<M: FontMetrics>(metrics: &M, line: &[char]) -> i32 {
+    let mut width = 0;
+    let mut previous = None;
+    for &ch in line {
+        if let Some(previous) = previous {
+            width += metrics.kerning(previous, ch);
+        }
+        width += metrics.advance(ch);
+        previous = Some(ch);
+    }
+    width
+}
+
+/// Breaks a paragraph (text without newlines) into lines that fit within the
+/// maximum width.
+fn wrap_paragraph<M: FontMetrics>(
+    metrics: &M,
+    paragraph: &str,
+    max_width: Option<f32>,
+) -> Vec<Vec<char>> {
+    let max_width = match max_width {
+        Some(max_width) => max_width,
+        None => return vec![paragraph.chars().collect()],
+    };
+
+    let mut lines = Vec::new();
+    let mut line: Vec<char> = Vec::new();
+    // The width of the line so far, kept as characters are added so that
+    // each pair is only measured once
+    let mut width = 0;
+    for ch in paragraph.chars() {
+        if let Some(&previous) = line.last() {
+            width += metrics.kerning(previous, ch);
+        }
+        width += metrics.advance(ch);
+        line.push(ch);
+        if line.len() < 2 || width as f32 <= max_width {
+            continue;
+        }
+
+        match line.iter().rposition(|&c| c == ' ') {
+            // Move the overflowing word onto the next line, dropping the space
+            Some(space) if space > 0 => {
+                let next = line.split_off(space + 1);
+                line.truncate(space);
+                lines.push(std::mem::replace(&mut line, next));
+            }
+            // The word is too long to fit on a line by itself
+            _ => {
+                let overflow = line.pop().unwrap();
+                lines.push(std::mem::replace(&mut line, vec![overflow]));
+            }
+        }
+        width = line_width(metrics, &line);
+    }
+    lines.push(line);
+
+    lines
+}
+
+/// Positions the characters of some text, top left first.
+pub fn layout_text<M: FontMetrics>(metrics: &M, text: &str, options: &TextOptions) -> TextLayout {
+    let lines: Vec<Vec<char>> = text
+        .split('\n')
+        .flat_map(|paragraph| wrap_paragraph(metrics, paragraph, options.max_width))
+        .collect();
+    let widths: Vec<i32> = lines.iter().map(|line| line_width(metrics, line)).collect();
+
+    // Lines are aligned within the wrapping width if there is one
+    let block_width = widths.iter().copied().max().unwrap_or(0) as f32;
+    let align_width = options.max_width.unwrap_or(block_width);
+
+    let line_height = metrics.line_height();
+    let mut glyphs = Vec::new();
+    for (index, (line, width)) in lines.iter().zip(&widths).enumerate() {
+        let mut x = match options.align {
+            TextAlign::Left => 0.0,
+            TextAlign::Center => ((align_width - *width as f32) / 2.0).floor(),
+            TextAlign::Right => align_width - *width as f32,
+        };
+        let y = (index as i32 * line_height) as f32;
+
+        let mut previous = None;
+        for &ch in line {
+            if let Some(previous) = previous {
+                x += metrics.kerning(previous, ch) as f32;
+            }
+            glyphs.push(LaidOutGlyph {
+                ch,
+                position: Vector2::new(x, y),
+            });
+            x += metrics.advance(ch) as f32;
+            previous = Some(ch);
+        }
+    }
+
+    TextLayout {
+        glyphs,
+        line_count: lines.len(),
+        width: block_width,
+        height: (lines.len() as i32 * line_height) as f32,
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Vertex)]
+pub struct TextVertex {
+    pub position: Vector2<f32>,
+    pub uv: Vector2<f32>,
+    pub color: [u8; 4],
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct CachedGlyph {
+    size: Vector2<f32>,
+    uv_min: Vector2<f32>,
+    uv_max: Vector2<f32>,
+    advance: i32,
+}
+
+/// Rasterizes the glyphs of a TrueType font into a texture as they are
+/// needed and builds meshes of textured quads for strings.
+pub struct GlyphCache<'ttf, RHType: RenderHandler> {
+    font: Font<'ttf, 'static>,
+    texture: Texture2D<RHType>,
+    packer: SkylinePacker,
+    glyphs: HashMap<char, CachedGlyph>,
+}
+
+impl<'ttf, RHType: RenderHandler> GlyphCache<'ttf, RHType> {
+    /// Loads a font at the provided point size with an empty cache texture
+    /// of the provided size.
+    pub fn new<P: AsRef<Path>>(
+        render_handler: &mut RHType,
+        ttf_context: &'ttf Sdl2TtfContext,
+        path: P,
+        point_size: u16,
+        cache_size: u32,
+    ) -> Result<Self, TextError> {
+        let mut font = ttf_context
+            .load_font(path, point_size)
+            .map_err(TextError::FontLoadFailed)?;
+        font.set_kerning(true);
+
+        // Start with a fully transparent texture
+        let clear = vec![0; cache_size as usize * cache_size as usize * 4];
+        let texture = Texture2D::from_data(
+            render_handler,
+            TextureFormat::Rgba8,
+            cache_size,
+            cache_size,
+            &clear,
+            TextureOptions::pixelated(),
+        )?;
+
+        Ok(Self {
+            font,
+            texture,
+            packer: SkylinePacker::new(cache_size, cache_size),
+            glyphs: HashMap::new(),
+        })
+    }
+
+    pub fn texture(&self) -> &Texture2D<RHType> {
+        &self.texture
+    }
+
+    pub fn texture_mut(&mut self) -> &mut Texture2D<RHType> {
+        &mut self.texture
+    }
+
+    /// Gets a glyph, rasterizing it into the cache if it hasn't been yet.
+    fn glyph(&mut self, ch: char) -> Result<CachedGlyph, TextError> {
+        if let Some(glyph) = self.glyphs.get(&ch) {
+            return Ok(*glyph);
+        }
+
+        let advance = self.advance(ch);
+        let glyph = if ch.is_whitespace() {
+            // Nothing needs to be drawn for whitespace
+            CachedGlyph {
+                size: Vector2::new(0.0, 0.0),
+                uv_min: Vector2::new(0.0, 0.0),
+                uv_max: Vector2::new(0.0, 0.0),
+                advance,
+            }
+        } else {
+            let surface = self
+                .font
+                .render_char(ch)
+                .blended(Color::RGBA(255, 255, 255, 255))
+                .map_err(|e| TextError::RenderGlyphFailed(ch, e.to_string()))?;
+            let (width, height) = surface.size();
+            let pixels = surface_to_rgba(&surface)?;
+
+            // Leave a pixel of space around glyphs so they don't bleed
+            let (x, y) = self
+                .packer
+                .insert(width + 2, height + 2)
+                .ok_or(TextError::GlyphCacheFull(ch))?;
+            self.texture
+                .update((x + 1, y + 1), (width, height), &pixels)?;
+
+            let cache_size =
+                Vector2::new(self.texture.width() as f32, self.texture.height() as f32);
+            CachedGlyph {
+                size: Vector2::new(width as f32, height as f32),
+                uv_min: Vector2::new((x + 1) as f32, (y + 1) as f32).component_div(&cache_size),
+                uv_max: Vector2::new((x + 1 + width) as f32, (y + 1 + height) as f32)
+                    .component_div(&cache_size),
+                advance,
+            }
+        };
+
+        self.glyphs.insert(ch, glyph);
+        Ok(glyph)
+    }
+
+    /// Lays out the text and builds the vertices and indices of its quads,
+    /// relative to the top left of the text.
+    fn text_geometry(
+        &mut self,
+        text: &str,
+        options: &TextOptions,
+        color: [u8; 4],
+    ) -> Result<(Vec<TextVertex>, Vec<u32>), TextError> {
+        let layout = layout_text(self, text, options);
+
+        let mut vertices = Vec::with_capacity(layout.glyphs.len() * 4);
+        let mut indices = Vec::with_capacity(layout.glyphs.len() * 6);
+        for laid_out in layout.glyphs {
+            let glyph = self.glyph(laid_out.ch)?;
+            if glyph.size.x == 0.0 {
+                continue;
+            }
+
+            let min = laid_out.position;
+            let max = min + glyph.size;
+            let first = vertices.len() as u32;
+            vertices.extend_from_slice(&[
+                TextVertex {
+                    position: min,
+                    uv: glyph.uv_min,
+                    color,
+                },
+                TextVertex {
+                    position: Vector2::new(max.x, min.y),
+                    uv: Vector2::new(glyph.uv_max.x, glyph.uv_min.y),
+                    color,
+                },
+                TextVertex {
+                    position: max,
+                    uv: glyph.uv_max,
+                    color,
+                },
+                TextVertex {
+                    position: Vector2::new(min.x, max.y),
+                    uv: Vector2::new(glyph.uv_min.x, glyph.uv_max.y),
+                    color,
+                },
+            ]);
+            indices.extend_from_slice(&[first, first + 2, first + 1, first, first + 3, first + 2]);
+        }
+
+        Ok((vertices, indices))
+    }
+
+    /// Builds a mesh of textured quads for the text, in pixels relative to
+    /// its top left corner.
+    pub fn text_mesh(
+        &mut self,
+        render_handler: &mut RHType,
+        text: &str,
+        options: &TextOptions,
+        color: [u8; 4],
+    ) -> Result<Mesh<RHType, TextVertex, u32>, TextError> {
+        let mut mesh = Mesh::new(render_handler);
+        self.update_text_mesh(&mut mesh, text, options, color)?;
+        Ok(mesh)
+    }
+
+    /// Replaces the contents of a text mesh, such as when a counter changes.
+    pub fn update_text_mesh(
+        &mut self,
+        mesh: &mut Mesh<RHType, TextVertex, u32>,
+        text: &str,
+        options: &TextOptions,
+        color: [u8; 4],
+    ) -> Result<(), TextError> {
+        let (vertices, indices) = self.text_geometry(text, options, color)?;
+        mesh.set_vertices(vertices, BufferUsage::DynamicDraw);
+        mesh.set_indices(indices, BufferUsage::DynamicDraw);
+        Ok(())
+    }
+}
+
+impl<'ttf, RHType: RenderHandler> FontMetrics for GlyphCache<'ttf, RHType> {
+    fn advance(&self, ch: char) -> i32 {
+        self.glyphs.get(&ch).map_or_else(
+            || {
+                self.font
+                    .find_glyph_metrics(ch)
+                    .map_or(0, |metrics| metrics.advance)
+            },
+            |glyph| glyph.advance,
+        )
+    }
+
+    fn kerning(&self, left: char, right: char) -> i32 {
+        // SDL_ttf applies kerning when measuring strings, so the kerning is
+        // whatever is left over after the advances of the pair
+        let mut pair = String::with_capacity(8);
+        pair.push(left);
+        pair.push(right);
+        match self.font.size_of(&pair) {
+            Ok((width, _)) => width as i32 - self.advance(left) - self.advance(right),
+            Err(_) => 0,
+        }
+    }
+
+    fn line_height(&self) -> i32 {
+        self.font.recommended_line_spacing()
+    }
+}
+
+/// Draws text meshes in screen space, where the origin is the top left of
+/// the screen and one unit is one pixel. Alpha blending should be enabled
+/// when drawing text.
+pub struct TextRenderer<RHType: RenderHandler> {
+    program: ShaderProgram<RHType>,
+}
+
+impl<RHType: RenderHandler> TextRenderer<RHType> {
+    pub fn new(render_handler: &mut RHType) -> Result<Self, TextError> {
+        let vertex_shader =
+            Shader::create_shader(render_handler, ShaderType::Vertex, TEXT_VERTEX_SHADER)?;
+        let fragment_shader =
+            Shader::create_shader(render_handler, ShaderType::Fragment, TEXT_FRAGMENT_SHADER)?;
+
+        Ok(Self {
+            program: ShaderProgram::from_shaders(
+                render_handler,
+                Some(vertex_shader),
+                None,
+                Some(fragment_shader),
+            )?,
+        })
+    }
+
+    /// Draws a text mesh built by the provided glyph cache with its top left
+    /// corner at the provided position on a screen of the provided size.
+    pub fn render(
+        &mut self,
+        glyph_cache: &mut GlyphCache<'_, RHType>,
+        mesh: &mut Mesh<RHType, TextVertex, u32>,
+        position: Vector2<f32>,
+        screen_size: (usize, usize),
+    ) {
+        let projection = Orthographic3::new(
+            0.0,
+            screen_size.0 as f32,
+            screen_size.1 as f32,
+            0.0,
+            -1.0,
+            1.0,
+        );
+
+        glyph_cache.texture_mut().bind(0);
+
+        self.program.bind();
+        self.program
+            .uniform("projection", projection.to_homogeneous());
+        self.program.uniform("offset", position);
+        self.program.uniform("glyphs", glyph_cache.texture());
+
+        mesh.render();
+    }
+}
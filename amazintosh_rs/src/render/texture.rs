@@ -0,0 +1,431 @@
+use crate::render::leak::{self, ResourceKind};
+use crate::render::shader::{GlslType, Sampler, ShaderUniformValue};
+use crate::render::RenderHandler;
+use sdl2::image::LoadSurface;
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::surface::{Surface, SurfaceRef};
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+use std::path::Path;
+
+// Handler for creating and managing textures
+pub trait TextureHandler: Clone {
+    fn gen_texture(&mut self) -> u32;
+
+    /// Selects the texture unit that following `bind_texture` calls affect.
+    fn active_texture(&mut self, unit: u32);
+
+    /// Binds a 2D texture to the active texture unit.
+    fn bind_texture(&mut self, handle: u32);
+
+    /// Allocates storage for the bound texture, optionally filling it with
+    /// tightly packed pixel data.
+    fn tex_image_2d(&mut self, format: TextureFormat, width: u32, height: u32, data: Option<&[u8]>);
+
+    /// Replaces a region of the bound texture with tightly packed pixel data.
+    fn tex_sub_image_2d(
+        &mut self,
+        format: TextureFormat,
+        offset: (u32, u32),
+        size: (u32, u32),
+        data: &[u8],
+    );
+
+    fn set_texture_filter(
+        &mut self,
+        min_filter: TextureFilter,
+        mag_filter: TextureFilter,
+        mipmap_filter: Option<TextureFilter>,
+    );
+
+    fn set_texture_wrap(&mut self, wrap_s: TextureWrap, wrap_t: TextureWrap);
+
+    fn generate_mipmap(&mut self);
+
+    fn delete_texture(&mut self, handle: u32);
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum TextureFormat {
+    R8,
+    Rgb8,
+    Rgba8,
+    Depth24,
+    Depth24Stencil8,
+}
+
+impl TextureFormat {
+    /// The number of bytes used by a single pixel of this format.
+    pub fn bytes_per_pixel(self) -> usize {
+        match self {
+            Self::R8 => 1,
+            Self::Rgb8 => 3,
+            Self::Rgba8 | Self::Depth24 | Self::Depth24Stencil8 => 4,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum TextureFilter {
+    Nearest,
+    Linear,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum TextureWrap {
+    Repeat,
+    MirroredRepeat,
+    ClampToEdge,
+    ClampToBorder,
+}
+
+/// Determines how a texture is sampled.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TextureOptions {
+    pub min_filter: TextureFilter,
+    pub mag_filter: TextureFilter,
+    pub wrap_s: TextureWrap,
+    pub wrap_t: TextureWrap,
+    /// If set, mipmaps are generated for the texture and sampled between
+    /// using this filter.
+    pub mipmaps: Option<TextureFilter>,
+}
+
+impl TextureOptions {
+    /// Options for crisp, pixelated textures, such as sprites.
+    pub fn pixelated() -> Self {
+        Self {
+            min_filter: TextureFilter::Nearest,
+            mag_filter: TextureFilter::Nearest,
+            wrap_s: TextureWrap::ClampToEdge,
+            wrap_t: TextureWrap::ClampToEdge,
+            mipmaps: None,
+        }
+    }
+
+    /// Options for smoothly filtered, mipmapped textures.
+    pub fn smooth() -> Self {
+        Self {
+            min_filter: TextureFilter::Linear,
+            mag_filter: TextureFilter::Linear,
+            wrap_s: TextureWrap::Repeat,
+            wrap_t: TextureWrap::Repeat,
+            mipmaps: Some(TextureFilter::Linear),
+        }
+    }
+
+    pub fn with_wrap(mut self, wrap: TextureWrap) -> Self {
+        self.wrap_s = wrap;
+        self.wrap_t = wrap;
+        self
+    }
+
+    pub fn with_mipmaps(mut self, mipmaps: Option<TextureFilter>) -> Self {
+        self.mipmaps = mipmaps;
+        self
+    }
+}
+
+impl Default for TextureOptions {
+    fn default() -> Self {
+        Self::smooth()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TextureError {
+    InvalidDataLength { expected: usize, found: usize },
+    OutOfBounds,
+    LoadFailed(String),
+    ConvertFailed(String),
+}
+
+impl Display for TextureError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidDataLength { expected, found } => write!(
+                f,
+                "expected {} bytes of pixel data but found {}",
+                expected, found
+            ),
+            Self::OutOfBounds => write!(f, "region is outside of the texture"),
+            Self::LoadFailed(message) => write!(f, "failed to load image: {}", message),
+            Self::ConvertFailed(message) => {
+                write!(f, "failed to convert image to RGBA: {}", message)
+            }
+        }
+    }
+}
+
+impl Error for TextureError {}
+
+/// Copies the pixels of a surface into a tightly packed RGBA buffer.
+pub fn surface_to_rgba(surface: &SurfaceRef) -> Result<Vec<u8>, TextureError> {
+    let surface = surface
+        .convert_format(PixelFormatEnum::RGBA32)
+        .map_err(TextureError::ConvertFailed)?;
+
+    let (width, height) = surface.size();
+    let row_length = width as usize * 4;
+    let pitch = surface.pitch() as usize;
+
+    // Surface rows may be padded, so copy them one at a time
+    Ok(surface.with_lock(|pixels| {
+        pixels
+            .chunks(pitch)
+            .take(height as usize)
+            .flat_map(|row| row[..row_length].iter().copied())
+            .collect()
+    }))
+}
+
+/// A 2D OpenGL texture that is deleted when dropped.
+pub struct Texture2D<RHType: RenderHandler> {
+    render_handler: RHType,
+    handle: u32,
+    format: TextureFormat,
+    width: u32,
+    height: u32,
+    options: TextureOptions,
+    unit: u32,
+}
+
+impl<RHType: RenderHandler> Texture2D<RHType> {
+    /// Creates a texture with uninitialized contents.
+    pub fn new(
+        render_handler: &mut RHType,
+        format: TextureFormat,
+        width: u32,
+        height: u32,
+        options: TextureOptions,
+    ) -> Self {
+        let handle = render_handler.gen_texture();
+        leak::track(ResourceKind::Texture, handle);
+
+        let mut texture = Self {
+            handle,
+            render_handler: render_handler.clone(),
+            format,
+            width,
+            height,
+            options,
+            unit: 0,
+        };
+
+        texture.bind(0);
+        texture
+            .render_handler
+            .tex_image_2d(format, width, height, None);
+        texture.set_options(options);
+
+        texture
+    }
+
+    /// Creates a texture from tightly packed pixel data in the provided format.
+    pub fn from_data(
+        render_handler: &mut RHType,
+        format: TextureFormat,
+        width: u32,
+        height: u32,
+        data: &[u8],
+        options: TextureOptions,
+    ) -> Result<Self, TextureError> {
+        check_data_length(format, width, height, data)?;
+
+        let handle = render_handler.gen_texture();
+        leak::track(ResourceKind::Texture, handle);
+
+        let mut texture = Self {
+            handle,
+            render_handler: render_handler.clone(),
+            format,
+            width,
+            height,
+            options,
+            unit: 0,
+        };
+
+        texture.bind(0);
+        texture
+            .render_handler
+            .tex_image_2d(format, width, height, Some(data));
+        texture.set_options(options);
+
+        Ok(texture)
+    }
+
+    /// Creates a texture from tightly packed RGBA bytes.
+    pub fn from_rgba(
+        render_handler: &mut RHType,
+        width: u32,
+        height: u32,
+        data: &[u8],
+        options: TextureOptions,
+    ) -> Result<Self, TextureError> {
+        Self::from_data(
+            render_handler,
+            TextureFormat::Rgba8,
+            width,
+            height,
+            data,
+            options,
+        )
+    }
+
+    /// Creates an RGBA texture from an SDL surface.
+    pub fn from_surface(
+        render_handler: &mut RHType,
+        surface: &SurfaceRef,
+        options: TextureOptions,
+    ) -> Result<Self, TextureError> {
+        let (width, height) = surface.size();
+        let data = surface_to_rgba(surface)?;
+
+        Self::from_rgba(render_handler, width, height, &data, options)
+    }
+
+    /// Loads an image file (such as a PNG or JPEG) into an RGBA texture.
+    pub fn from_file<P: AsRef<Path>>(
+        render_handler: &mut RHType,
+        path: P,
+        options: TextureOptions,
+    ) -> Result<Self, TextureError> {
+        let surface = Surface::from_file(path).map_err(TextureError::LoadFailed)?;
+
+        Self::from_surface(render_handler, &surface, options)
+    }
+
+    /// Binds this texture to the provided texture unit. Sampler uniforms set
+    /// from this texture will refer to the unit it was most recently bound to.
+    pub fn bind(&mut self, unit: u32) {
+        self.unit = unit;
+        self.render_handler.active_texture(unit);
+        self.render_handler.bind_texture(self.handle);
+    }
+
+    /// The texture unit this texture was most recently bound to, such as for
+    /// setting an array of samplers.
+    pub fn sampler(&self) -> Sampler {
+        Sampler(self.unit)
+    }
+
+    pub fn set_options(&mut self, options: TextureOptions) {
+        self.options = options;
+        self.bind(self.unit);
+        self.render_handler.set_texture_filter(
+            options.min_filter,
+            options.mag_filter,
+            options.mipmaps,
+        );
+        self.render_handler
+            .set_texture_wrap(options.wrap_s, options.wrap_t);
+
+        if options.mipmaps.is_some() {
+            self.render_handler.generate_mipmap();
+        }
+    }
+
+    /// Replaces a region of this texture with tightly packed pixel data in
+    /// this texture's format, regenerating its mipmaps if it has them.
+    pub fn update(
+        &mut self,
+        offset: (u32, u32),
+        size: (u32, u32),
+        data: &[u8],
+    ) -> Result<(), TextureError> {
+        let fits = |offset: u32, size: u32, limit| {
+            offset.checked_add(size).is_some_and(|end| end <= limit)
+        };
+        if !fits(offset.0, size.0, self.width) || !fits(offset.1, size.1, self.height) {
+            return Err(TextureError::OutOfBounds);
+        }
+        check_data_length(self.format, size.0, size.1, data)?;
+
+        self.bind(self.unit);
+        self.render_handler
+            .tex_sub_image_2d(self.format, offset, size, data);
+        if self.options.mipmaps.is_some() {
+            self.render_handler.generate_mipmap();
+        }
+
+        Ok(())
+    }
+
+    /// Reallocates this texture with a new size, discarding its contents.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.width = width;
+        self.height = height;
+
+        self.bind(self.unit);
+        self.render_handler
+            .tex_image_2d(self.format, width, height, None);
+        if self.options.mipmaps.is_some() {
+            self.render_handler.generate_mipmap();
+        }
+    }
+
+    pub fn handle(&self) -> u32 {
+        self.handle
+    }
+
+    pub fn options(&self) -> TextureOptions {
+        self.options
+    }
+
+    pub fn format(&self) -> TextureFormat {
+        self.format
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// The texture unit this texture was most recently bound to.
+    pub fn unit(&self) -> u32 {
+        self.unit
+    }
+}
+
+fn check_data_length(
+    format: TextureFormat,
+    width: u32,
+    height: u32,
+    data: &[u8],
+) -> Result<(), TextureError> {
+    let expected = width as usize * height as usize * format.bytes_per_pixel();
+    if data.len() != expected {
+        return Err(TextureError::InvalidDataLength {
+            expected,
+            found: data.len(),
+        });
+    }
+
+    Ok(())
+}
+
+impl<RHType: RenderHandler> Drop for Texture2D<RHType> {
+    fn drop(&mut self) {
+        log::debug!("Dropping texture {}", self.handle);
+
+        self.render_handler.delete_texture(self.handle);
+        leak::untrack(ResourceKind::Texture, self.handle);
+    }
+}
+
+impl<RHType: RenderHandler> ShaderUniformValue for &Texture2D<RHType> {
+    fn glsl_type(&self) -> GlslType {
+        GlslType::Sampler2D
+    }
+
+    fn uniform<RH: RenderHandler>(&self, render_handler: &mut RH, location: i32) {
+        self.sampler().uniform(render_handler, location);
+    }
+}
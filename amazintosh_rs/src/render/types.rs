@@ -73,3 +73,85 @@ impl From<[f32; 3]> for RGBAColor {
         Self::from_rgb(array[0] as f64, array[1] as f64, array[2] as f64)
     }
 }
+
+/// A 16-bit floating point number, used to store vertex data such as texture
+/// coordinates in half the space of an `f32`.
+#[repr(transparent)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Half(pub u16);
+
+impl Half {
+    /// Converts from an `f32`, rounding to the nearest representable value.
+    pub fn from_f32(value: f32) -> Self {
+        let bits = value.to_bits();
+        let sign = ((bits >> 16) & 0x8000) as u16;
+        let exponent = ((bits >> 23) & 0xff) as i32;
+        let mantissa = bits & 0x7f_ffff;
+
+        // Infinity and NaN
+        if exponent == 0xff {
+            let nan = if mantissa != 0 { 0x200 } else { 0 };
+            return Self(sign | 0x7c00 | nan);
+        }
+
+        let exponent = exponent - 127 + 15;
+        if exponent >= 0x1f {
+            // Too large, so round to infinity
+            return Self(sign | 0x7c00);
+        }
+
+        let (half, shift, mantissa) = if exponent <= 0 {
+            // Too small to be normal, so store as a subnormal or zero
+            if exponent < -10 {
+                return Self(sign);
+            }
+            let mantissa = mantissa | 0x80_0000;
+            let shift = (14 - exponent) as u32;
+            (mantissa >> shift, shift, mantissa)
+        } else {
+            (((exponent as u32) << 10) | (mantissa >> 13), 13, mantissa)
+        };
+
+        // Round half to even using the bits that were shifted away. A carry
+        // into the exponent correctly rounds up to the next power of two.
+        let round_bit = 1 << (shift - 1);
+        let rounded = if mantissa & round_bit != 0 && mantissa & (3 * round_bit - 1) != 0 {
+            half + 1
+        } else {
+            half
+        };
+
+        Self(sign | rounded as u16)
+    }
+
+    pub fn to_f32(self) -> f32 {
+        let sign = ((self.0 & 0x8000) as u32) << 16;
+        let exponent = ((self.0 >> 10) & 0x1f) as u32;
+        let mantissa = (self.0 & 0x3ff) as u32;
+
+        let bits = match exponent {
+            0 if mantissa == 0 => sign,
+            0 => {
+                // Subnormals are the mantissa scaled by 2^-24
+                let value = mantissa as f32 / 16_777_216.0;
+                return if sign != 0 { -value } else { value };
+            }
+            0x1f => sign | 0x7f80_0000 | (mantissa << 13),
+            _ => sign | ((exponent + 127 - 15) << 23) | (mantissa << 13),
+        };
+
+        f32::from_bits(bits)
+    }
+}
+
+impl From<f32> for Half {
+    fn from(value: f32) -> Self {
+        Self::from_f32(value)
+    }
+}
+
+impl From<Half> for f32 {
+    fn from(value: Half) -> Self {
+        value.to_f32()
+    }
+}
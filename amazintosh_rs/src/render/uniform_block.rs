@@ -0,0 +1,147 @@
+use crate::render::buffer::{Buffer, BufferType, BufferUsage};
+use crate::render::RenderHandler;
+use nalgebra::{Matrix4, Vector2, Vector3, Vector4};
+use std::collections::HashMap;
+
+pub use amazintosh_derive::Std140;
+
+/// A `#[repr(C)]` struct whose fields are laid out the way GLSL lays out a
+/// `layout(std140)` uniform block, so it can be uploaded to a uniform buffer
+/// as-is. This should be implemented with `#[derive(Std140)]`, which checks
+/// the offset of every field at compile time.
+///
+/// Blocks are rounded up to a multiple of 16 bytes, so structs that end
+/// early need trailing padding:
+///
+/// ```compile_fail
+/// use amazintosh_rs::nalgebra::{Matrix4, Vector3};
+/// use amazintosh_rs::render::uniform_block::Std140;
+///
+/// #[repr(C)]
+/// #[derive(Copy, Clone, Std140)]
+/// struct Light {
+///     transform: Matrix4<f32>,
+///     color: Vector3<f32>,
+/// }
+/// ```
+pub trait Std140: Sized {}
+
+/// A type that can be a field of a `#[derive(Std140)]` struct, along with
+/// where std140 places it.
+///
+/// Types whose std140 layout differs from their layout in Rust, such as
+/// `mat3` (whose columns are padded to four floats) or `bool`, aren't
+/// supported. Arrays are only supported when their elements are a multiple
+/// of 16 bytes, such as `[Vector4<f32>; N]` or `[Matrix4<f32>; N]`.
+pub trait Std140Field {
+    /// The alignment of the field's offset within a block.
+    const ALIGNMENT: usize;
+    /// The number of bytes std140 uses to store the field.
+    const SIZE: usize;
+}
+
+macro_rules! std140_field {
+    ($type:ty, $alignment:expr, $size:expr) => {
+        impl Std140Field for $type {
+            const ALIGNMENT: usize = $alignment;
+            const SIZE: usize = $size;
+        }
+    };
+}
+
+std140_field!(f32, 4, 4);
+std140_field!(i32, 4, 4);
+std140_field!(u32, 4, 4);
+std140_field!(Vector2<f32>, 8, 8);
+std140_field!(Vector2<i32>, 8, 8);
+std140_field!(Vector2<u32>, 8, 8);
+// Three component vectors are aligned like four component ones, but a
+// scalar can be packed into the space after them
+std140_field!(Vector3<f32>, 16, 12);
+std140_field!(Vector3<i32>, 16, 12);
+std140_field!(Vector3<u32>, 16, 12);
+std140_field!(Vector4<f32>, 16, 16);
+std140_field!(Vector4<i32>, 16, 16);
+std140_field!(Vector4<u32>, 16, 16);
+std140_field!(Matrix4<f32>, 16, 64);
+
+// Every element of an array is rounded up to 16 bytes, so arrays of smaller
+// types don't match their Rust layout, which `#[derive(Std140)]` reports
+impl<Element: Std140Field, const N: usize> Std140Field for [Element; N] {
+    const ALIGNMENT: usize = 16;
+    const SIZE: usize = N * Element::SIZE.div_ceil(16) * 16;
+}
+
+/// Assigns uniform block names to binding points, so that every program with
+/// a block of a given name reads from the same uniform buffer.
+#[derive(Debug, Clone, Default)]
+pub struct UniformBindings {
+    points: HashMap<String, u32>,
+}
+
+impl UniformBindings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Gets the binding point for a block name, assigning the next unused
+    /// one the first time a name is seen.
+    pub fn binding_point(&mut self, name: &str) -> u32 {
+        let next = self.points.len() as u32;
+        *self.points.entry(name.to_owned()).or_insert(next)
+    }
+}
+
+/// The data of a named uniform block, such as the camera's matrices, stored
+/// in a uniform buffer that every program with a block of the same name
+/// reads from.
+pub struct UniformBlock<RHType: RenderHandler, DataType: Std140> {
+    render_handler: RHType,
+    name: String,
+    binding: u32,
+    buffer: Buffer<RHType, DataType>,
+}
+
+impl<RHType: RenderHandler, DataType: Std140> UniformBlock<RHType, DataType> {
+    /// Creates a uniform buffer for the block with the provided name, filled
+    /// with an initial value, and binds it to the block's binding point.
+    pub fn new(render_handler: &mut RHType, name: &str, value: DataType) -> Self {
+        let mut buffer = Buffer::new(render_handler, BufferType::UniformBuffer);
+        buffer.buffer_data(vec![value], BufferUsage::DynamicDraw);
+
+        let mut block = Self {
+            binding: render_handler.uniform_block_binding_point(name),
+            render_handler: render_handler.clone(),
+            name: name.to_owned(),
+            buffer,
+        };
+        block.bind();
+        block
+    }
+
+    /// Uploads a new value, which every program using this block will read
+    /// the next time it is drawn with.
+    pub fn set(&mut self, value: DataType) {
+        self.buffer
+            .update(0, &[value])
+            .expect("uniform block buffers always hold one value");
+    }
+
+    /// Binds the buffer to the block's binding point again, which is only
+    /// needed if another buffer has been bound there since.
+    pub fn bind(&mut self) {
+        self.render_handler.bind_buffer_base(
+            BufferType::UniformBuffer,
+            self.binding,
+            self.buffer.handle(),
+        );
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn binding(&self) -> u32 {
+        self.binding
+    }
+}
@@ -1,6 +1,9 @@
 use super::inner_gl::types::GLenum;
+use super::types::Half;
 use super::GlDataType;
-use crate::render::RenderHandler;
+use nalgebra::{Matrix3, Matrix4, Vector2, Vector3, Vector4};
+
+pub use amazintosh_derive::{InstanceData, Vertex};
 
 #[derive(Debug, Clone)]
 pub struct VertexAttribPointer {
@@ -9,6 +12,9 @@ pub struct VertexAttribPointer {
     pub data_type: GLenum,
     pub normalized: bool,
     pub offset: usize,
+    /// Whether the attribute is read by shaders as an integer (such as an
+    /// `int` or `uvec2`) rather than being converted into a float.
+    pub integer: bool,
 }
 
 impl VertexAttribPointer {
@@ -24,12 +30,185 @@ impl VertexAttribPointer {
             data_type: DataType::gl_data_type(),
             normalized,
             offset,
+            integer: false,
+        }
+    }
+
+    /// Creates a pointer to an attribute that shaders read as integers.
+    pub fn new_integer<DataType: GlDataType>(index: usize, size: usize, offset: usize) -> Self {
+        Self {
+            index,
+            size,
+            data_type: DataType::gl_data_type(),
+            normalized: false,
+            offset,
+            integer: true,
         }
     }
 }
 
+/// Describes the memory layout of a vertex so that it can be read from a
+/// vertex buffer.
+///
+/// This is usually derived, which only works for `#[repr(C)]` structs, since
+/// the offsets of other structs' fields can change:
+///
+/// ```compile_fail
+/// use amazintosh_rs::nalgebra::Vector3;
+/// use amazintosh_rs::render::vertex::Vertex;
+///
+/// #[derive(Copy, Clone, Vertex)]
+/// struct PosVert {
+///     position: Vector3<f32>,
+/// }
+/// ```
+///
+/// Fields can be moved with `#[location = N]`, where `N` must be an integer:
+///
+/// ```compile_fail
+/// use amazintosh_rs::nalgebra::Vector3;
+/// use amazintosh_rs::render::vertex::Vertex;
+///
+/// #[repr(C)]
+/// #[derive(Copy, Clone, Vertex)]
+/// struct PosVert {
+///     #[location(2)]
+///     position: Vector3<f32>,
+/// }
+/// ```
+///
+/// Moved fields can't share locations with other fields, including the extra
+/// locations used by matrices:
+///
+/// ```compile_fail
+/// use amazintosh_rs::nalgebra::{Matrix4, Vector3};
+/// use amazintosh_rs::render::vertex::Vertex;
+///
+/// #[repr(C)]
+/// #[derive(Copy, Clone, Vertex)]
+/// struct BoneVert {
+///     bone: Matrix4<f32>,
+///     #[location = 2]
+///     position: Vector3<f32>,
+/// }
+/// ```
 pub trait Vertex: Sized {
     fn attrib_pointers() -> Vec<VertexAttribPointer>;
+}
+
+/// Data that changes once per instance rather than once per vertex, such as
+/// the position and tint of each house in a single instanced draw.
+///
+/// The locations of derived instance data follow the mesh's vertices, so its
+/// fields can't be moved:
+///
+/// ```compile_fail
+/// use amazintosh_rs::nalgebra::Matrix4;
+/// use amazintosh_rs::render::vertex::InstanceData;
+///
+/// #[repr(C)]
+/// #[derive(Copy, Clone, InstanceData)]
+/// struct House {
+///     #[location = 4]
+///     model: Matrix4<f32>,
+/// }
+/// ```
+pub trait InstanceData: Sized {
+    /// Describes the fields of this type, starting at the first attribute
+    /// location after the ones used by the mesh's vertices.
+    fn attrib_pointers(first_location: usize) -> Vec<VertexAttribPointer>;
+}
+
+/// A type that can be used as a field of a `#[derive(Vertex)]` or
+/// `#[derive(InstanceData)]` struct.
+pub trait VertexAttribute {
+    /// The number of consecutive attribute locations this type takes up.
+    const LOCATIONS: usize = 1;
 
-    fn render<RHType: RenderHandler>(render_handler: &mut RHType, elements: usize);
+    /// Describes this type when it is stored at the provided byte offset
+    /// within a vertex and bound to the provided attribute location.
+    fn attrib_pointers(location: usize, offset: usize) -> Vec<VertexAttribPointer>;
 }
+
+macro_rules! vertex_attribute {
+    ($type:ty, $data_type:ty, $size:expr, integer) => {
+        impl VertexAttribute for $type {
+            fn attrib_pointers(location: usize, offset: usize) -> Vec<VertexAttribPointer> {
+                vec![VertexAttribPointer::new_integer::<$data_type>(
+                    location, $size, offset,
+                )]
+            }
+        }
+    };
+    ($type:ty, $data_type:ty, $size:expr, $normalized:expr) => {
+        impl VertexAttribute for $type {
+            fn attrib_pointers(location: usize, offset: usize) -> Vec<VertexAttribPointer> {
+                vec![VertexAttribPointer::new::<$data_type>(
+                    location,
+                    $size,
+                    $normalized,
+                    offset,
+                )]
+            }
+        }
+    };
+}
+
+vertex_attribute!(f32, f32, 1, false);
+vertex_attribute!(Vector2<f32>, f32, 2, false);
+vertex_attribute!(Vector3<f32>, f32, 3, false);
+vertex_attribute!(Vector4<f32>, f32, 4, false);
+vertex_attribute!([f32; 2], f32, 2, false);
+vertex_attribute!([f32; 3], f32, 3, false);
+vertex_attribute!([f32; 4], f32, 4, false);
+
+vertex_attribute!([Half; 2], Half, 2, false);
+vertex_attribute!([Half; 3], Half, 3, false);
+vertex_attribute!([Half; 4], Half, 4, false);
+
+// Colors and packed normals stored as bytes or shorts are read by shaders as
+// floats from 0 (or -1 when signed) to 1
+vertex_attribute!([u8; 4], u8, 4, true);
+vertex_attribute!([i8; 4], i8, 4, true);
+vertex_attribute!([u16; 2], u16, 2, true);
+vertex_attribute!([i16; 2], i16, 2, true);
+vertex_attribute!([i16; 4], i16, 4, true);
+
+vertex_attribute!(i8, i8, 1, integer);
+vertex_attribute!(u8, u8, 1, integer);
+vertex_attribute!(i16, i16, 1, integer);
+vertex_attribute!(u16, u16, 1, integer);
+vertex_attribute!(i32, i32, 1, integer);
+vertex_attribute!(Vector2<i32>, i32, 2, integer);
+vertex_attribute!(Vector3<i32>, i32, 3, integer);
+vertex_attribute!(Vector4<i32>, i32, 4, integer);
+vertex_attribute!(u32, u32, 1, integer);
+vertex_attribute!(Vector2<u32>, u32, 2, integer);
+vertex_attribute!(Vector3<u32>, u32, 3, integer);
+vertex_attribute!(Vector4<u32>, u32, 4, integer);
+
+// Matrices are too large for a single attribute, so each column gets its own
+// location
+macro_rules! matrix_attribute {
+    ($type:ty, $columns:expr) => {
+        impl VertexAttribute for $type {
+            const LOCATIONS: usize = $columns;
+
+            fn attrib_pointers(location: usize, offset: usize) -> Vec<VertexAttribPointer> {
+                (0..$columns)
+                    .map(|column| {
+                        VertexAttribPointer::new::<f32>(
+                            location + column,
+                            $columns,
+                            false,
+                            offset + column * $columns * std::mem::size_of::<f32>(),
+                        )
+                    })
+                    .collect()
+            }
+        }
+    };
+}
+
+matrix_attribute!(Matrix3<f32>, 3);
+matrix_attribute!(Matrix4<f32>, 4);
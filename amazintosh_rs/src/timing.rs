@@ -0,0 +1,163 @@
+use std::time::Duration;
+
+/// How many ticks run in the time one tick normally takes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum SimulationSpeed {
+    Paused,
+    #[default]
+    Normal,
+    /// Three times as fast as normal.
+    Fast,
+    /// Ten times as fast as normal.
+    Fastest,
+}
+
+impl SimulationSpeed {
+    pub fn multiplier(self) -> u32 {
+        match self {
+            Self::Paused => 0,
+            Self::Normal => 1,
+            Self::Fast => 3,
+            Self::Fastest => 10,
+        }
+    }
+}
+
+/// Averages of how long recent frames and ticks have taken.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct TimingStats {
+    /// The real time between the starts of consecutive frames.
+    pub frame_time: Duration,
+    /// The real time spent running a single tick.
+    pub tick_time: Duration,
+    /// The number of ticks that have run.
+    pub ticks: u64,
+    /// The simulated time that was skipped because the simulation fell too
+    /// far behind to catch up.
+    pub skipped: Duration,
+}
+
+impl TimingStats {
+    /// How much of each new sample goes into the averages. Lower values
+    /// smooth the averages over more frames.
+    const SMOOTHING: f64 = 0.1;
+
+    /// The number of frames per second based on the average frame time.
+    pub fn fps(&self) -> f64 {
+        if self.frame_time == Duration::from_secs(0) {
+            0.0
+        } else {
+            1.0 / self.frame_time.as_secs_f64()
+        }
+    }
+
+    fn average(average: Duration, sample: Duration) -> Duration {
+        if average == Duration::from_secs(0) {
+            sample
+        } else {
+            Duration::from_secs_f64(
+                average.as_secs_f64() * (1.0 - Self::SMOOTHING)
+                    + sample.as_secs_f64() * Self::SMOOTHING,
+            )
+        }
+    }
+}
+
+/// Decides how many fixed length ticks of the simulation to run each frame,
+/// so the simulation advances the same way no matter how fast frames are
+/// drawn.
+///
+/// Each frame, the real time since the previous frame is passed to
+/// `advance`, which returns the number of ticks to run. Time that doesn't
+/// add up to a whole tick carries over to the next frame, and `alpha` says
+/// how far between the last two ticks the frame is, so that rendering can
+/// interpolate between them.
+#[derive(Debug, Clone)]
+pub struct FixedTimestep {
+    tick_duration: Duration,
+    speed: SimulationSpeed,
+    max_frame_time: Duration,
+    accumulator: Duration,
+    stats: TimingStats,
+}
+
+impl FixedTimestep {
+    /// Creates a timestep running the provided number of ticks every second
+    /// at normal speed.
+    pub fn new(ticks_per_second: u32) -> Self {
+        Self {
+            tick_duration: Duration::from_secs(1) / ticks_per_second.max(1),
+            speed: SimulationSpeed::Normal,
+            max_frame_time: Duration::from_millis(250),
+            accumulator: Duration::from_secs(0),
+            stats: TimingStats::default(),
+        }
+    }
+
+    pub fn with_speed(mut self, speed: SimulationSpeed) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// Sets the most real time a single frame can advance the simulation by.
+    /// When a frame takes longer than this, such as when ticks take longer
+    /// than the time they simulate, the rest is skipped rather than trying
+    /// to catch up and falling further behind every frame. This defaults to
+    /// a quarter of a second.
+    pub fn with_max_frame_time(mut self, max_frame_time: Duration) -> Self {
+        self.max_frame_time = max_frame_time;
+        self
+    }
+
+    /// The length of simulated time each tick advances by.
+    pub fn tick_duration(&self) -> Duration {
+        self.tick_duration
+    }
+
+    pub fn speed(&self) -> SimulationSpeed {
+        self.speed
+    }
+
+    pub fn set_speed(&mut self, speed: SimulationSpeed) {
+        self.speed = speed;
+    }
+
+    /// Adds the real time since the previous frame, returning how many ticks
+    /// should run before this frame is drawn.
+    pub fn advance(&mut self, elapsed: Duration) -> u32 {
+        self.stats.frame_time = TimingStats::average(self.stats.frame_time, elapsed);
+
+        let multiplier = self.speed.multiplier();
+        if elapsed > self.max_frame_time {
+            self.stats.skipped += (elapsed - self.max_frame_time) * multiplier;
+        }
+        self.accumulator += elapsed.min(self.max_frame_time) * multiplier;
+
+        let ticks = (self.accumulator.as_nanos() / self.tick_duration.as_nanos()) as u32;
+        self.accumulator -= self.tick_duration * ticks;
+        ticks
+    }
+
+    /// Records how long a tick took to run, for the tick time statistics.
+    pub fn record_tick(&mut self, duration: Duration) {
+        self.stats.tick_time = TimingStats::average(self.stats.tick_time, duration);
+        self.stats.ticks += 1;
+    }
+
+    /// How far the current frame is between the previous tick and the next
+    /// one, from 0 to 1.
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator.as_secs_f64() / self.tick_duration.as_secs_f64()) as f32
+    }
+
+    pub fn stats(&self) -> &TimingStats {
+        &self.stats
+    }
+}
+
+impl Default for FixedTimestep {
+    /// Twenty ticks every second.
+    fn default() -> Self {
+        Self::new(20)
+    }
+}
@@ -1,7 +1,13 @@
+use crate::render::framebuffer::FramebufferHandler;
+use crate::render::leak;
 use crate::render::Gl;
+use crate::timing::FixedTimestep;
+use crate::window_config::{DisplayMode, Vsync, WindowConfig};
 use sdl2::event::{Event, WindowEvent};
+use sdl2::video::{FullscreenType, SwapInterval};
 use std::error::Error;
 use std::fmt::{Display, Formatter};
+use std::time::{Duration, Instant};
 
 pub trait AWindow<ContextType, WindowEventType> {
     type ErrorType;
@@ -12,96 +18,213 @@ pub trait AWindow<ContextType, WindowEventType> {
 
     fn size(&self) -> (usize, usize);
 
+    /// The size of the window in pixels, which is larger than its size on
+    /// high DPI displays when the window was created with `high_dpi`.
+    fn drawable_size(&self) -> (usize, usize) {
+        self.size()
+    }
+
+    fn set_display_mode(&mut self, display_mode: DisplayMode) -> Result<(), Self::ErrorType>;
+
+    fn set_vsync(&mut self, vsync: Vsync) -> Result<(), Self::ErrorType>;
+
+    /// The window's current configuration, including any changes made since
+    /// it was created, such as to save as the player's settings.
+    fn config(&self) -> WindowConfig;
+
     fn hide(&mut self) -> Result<(), Self::ErrorType>;
 
     fn ctx(&mut self) -> Option<ContextType>;
 
+    /// Decides how many times the loop calls `update` each frame, and how
+    /// far between ticks each frame is drawn.
+    fn timestep(&self) -> &FixedTimestep;
+
+    fn timestep_mut(&mut self) -> &mut FixedTimestep;
+
+    /// Runs until a callback returns true. Every frame, `update` is called
+    /// once for each tick of the timestep with the simulated time a tick
+    /// lasts, then the frame is drawn between `pre_render` and `post_render`,
+    /// and finally `event_handler` is called for each new event.
     fn start_loop<
         DataType,
+        Update: Fn(&mut Self, &mut DataType, Duration) -> bool,
         PreRender: Fn(&mut Self, &mut DataType) -> bool,
         PostRender: Fn(&mut Self, &mut DataType) -> bool,
         EventHandler: Fn(&mut Self, &mut DataType, WindowEventType) -> bool,
     >(
         self,
         data: DataType,
+        update: Update,
         pre_render: PreRender,
         post_render: PostRender,
         event_handler: EventHandler,
     ) -> Result<(), Self::ErrorType>;
 }
 
+/// An error message returned by SDL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SdlError(pub String);
+
+impl Display for SdlError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Error for SdlError {}
+
 #[derive(Debug)]
 pub enum SdlWindowError {
-    ContextInitFailed(String),
-    VideoSubsystem(String),
+    ContextInitFailed(SdlError),
+    VideoSubsystem(SdlError),
     WindowCreateFailed(sdl2::video::WindowBuildError),
-    GlContextCreateFailed(String),
+    GlContextCreateFailed(SdlError),
     IntegerOverflow,
-    InitEventPumpFailed(String),
+    InitEventPumpFailed(SdlError),
     NulError,
-    FullscreenErr(String),
+    FullscreenErr(SdlError),
+    SwapIntervalFailed(SdlError),
 }
 
 impl Display for SdlWindowError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:?}", self)
+        match self {
+            Self::ContextInitFailed(_) => write!(f, "failed to initialize SDL"),
+            Self::VideoSubsystem(_) => write!(f, "failed to initialize the SDL video subsystem"),
+            Self::WindowCreateFailed(_) => write!(f, "failed to create the window"),
+            Self::GlContextCreateFailed(_) => write!(f, "failed to create an OpenGL context"),
+            Self::IntegerOverflow => write!(f, "window size is too large"),
+            Self::InitEventPumpFailed(_) => write!(f, "failed to start polling events"),
+            Self::NulError => write!(f, "window title contains a nul byte"),
+            Self::FullscreenErr(_) => write!(f, "failed to change fullscreen mode"),
+            Self::SwapIntervalFailed(_) => write!(f, "failed to change vsync"),
+        }
     }
 }
 
-impl Error for SdlWindowError {}
+impl Error for SdlWindowError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::ContextInitFailed(error)
+            | Self::VideoSubsystem(error)
+            | Self::GlContextCreateFailed(error)
+            | Self::InitEventPumpFailed(error)
+            | Self::FullscreenErr(error)
+            | Self::SwapIntervalFailed(error) => Some(error),
+            Self::WindowCreateFailed(error) => Some(error),
+            Self::IntegerOverflow | Self::NulError => None,
+        }
+    }
+}
 
+/// A window with an OpenGL context.
+///
+/// Dropping the window destroys its context along with every object in it,
+/// so any wrappers that are still alive, such as meshes or textures, can no
+/// longer be used. They can still be dropped safely, since the window marks
+/// its context as destroyed first.
 pub struct SdlWindow {
     sdl_context: sdl2::Sdl,
-    _video_subsystem: sdl2::VideoSubsystem,
+    video_subsystem: sdl2::VideoSubsystem,
     window: sdl2::video::Window,
-    _gl_ctx: sdl2::video::GLContext,
+    // The wrapper is dropped before the context, since dropping its last
+    // clone makes OpenGL calls
     gl: Gl,
+    _gl_ctx: sdl2::video::GLContext,
+    timestep: FixedTimestep,
+    config: WindowConfig,
+}
+
+impl Drop for SdlWindow {
+    fn drop(&mut self) {
+        self.gl.destroy_context();
+    }
 }
 
 impl SdlWindow {
     pub fn new(title: &str, width: usize, height: usize) -> Result<Self, SdlWindowError> {
+        Self::from_config(WindowConfig::new(title, width, height))
+    }
+
+    /// Creates a window that is never shown, for rendering offscreen where a
+    /// display is available.
+    pub fn hidden(title: &str, width: usize, height: usize) -> Result<Self, SdlWindowError> {
+        Self::from_config(WindowConfig::new(title, width, height).with_hidden(true))
+    }
+
+    pub fn from_config(config: WindowConfig) -> Result<Self, SdlWindowError> {
         // Initialize SDL2
-        let sdl_context = sdl2::init().map_err(|e| SdlWindowError::ContextInitFailed(e))?;
+        let sdl_context =
+            sdl2::init().map_err(|e| SdlWindowError::ContextInitFailed(SdlError(e)))?;
 
         // Get SDL2's video subsystem to get information about video/rendering
         let video_subsystem = sdl_context
             .video()
-            .map_err(|e| SdlWindowError::VideoSubsystem(e))?;
+            .map_err(|e| SdlWindowError::VideoSubsystem(SdlError(e)))?;
 
-        // Set the GL versions
+        // Set the GL versions and the default framebuffer's format
         let gl_attrs = video_subsystem.gl_attr();
         gl_attrs.set_accelerated_visual(true);
         gl_attrs.set_context_version(3, 3);
         gl_attrs.set_context_profile(sdl2::video::GLProfile::Core);
+        gl_attrs.set_depth_size(config.depth_bits);
+        gl_attrs.set_stencil_size(config.stencil_bits);
+        if config.samples > 0 {
+            gl_attrs.set_multisample_buffers(1);
+            gl_attrs.set_multisample_samples(config.samples);
+        }
+        // Debug contexts report errors through debug output
+        if config.debug_context {
+            gl_attrs.set_context_flags().debug().set();
+        }
 
         // Create the window
-        let mut window = video_subsystem
-            .window(title, width as u32, height as u32)
-            .resizable()
-            .opengl()
+        let mut builder =
+            video_subsystem.window(&config.title, config.size.0 as u32, config.size.1 as u32);
+        builder.resizable().opengl();
+        if config.hidden {
+            builder.hidden();
+        }
+        if config.high_dpi {
+            builder.allow_highdpi();
+        }
+        let mut window = builder
             .build()
-            .map_err(|e| SdlWindowError::WindowCreateFailed(e))?;
+            .map_err(SdlWindowError::WindowCreateFailed)?;
 
-        window
-            .set_fullscreen(sdl2::video::FullscreenType::Off)
-            .map_err(|e| SdlWindowError::FullscreenErr(e))?;
+        if let Some((width, height)) = config.min_size {
+            window
+                .set_minimum_size(width as u32, height as u32)
+                .map_err(|_| SdlWindowError::IntegerOverflow)?;
+        }
 
         // Get the OpenGL context for the window
         let gl_ctx = window
             .gl_create_context()
-            .map_err(|e| SdlWindowError::GlContextCreateFailed(e))?;
+            .map_err(|e| SdlWindowError::GlContextCreateFailed(SdlError(e)))?;
 
         // Create an Amazintosh GL wrapper
-        let gl = super::render::Gl::new(|s| video_subsystem.gl_get_proc_address(s) as *const _);
+        let mut gl = super::render::Gl::new(|s| video_subsystem.gl_get_proc_address(s) as *const _);
+        gl.set_default_framebuffer_size(window.drawable_size());
 
-        // Return this wrapper and keep everything alive as long as it needs to be
-        Ok(Self {
+        // Keep everything alive as long as it needs to be
+        let mut sdl_window = Self {
             sdl_context,
-            _video_subsystem: video_subsystem,
+            video_subsystem,
             window,
             _gl_ctx: gl_ctx,
             gl,
-        })
+            timestep: FixedTimestep::default(),
+            config: config.clone(),
+        };
+
+        // The swap interval belongs to the context, so it can only be set
+        // once the context exists
+        sdl_window.set_display_mode(config.display_mode)?;
+        sdl_window.set_vsync(config.vsync)?;
+
+        Ok(sdl_window)
     }
 }
 
@@ -111,7 +234,9 @@ impl AWindow<Gl, sdl2::event::Event> for SdlWindow {
     fn set_title(&mut self, title: &str) -> Result<(), Self::ErrorType> {
         self.window
             .set_title(title)
-            .map_err(|_| SdlWindowError::NulError)
+            .map_err(|_| SdlWindowError::NulError)?;
+        self.config.title = title.to_owned();
+        Ok(())
     }
 
     fn set_size(&mut self, size: (usize, usize)) -> Result<(), Self::ErrorType> {
@@ -125,6 +250,61 @@ impl AWindow<Gl, sdl2::event::Event> for SdlWindow {
         (w as usize, h as usize)
     }
 
+    fn drawable_size(&self) -> (usize, usize) {
+        let (w, h) = self.window.drawable_size();
+        (w as usize, h as usize)
+    }
+
+    fn set_display_mode(&mut self, display_mode: DisplayMode) -> Result<(), Self::ErrorType> {
+        let fullscreen_type = match display_mode {
+            DisplayMode::Windowed => FullscreenType::Off,
+            DisplayMode::Borderless => FullscreenType::Desktop,
+            DisplayMode::Fullscreen => FullscreenType::True,
+        };
+
+        // Remember the windowed size, since the window takes the size of the
+        // screen while it is fullscreen
+        if self.config.display_mode == DisplayMode::Windowed {
+            self.config.size = self.size();
+        }
+
+        self.window
+            .set_fullscreen(fullscreen_type)
+            .map_err(|e| SdlWindowError::FullscreenErr(SdlError(e)))?;
+        self.config.display_mode = display_mode;
+        Ok(())
+    }
+
+    fn set_vsync(&mut self, vsync: Vsync) -> Result<(), Self::ErrorType> {
+        let interval = match vsync {
+            Vsync::Off => SwapInterval::Immediate,
+            Vsync::On => SwapInterval::VSync,
+            Vsync::Adaptive => SwapInterval::LateSwapTearing,
+        };
+
+        let result = self.video_subsystem.gl_set_swap_interval(interval);
+        match result {
+            Err(e) if vsync == Vsync::Adaptive => {
+                log::warn!("Adaptive vsync isn't supported, using vsync instead: {}", e);
+                self.video_subsystem
+                    .gl_set_swap_interval(SwapInterval::VSync)
+                    .map_err(|e| SdlWindowError::SwapIntervalFailed(SdlError(e)))?;
+            }
+            result => result.map_err(|e| SdlWindowError::SwapIntervalFailed(SdlError(e)))?,
+        }
+
+        self.config.vsync = vsync;
+        Ok(())
+    }
+
+    fn config(&self) -> WindowConfig {
+        let mut config = self.config.clone();
+        if config.display_mode == DisplayMode::Windowed {
+            config.size = self.size();
+        }
+        config
+    }
+
     fn hide(&mut self) -> Result<(), Self::ErrorType> {
         self.window.hide();
 
@@ -136,14 +316,24 @@ impl AWindow<Gl, sdl2::event::Event> for SdlWindow {
         Some(self.gl.clone())
     }
 
+    fn timestep(&self) -> &FixedTimestep {
+        &self.timestep
+    }
+
+    fn timestep_mut(&mut self) -> &mut FixedTimestep {
+        &mut self.timestep
+    }
+
     fn start_loop<
         DataType,
+        Update: Fn(&mut Self, &mut DataType, Duration) -> bool,
         PreRender: Fn(&mut Self, &mut DataType) -> bool,
         PostRender: Fn(&mut Self, &mut DataType) -> bool,
         EventHandler: Fn(&mut Self, &mut DataType, sdl2::event::Event) -> bool,
     >(
         mut self,
         mut data: DataType,
+        update: Update,
         pre_render: PreRender,
         post_render: PostRender,
         event_handler: EventHandler,
@@ -152,10 +342,19 @@ impl AWindow<Gl, sdl2::event::Event> for SdlWindow {
         let mut event_pump = self
             .sdl_context
             .event_pump()
-            .map_err(|e| SdlWindowError::InitEventPumpFailed(e))?;
+            .map_err(|e| SdlWindowError::InitEventPumpFailed(SdlError(e)))?;
+
+        let mut last_frame = Instant::now();
 
         // Start the loop and label it to allow breaking out of it
         'running: loop {
+            // Run as many ticks as the time since the last frame calls for
+            let now = Instant::now();
+            if run_ticks(&mut self, &mut data, now - last_frame, &update) {
+                break 'running;
+            }
+            last_frame = now;
+
             // Run the pre-mod callback
             if pre_render(&mut self, &mut data) {
                 break 'running;
@@ -171,15 +370,21 @@ impl AWindow<Gl, sdl2::event::Event> for SdlWindow {
 
             // Run the event handler for all the events
             for event in event_pump.poll_iter() {
-                match &event {
-                    // Update GL viewport on resize
-                    &Event::Window {
-                        win_event: WindowEvent::Resized(w, h),
-                        ..
-                    } => unsafe {
-                        self.gl.0.Viewport(0, 0, w, h);
-                    },
-                    _ => {}
+                // Update GL viewport on resize and let window-sized
+                // framebuffers know that they need to resize. SDL only sends
+                // `Resized` when the user resizes the window, while
+                // `SizeChanged` is sent for every change, including changes
+                // to the display mode. The event has the size in screen
+                // coordinates, which is smaller than the framebuffer on high
+                // DPI displays.
+                if let Event::Window {
+                    win_event: WindowEvent::SizeChanged(..),
+                    ..
+                } = event
+                {
+                    let (w, h) = self.window.drawable_size();
+                    self.gl.viewport(0, 0, w, h);
+                    self.gl.set_default_framebuffer_size((w, h));
                 }
 
                 if event_handler(&mut self, &mut data, event) {
@@ -188,7 +393,36 @@ impl AWindow<Gl, sdl2::event::Event> for SdlWindow {
             }
         }
 
+        // Everything the loop rendered with should be deleted by now, while
+        // the context still exists
+        drop(data);
+        leak::report_leaks();
+
         // Done with the loop and no errors
         Ok(())
     }
 }
+
+/// Runs as many ticks as the window's timestep calls for after `elapsed`
+/// time, returning whether `update` asked to stop.
+pub(crate) fn run_ticks<ContextType, EventType, WindowType, DataType, Update>(
+    window: &mut WindowType,
+    data: &mut DataType,
+    elapsed: Duration,
+    update: &Update,
+) -> bool
+where
+    WindowType: AWindow<ContextType, EventType>,
+    Update: Fn(&mut WindowType, &mut DataType, Duration) -> bool,
+{
+    let ticks = window.timestep_mut().advance(elapsed);
+    let tick_duration = window.timestep().tick_duration();
+    for _ in 0..ticks {
+        let tick_start = Instant::now();
+        if update(window, data, tick_duration) {
+            return true;
+        }
+        window.timestep_mut().record_tick(tick_start.elapsed());
+    }
+    false
+}
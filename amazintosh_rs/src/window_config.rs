@@ -0,0 +1,289 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io::{self, ErrorKind};
+use std::path::Path;
+
+/// How a window covers the screen.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum DisplayMode {
+    #[default]
+    Windowed,
+    /// Covers the whole screen without changing the display's resolution.
+    Borderless,
+    /// Takes over the display, changing its resolution to the window's size.
+    Fullscreen,
+}
+
+impl DisplayMode {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Windowed => "windowed",
+            Self::Borderless => "borderless",
+            Self::Fullscreen => "fullscreen",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "windowed" => Some(Self::Windowed),
+            "borderless" => Some(Self::Borderless),
+            "fullscreen" => Some(Self::Fullscreen),
+            _ => None,
+        }
+    }
+}
+
+/// Whether swapping buffers waits for the display to refresh.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum Vsync {
+    /// Swaps immediately, which can tear.
+    Off,
+    #[default]
+    On,
+    /// Waits for the display unless the frame is late, in which case it
+    /// swaps immediately rather than waiting for the next refresh. Where
+    /// this isn't supported, `On` is used instead.
+    Adaptive,
+}
+
+impl Vsync {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::On => "on",
+            Self::Adaptive => "adaptive",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "off" => Some(Self::Off),
+            "on" => Some(Self::On),
+            "adaptive" => Some(Self::Adaptive),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SettingsError {
+    ReadFailed(io::Error),
+    WriteFailed(io::Error),
+    /// A line that isn't a comment or a `key = value` pair.
+    InvalidLine(usize),
+    InvalidValue {
+        line: usize,
+        key: String,
+        value: String,
+    },
+}
+
+impl Display for SettingsError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ReadFailed(_) => write!(f, "failed to read settings"),
+            Self::WriteFailed(_) => write!(f, "failed to write settings"),
+            Self::InvalidLine(line) => write!(f, "line {} of the settings is not a setting", line),
+            Self::InvalidValue { line, key, value } => write!(
+                f,
+                "invalid value {:?} for {} on line {} of the settings",
+                value, key, line
+            ),
+        }
+    }
+}
+
+impl Error for SettingsError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::ReadFailed(error) | Self::WriteFailed(error) => Some(error),
+            Self::InvalidLine(_) | Self::InvalidValue { .. } => None,
+        }
+    }
+}
+
+/// Everything about a window that is decided when it is created.
+///
+/// The settings players can change, which are the size, display mode, vsync,
+/// sample count and high DPI, can be saved to and loaded from a settings
+/// file. Everything else is up to the game, so that old settings files can't
+/// override what it needs.
+///
+/// Settings files have one `key = value` pair on each line, with lines
+/// starting with `#` ignored:
+///
+/// ```text
+/// size = 1280x720
+/// display_mode = borderless
+/// vsync = adaptive
+/// samples = 4
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WindowConfig {
+    pub title: String,
+    /// The size of the window while windowed, in screen coordinates, which
+    /// can be smaller than its size in pixels on high DPI displays.
+    pub size: (usize, usize),
+    pub min_size: Option<(usize, usize)>,
+    pub display_mode: DisplayMode,
+    pub vsync: Vsync,
+    /// The number of samples for multisample anti-aliasing, or 0 to turn it
+    /// off.
+    pub samples: u8,
+    pub depth_bits: u8,
+    pub stencil_bits: u8,
+    /// Whether to draw at the display's full resolution when it has more
+    /// pixels than screen coordinates, rather than scaling the window up.
+    pub high_dpi: bool,
+    /// Whether to ask for an OpenGL context that reports errors through debug
+    /// output, which is slower. This defaults to true in debug builds.
+    pub debug_context: bool,
+    /// Whether the window is never shown.
+    pub hidden: bool,
+}
+
+impl WindowConfig {
+    /// Creates a resizable, windowed configuration with vsync, a 24 bit
+    /// depth buffer and an 8 bit stencil buffer.
+    pub fn new(title: &str, width: usize, height: usize) -> Self {
+        Self {
+            title: title.to_owned(),
+            size: (width, height),
+            min_size: None,
+            display_mode: DisplayMode::Windowed,
+            vsync: Vsync::On,
+            samples: 0,
+            depth_bits: 24,
+            stencil_bits: 8,
+            high_dpi: false,
+            debug_context: cfg!(debug_assertions),
+            hidden: false,
+        }
+    }
+
+    pub fn with_min_size(mut self, width: usize, height: usize) -> Self {
+        self.min_size = Some((width, height));
+        self
+    }
+
+    pub fn with_display_mode(mut self, display_mode: DisplayMode) -> Self {
+        self.display_mode = display_mode;
+        self
+    }
+
+    pub fn with_vsync(mut self, vsync: Vsync) -> Self {
+        self.vsync = vsync;
+        self
+    }
+
+    pub fn with_samples(mut self, samples: u8) -> Self {
+        self.samples = samples;
+        self
+    }
+
+    pub fn with_depth_bits(mut self, depth_bits: u8) -> Self {
+        self.depth_bits = depth_bits;
+        self
+    }
+
+    pub fn with_stencil_bits(mut self, stencil_bits: u8) -> Self {
+        self.stencil_bits = stencil_bits;
+        self
+    }
+
+    pub fn with_high_dpi(mut self, high_dpi: bool) -> Self {
+        self.high_dpi = high_dpi;
+        self
+    }
+
+    pub fn with_debug_context(mut self, debug_context: bool) -> Self {
+        self.debug_context = debug_context;
+        self
+    }
+
+    pub fn with_hidden(mut self, hidden: bool) -> Self {
+        self.hidden = hidden;
+        self
+    }
+
+    /// Overrides this configuration with the settings saved at `path`. A
+    /// missing file isn't an error, since there is nothing to load until the
+    /// settings are first saved.
+    pub fn load<P: AsRef<Path>>(mut self, path: P) -> Result<Self, SettingsError> {
+        match std::fs::read_to_string(path) {
+            Ok(settings) => {
+                self.apply_settings(&settings)?;
+                Ok(self)
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(self),
+            Err(e) => Err(SettingsError::ReadFailed(e)),
+        }
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), SettingsError> {
+        std::fs::write(path, self.to_settings()).map_err(SettingsError::WriteFailed)
+    }
+
+    /// Formats the player's settings as the contents of a settings file.
+    pub fn to_settings(&self) -> String {
+        format!(
+            "size = {}x{}\n\
+             display_mode = {}\n\
+             vsync = {}\n\
+             samples = {}\n\
+             high_dpi = {}\n",
+            self.size.0,
+            self.size.1,
+            self.display_mode.name(),
+            self.vsync.name(),
+            self.samples,
+            self.high_dpi,
+        )
+    }
+
+    /// Overrides this configuration with the contents of a settings file.
+    /// Settings that are missing are left as they are, and unknown settings
+    /// are ignored with a warning so that older versions can read newer
+    /// files. Nothing is changed if any setting is invalid.
+    pub fn apply_settings(&mut self, settings: &str) -> Result<(), SettingsError> {
+        let mut config = self.clone();
+
+        for (index, line) in settings.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (key, value) = line
+                .split_once('=')
+                .ok_or(SettingsError::InvalidLine(line_number))?;
+            let (key, value) = (key.trim(), value.trim());
+            let invalid = || SettingsError::InvalidValue {
+                line: line_number,
+                key: key.to_owned(),
+                value: value.to_owned(),
+            };
+
+            match key {
+                "size" => config.size = parse_size(value).ok_or_else(invalid)?,
+                "display_mode" => {
+                    config.display_mode = DisplayMode::from_name(value).ok_or_else(invalid)?
+                }
+                "vsync" => config.vsync = Vsync::from_name(value).ok_or_else(invalid)?,
+                "samples" => config.samples = value.parse().map_err(|_| invalid())?,
+                "high_dpi" => config.high_dpi = value.parse().map_err(|_| invalid())?,
+                _ => log::warn!("Ignoring unknown setting {:?}", key),
+            }
+        }
+
+        *self = config;
+        Ok(())
+    }
+}
+
+/// Parses a size written as `WIDTHxHEIGHT`, such as `1280x720`.
+fn parse_size(value: &str) -> Option<(usize, usize)> {
+    let (width, height) = value.split_once('x')?;
+    Some((width.trim().parse().ok()?, height.trim().parse().ok()?))
+}
@@ -0,0 +1,270 @@
+use amazintosh_rs::application::{Application, Control, Runner};
+use amazintosh_rs::error::EngineError;
+use amazintosh_rs::render::framebuffer::FramebufferHandler;
+use amazintosh_rs::render::mock::MockRenderHandler;
+use amazintosh_rs::sdl2::event::{Event, WindowEvent};
+use amazintosh_rs::timing::FixedTimestep;
+use amazintosh_rs::window::AWindow;
+use amazintosh_rs::window_config::{DisplayMode, Vsync, WindowConfig};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+/// A window that runs a fixed number of 50ms frames, sending each frame's
+/// events after it is drawn.
+struct ScriptedWindow {
+    handler: MockRenderHandler,
+    timestep: FixedTimestep,
+    frames: Vec<Vec<Event>>,
+}
+
+impl AWindow<MockRenderHandler, Event> for ScriptedWindow {
+    type ErrorType = EngineError;
+
+    fn set_title(&mut self, _title: &str) -> Result<(), EngineError> {
+        Ok(())
+    }
+
+    fn set_size(&mut self, _size: (usize, usize)) -> Result<(), EngineError> {
+        Ok(())
+    }
+
+    fn size(&self) -> (usize, usize) {
+        (300, 300)
+    }
+
+    fn set_display_mode(&mut self, _display_mode: DisplayMode) -> Result<(), EngineError> {
+        Ok(())
+    }
+
+    fn set_vsync(&mut self, _vsync: Vsync) -> Result<(), EngineError> {
+        Ok(())
+    }
+
+    fn config(&self) -> WindowConfig {
+        WindowConfig::new("", 300, 300)
+    }
+
+    fn hide(&mut self) -> Result<(), EngineError> {
+        Ok(())
+    }
+
+    fn ctx(&mut self) -> Option<MockRenderHandler> {
+        Some(self.handler.clone())
+    }
+
+    fn timestep(&self) -> &FixedTimestep {
+        &self.timestep
+    }
+
+    fn timestep_mut(&mut self) -> &mut FixedTimestep {
+        &mut self.timestep
+    }
+
+    fn start_loop<
+        DataType,
+        Update: Fn(&mut Self, &mut DataType, Duration) -> bool,
+        PreRender: Fn(&mut Self, &mut DataType) -> bool,
+        PostRender: Fn(&mut Self, &mut DataType) -> bool,
+        EventHandler: Fn(&mut Self, &mut DataType, Event) -> bool,
+    >(
+        mut self,
+        mut data: DataType,
+        update: Update,
+        pre_render: PreRender,
+        post_render: PostRender,
+        event_handler: EventHandler,
+    ) -> Result<(), EngineError> {
+        let frames = std::mem::take(&mut self.frames);
+        'running: for events in frames {
+            let dt = self.timestep.tick_duration();
+            for _ in 0..self.timestep.advance(Duration::from_millis(50)) {
+                if update(&mut self, &mut data, dt) {
+                    break 'running;
+                }
+            }
+            if pre_render(&mut self, &mut data) || post_render(&mut self, &mut data) {
+                break;
+            }
+            for event in events {
+                if let Event::Window {
+                    win_event: WindowEvent::SizeChanged(w, h),
+                    ..
+                } = event
+                {
+                    self.handler.viewport(0, 0, w as u32, h as u32);
+                    self.handler
+                        .set_default_framebuffer_size((w as u32, h as u32));
+                }
+                if event_handler(&mut self, &mut data, event) {
+                    break 'running;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn window(frames: Vec<Vec<Event>>) -> ScriptedWindow {
+    ScriptedWindow {
+        handler: MockRenderHandler::new(),
+        timestep: FixedTimestep::new(40),
+        frames,
+    }
+}
+
+fn close() -> Event {
+    Event::Window {
+        timestamp: 0,
+        window_id: 0,
+        win_event: WindowEvent::Close,
+    }
+}
+
+fn resize(width: i32, height: i32) -> Event {
+    Event::Window {
+        timestamp: 0,
+        window_id: 0,
+        win_event: WindowEvent::SizeChanged(width, height),
+    }
+}
+
+/// Records every hook that is called.
+struct Recorder {
+    calls: Rc<RefCell<Vec<String>>>,
+    quit_after_ticks: Option<usize>,
+    ticks: usize,
+}
+
+thread_local! {
+    static CALLS: Rc<RefCell<Vec<String>>> = Rc::new(RefCell::new(Vec::new()));
+    static QUIT_AFTER_TICKS: RefCell<Option<usize>> = const { RefCell::new(None) };
+}
+
+impl Application<MockRenderHandler> for Recorder {
+    fn init<WindowType: AWindow<MockRenderHandler, Event>>(
+        window: &mut WindowType,
+        _render_handler: &mut MockRenderHandler,
+    ) -> Result<Self, EngineError> {
+        let calls = CALLS.with(Rc::clone);
+        calls.borrow_mut().push(format!("init {:?}", window.size()));
+        Ok(Self {
+            calls,
+            quit_after_ticks: QUIT_AFTER_TICKS.with(|quit| *quit.borrow()),
+            ticks: 0,
+        })
+    }
+
+    fn update<WindowType: AWindow<MockRenderHandler, Event>>(
+        &mut self,
+        _window: &mut WindowType,
+        dt: Duration,
+    ) -> Control {
+        self.ticks += 1;
+        self.calls
+            .borrow_mut()
+            .push(format!("update {}", dt.as_millis()));
+        if Some(self.ticks) == self.quit_after_ticks {
+            Control::Quit
+        } else {
+            Control::Continue
+        }
+    }
+
+    fn render<WindowType: AWindow<MockRenderHandler, Event>>(
+        &mut self,
+        _window: &mut WindowType,
+        _render_handler: &mut MockRenderHandler,
+        alpha: f32,
+    ) {
+        self.calls.borrow_mut().push(format!("render {}", alpha));
+    }
+
+    fn on_resize<WindowType: AWindow<MockRenderHandler, Event>>(
+        &mut self,
+        _window: &mut WindowType,
+        render_handler: &mut MockRenderHandler,
+        size: (u32, u32),
+    ) {
+        let viewport = render_handler.state().viewport;
+        self.calls
+            .borrow_mut()
+            .push(format!("resize {:?} {:?}", size, viewport));
+    }
+
+    fn on_quit<WindowType: AWindow<MockRenderHandler, Event>>(&mut self, _window: &mut WindowType) {
+        self.calls.borrow_mut().push("quit".to_owned());
+    }
+}
+
+fn run(frames: Vec<Vec<Event>>, quit_after_ticks: Option<usize>) -> Vec<String> {
+    CALLS.with(|calls| calls.borrow_mut().clear());
+    QUIT_AFTER_TICKS.with(|quit| *quit.borrow_mut() = quit_after_ticks);
+
+    Runner::new(window(frames))
+        .unwrap()
+        .run::<Recorder>()
+        .unwrap();
+    CALLS.with(|calls| calls.borrow().clone())
+}
+
+#[test]
+fn hooks_are_called_in_order() {
+    let calls = run(vec![vec![], vec![resize(640, 480)], vec![close()]], None);
+
+    assert_eq!(
+        calls,
+        vec![
+            "init (300, 300)",
+            "update 25",
+            "update 25",
+            "render 0",
+            "update 25",
+            "update 25",
+            "render 0",
+            "resize (640, 480) (0, 0, 640, 480)",
+            "update 25",
+            "update 25",
+            "render 0",
+            "quit",
+        ]
+    );
+}
+
+#[test]
+fn updates_can_quit() {
+    let calls = run(vec![vec![]; 10], Some(3));
+
+    assert_eq!(calls.iter().filter(|call| *call == "quit").count(), 1);
+    assert_eq!(calls.last().unwrap(), "quit");
+    assert_eq!(
+        calls
+            .iter()
+            .filter(|call| call.starts_with("update"))
+            .count(),
+        3
+    );
+}
+
+#[test]
+fn user_resizes_are_only_handled_once() {
+    // SDL sends both events when the user resizes the window, but only
+    // `SizeChanged` when the size is changed any other way
+    let user_resize = Event::Window {
+        timestamp: 0,
+        window_id: 0,
+        win_event: WindowEvent::Resized(640, 480),
+    };
+    let calls = run(
+        vec![vec![user_resize, resize(640, 480)], vec![close()]],
+        None,
+    );
+
+    assert_eq!(
+        calls
+            .iter()
+            .filter(|call| call.starts_with("resize"))
+            .collect::<Vec<_>>(),
+        vec!["resize (640, 480) (0, 0, 640, 480)"]
+    );
+}
@@ -0,0 +1,152 @@
+use amazintosh_rs::render::atlas::{AtlasBuilder, AtlasError, SkylinePacker};
+use amazintosh_rs::render::mock::MockRenderHandler;
+use amazintosh_rs::render::texture::TextureOptions;
+
+fn solid(width: u32, height: u32, value: u8) -> Vec<u8> {
+    vec![value; width as usize * height as usize * 4]
+}
+
+fn overlaps(a: ((u32, u32), (u32, u32)), b: ((u32, u32), (u32, u32))) -> bool {
+    let ((ax, ay), (aw, ah)) = a;
+    let ((bx, by), (bw, bh)) = b;
+    ax < bx + bw && bx < ax + aw && ay < by + bh && by < ay + ah
+}
+
+#[test]
+fn skyline_fills_rows_before_stacking() {
+    let mut packer = SkylinePacker::new(8, 8);
+
+    assert_eq!(packer.insert(4, 2), Some((0, 0)));
+    assert_eq!(packer.insert(4, 3), Some((4, 0)));
+    assert_eq!(packer.insert(4, 4), Some((0, 2)));
+    assert_eq!(packer.insert(8, 3), None);
+    assert_eq!(packer.insert(4, 5), Some((4, 3)));
+}
+
+#[test]
+fn packed_images_do_not_overlap() {
+    let mut builder = AtlasBuilder::new(64, 64).with_padding(1);
+    let sizes = [
+        (10, 12),
+        (30, 8),
+        (7, 7),
+        (16, 16),
+        (5, 20),
+        (22, 3),
+        (12, 12),
+    ];
+    for (i, &(w, h)) in sizes.iter().enumerate() {
+        builder
+            .add_rgba(&format!("image{}", i), w, h, solid(w, h, i as u8 + 1))
+            .unwrap();
+    }
+
+    let packed = builder.pack().unwrap();
+    assert_eq!(packed.pages.len(), 1);
+
+    let placed: Vec<_> = (0..sizes.len())
+        .map(|i| packed.region(&format!("image{}", i)).unwrap())
+        .map(|r| {
+            (
+                (r.position.0 - 1, r.position.1 - 1),
+                (r.size.0 + 2, r.size.1 + 2),
+            )
+        })
+        .collect();
+    for (i, a) in placed.iter().enumerate() {
+        assert!(a.0 .0 + a.1 .0 <= 64 && a.0 .1 + a.1 .1 <= 64);
+        for b in &placed[i + 1..] {
+            assert!(!overlaps(*a, *b), "{:?} overlaps {:?}", a, b);
+        }
+    }
+}
+
+#[test]
+fn pixels_and_uvs_match_regions() {
+    let mut builder = AtlasBuilder::new(16, 16).with_padding(0);
+    builder.add_rgba("road", 4, 2, solid(4, 2, 9)).unwrap();
+
+    let packed = builder.pack().unwrap();
+    let region = *packed.region("road").unwrap();
+    assert_eq!(region.position, (0, 0));
+    assert_eq!(region.uv_min.x, 0.0);
+    assert_eq!(region.uv_max.x, 0.25);
+    assert_eq!(region.uv_max.y, 0.125);
+
+    let page = &packed.pages[0];
+    assert_eq!(&page.pixels[0..16], &[9; 16][..]);
+    assert_eq!(page.pixels[16], 0);
+    assert_eq!(&page.pixels[64..80], &[9; 16][..]);
+}
+
+#[test]
+fn padding_repeats_the_edge_pixels() {
+    let mut builder = AtlasBuilder::new(8, 8).with_padding(1);
+    let pixels = [vec![1; 4], vec![2; 4]].concat();
+    builder.add_rgba("fence", 2, 1, pixels).unwrap();
+
+    let packed = builder.pack().unwrap();
+    assert_eq!(packed.region("fence").unwrap().position, (1, 1));
+
+    let page = &packed.pages[0];
+    let row = |y: usize| &page.pixels[y * 32..y * 32 + 20];
+    let expected = [[1; 8], [2; 8]].concat();
+    for y in 0..3 {
+        assert_eq!(&row(y)[..16], &expected[..]);
+        assert_eq!(&row(y)[16..], &[0; 4]);
+    }
+    assert_eq!(&row(3)[..4], &[0; 4]);
+}
+
+#[test]
+fn overflowing_images_start_new_pages() {
+    let mut builder = AtlasBuilder::new(16, 16).with_padding(0);
+    for i in 0..3 {
+        builder
+            .add_rgba(&format!("house{}", i), 16, 10, solid(16, 10, 1))
+            .unwrap();
+    }
+
+    let packed = builder.pack().unwrap();
+    assert_eq!(packed.pages.len(), 3);
+
+    let mut handler = MockRenderHandler::new();
+    let atlas = packed
+        .upload(&mut handler, TextureOptions::pixelated())
+        .unwrap();
+    assert_eq!(atlas.page_count(), 3);
+    assert_eq!(handler.state().textures.len(), 3);
+}
+
+#[test]
+fn invalid_images_are_rejected() {
+    let mut builder = AtlasBuilder::new(16, 16);
+    builder.add_rgba("tree", 2, 2, solid(2, 2, 1)).unwrap();
+
+    assert_eq!(
+        builder.add_rgba("tree", 2, 2, solid(2, 2, 1)),
+        Err(AtlasError::DuplicateName("tree".to_owned()))
+    );
+
+    builder.add_rgba("tower", 15, 15, solid(15, 15, 1)).unwrap();
+    assert_eq!(
+        builder.pack().err(),
+        Some(AtlasError::ImageTooLarge("tower".to_owned()))
+    );
+}
+
+#[test]
+fn huge_padding_is_rejected_without_overflowing() {
+    let mut builder = AtlasBuilder::new(16, 16).with_padding(u32::MAX / 2);
+    builder.add_rgba("tree", 2, 2, solid(2, 2, 1)).unwrap();
+
+    assert_eq!(
+        builder.pack().err(),
+        Some(AtlasError::ImageTooLarge("tree".to_owned()))
+    );
+
+    let mut packer = SkylinePacker::new(16, 16);
+    assert_eq!(packer.insert(4, 4), Some((0, 0)));
+    assert_eq!(packer.insert(u32::MAX, 1), None);
+    assert_eq!(packer.insert(1, u32::MAX), None);
+}
@@ -0,0 +1,80 @@
+use amazintosh_rs::render::buffer::{Buffer, BufferError, BufferType, BufferUsage};
+use amazintosh_rs::render::mock::MockRenderHandler;
+
+fn data(buffer: &Buffer<MockRenderHandler, u32>, handler: &MockRenderHandler) -> Vec<u32> {
+    handler.state().buffers[&buffer.handle()]
+        .data
+        .chunks(4)
+        .map(|bytes| u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .collect()
+}
+
+#[test]
+fn buffer_only_reallocates_when_growing() {
+    let mut handler = MockRenderHandler::new();
+    let mut buffer = Buffer::new(&mut handler, BufferType::ArrayBuffer);
+
+    buffer.buffer_data(vec![1, 2, 3], BufferUsage::DynamicDraw);
+    assert_eq!((buffer.len(), buffer.capacity()), (3, 3));
+
+    buffer.buffer_data(vec![4, 5], BufferUsage::DynamicDraw);
+    assert_eq!((buffer.len(), buffer.capacity()), (2, 3));
+    assert_eq!(handler.state().buffers[&buffer.handle()].allocations, 1);
+    assert_eq!(data(&buffer, &handler), vec![4, 5, 3]);
+
+    // Growing at least doubles the capacity
+    buffer.buffer_data(vec![6, 7, 8, 9], BufferUsage::DynamicDraw);
+    assert_eq!((buffer.len(), buffer.capacity()), (4, 6));
+    assert_eq!(handler.state().buffers[&buffer.handle()].allocations, 2);
+    assert_eq!(data(&buffer, &handler), vec![6, 7, 8, 9, 0, 0]);
+}
+
+#[test]
+fn changing_usage_reallocates() {
+    let mut handler = MockRenderHandler::new();
+    let mut buffer = Buffer::new(&mut handler, BufferType::ArrayBuffer);
+
+    buffer.buffer_data(vec![1, 2, 3], BufferUsage::StaticDraw);
+    buffer.buffer_data(vec![1], BufferUsage::StreamDraw);
+
+    let state = handler.state();
+    let mock = &state.buffers[&buffer.handle()];
+    assert_eq!(mock.usage, Some(BufferUsage::StreamDraw));
+    assert_eq!(mock.allocations, 2);
+    assert_eq!(buffer.capacity(), 1);
+}
+
+#[test]
+fn update_replaces_a_range() {
+    let mut handler = MockRenderHandler::new();
+    let mut buffer = Buffer::with_capacity(
+        &mut handler,
+        BufferType::ArrayBuffer,
+        BufferUsage::DynamicDraw,
+        8,
+    );
+    assert!(buffer.is_empty());
+    assert_eq!(buffer.capacity(), 8);
+
+    buffer.buffer_data(vec![1, 2, 3, 4], BufferUsage::DynamicDraw);
+    buffer.update(1, &[20, 30]).unwrap();
+    assert_eq!(&data(&buffer, &handler)[..4], &[1, 20, 30, 4]);
+
+    assert_eq!(
+        buffer.update(3, &[40, 50]),
+        Err(BufferError::OutOfBounds {
+            offset: 3,
+            count: 2,
+            len: 4
+        })
+    );
+    assert_eq!(
+        buffer.update(usize::MAX, &[40]),
+        Err(BufferError::OutOfBounds {
+            offset: usize::MAX,
+            count: 1,
+            len: 4
+        })
+    );
+    assert_eq!(handler.state().buffers[&buffer.handle()].allocations, 1);
+}
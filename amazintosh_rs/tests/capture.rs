@@ -0,0 +1,96 @@
+use amazintosh_rs::render::capture::{FrameCapture, Screenshot};
+use amazintosh_rs::render::framebuffer::{
+    AttachmentStorage, Framebuffer, FramebufferHandler, FramebufferSize,
+};
+use amazintosh_rs::render::mock::MockRenderHandler;
+use amazintosh_rs::render::texture::{TextureFormat, TextureOptions};
+
+fn framebuffer(handler: &mut MockRenderHandler) -> Framebuffer<MockRenderHandler> {
+    Framebuffer::new(
+        handler,
+        FramebufferSize::Fixed(2, 3),
+        &[AttachmentStorage::Texture(
+            TextureFormat::Rgba8,
+            TextureOptions::pixelated(),
+        )],
+        None,
+    )
+    .unwrap()
+}
+
+#[test]
+fn flip_vertical_reverses_rows() {
+    let mut screenshot = Screenshot {
+        width: 1,
+        height: 3,
+        pixels: vec![1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3],
+    };
+    screenshot.flip_vertical();
+
+    assert_eq!(screenshot.pixels, vec![3, 3, 3, 3, 2, 2, 2, 2, 1, 1, 1, 1]);
+}
+
+#[test]
+fn framebuffer_readback_is_flipped() {
+    let mut handler = MockRenderHandler::new();
+    handler.set_default_framebuffer_size((100, 100));
+    handler.viewport(0, 0, 100, 100);
+    let mut framebuffer = framebuffer(&mut handler);
+
+    // Rows as OpenGL stores them, starting at the bottom
+    let rows: Vec<u8> = (0..3).flat_map(|row| vec![row; 8]).collect();
+    framebuffer
+        .color_texture_mut(0)
+        .unwrap()
+        .update((0, 0), (2, 3), &rows)
+        .unwrap();
+
+    let screenshot = Screenshot::from_framebuffer(&mut framebuffer);
+    assert_eq!((screenshot.width, screenshot.height), (2, 3));
+    assert_eq!(&screenshot.pixels[..8], &[2; 8]);
+    assert_eq!(&screenshot.pixels[16..], &[0; 8]);
+
+    let state = handler.state();
+    assert_eq!(state.bound_framebuffer, 0);
+    assert_eq!(state.viewport, (0, 0, 100, 100));
+}
+
+#[test]
+fn window_readback_uses_default_size() {
+    let mut handler = MockRenderHandler::new();
+    handler.set_default_framebuffer_size((4, 2));
+
+    let screenshot = Screenshot::from_window(&mut handler);
+    assert_eq!((screenshot.width, screenshot.height), (4, 2));
+    assert_eq!(screenshot.pixels.len(), 4 * 2 * 4);
+    assert!(screenshot
+        .pixels
+        .chunks(4)
+        .all(|pixel| pixel == [0, 0, 0, 255]));
+}
+
+#[test]
+fn frame_capture_numbers_every_interval() {
+    let directory = std::env::temp_dir().join(format!("amazintosh_capture_{}", std::process::id()));
+    let mut capture = FrameCapture::new(&directory, 3)
+        .unwrap()
+        .with_prefix("city");
+
+    let paths: Vec<_> = (0..7).map(|_| capture.next_frame()).collect();
+    assert_eq!(
+        paths,
+        vec![
+            Some(directory.join("city_000000.png")),
+            None,
+            None,
+            Some(directory.join("city_000001.png")),
+            None,
+            None,
+            Some(directory.join("city_000002.png")),
+        ]
+    );
+    assert_eq!(capture.frame(), 7);
+    assert_eq!(capture.saved(), 3);
+
+    std::fs::remove_dir_all(directory).unwrap();
+}
@@ -0,0 +1,104 @@
+use amazintosh_rs::error::{report, EngineError};
+use amazintosh_rs::render::atlas::AtlasError;
+use amazintosh_rs::render::capture::{CaptureError, FrameCapture};
+use amazintosh_rs::render::debug::{DebugHandler, GlError};
+use amazintosh_rs::render::mock::MockRenderHandler;
+use amazintosh_rs::render::preprocessor::{MemoryFileSystem, Preprocessor};
+use amazintosh_rs::render::shader::{Shader, ShaderError, ShaderType};
+use amazintosh_rs::render::texture::TextureError;
+use amazintosh_rs::window::{SdlError, SdlWindowError};
+use std::error::Error;
+
+#[test]
+fn sources_are_chained() {
+    let error = SdlWindowError::GlContextCreateFailed(SdlError("GLXBadFBConfig".to_owned()));
+    assert_eq!(error.to_string(), "failed to create an OpenGL context");
+    assert_eq!(error.source().unwrap().to_string(), "GLXBadFBConfig");
+    assert_eq!(
+        report(&error),
+        "failed to create an OpenGL context: GLXBadFBConfig"
+    );
+
+    let error = AtlasError::Texture(TextureError::InvalidDataLength {
+        expected: 16,
+        found: 12,
+    });
+    assert_eq!(
+        report(&error),
+        "failed to add image to the atlas: expected 16 bytes of pixel data but found 12"
+    );
+}
+
+#[test]
+fn io_errors_are_sources() {
+    let files = MemoryFileSystem::new().with_file("main.glsl", "#include \"light.glsl\"\n");
+    let error = Preprocessor::new()
+        .process(&files, "main.glsl")
+        .unwrap_err();
+    assert_eq!(
+        report(&error),
+        "failed to read included shader source light.glsl: no such file"
+    );
+
+    // A directory can't be created inside of a file
+    let file = std::env::temp_dir().join(format!("amazintosh_error_{}", std::process::id()));
+    std::fs::write(&file, "").unwrap();
+    let error = FrameCapture::new(file.join("frames"), 1).unwrap_err();
+    std::fs::remove_file(&file).unwrap();
+
+    assert!(matches!(error, CaptureError::CreateDirectoryFailed { .. }));
+    assert!(error.source().is_some());
+}
+
+#[test]
+fn engine_errors_read_like_the_error_they_wrap() {
+    let mut handler = MockRenderHandler::new();
+    let result: Result<_, EngineError> = (|| {
+        Ok(Shader::create_shader(
+            &mut handler,
+            ShaderType::Fragment,
+            "#version 330\n#error broken\n",
+        )?)
+    })();
+    let error = result.err().unwrap();
+
+    assert!(matches!(
+        error,
+        EngineError::Shader(ShaderError::CompileError(_))
+    ));
+    assert!(error.to_string().starts_with("failed to compile shader:\n"));
+    assert!(error.to_string().contains("broken"));
+    assert!(error.source().is_none());
+
+    let error = EngineError::from(SdlWindowError::VideoSubsystem(SdlError(
+        "no display".to_owned(),
+    )));
+    assert_eq!(
+        report(&error),
+        "failed to initialize the SDL video subsystem: no display"
+    );
+}
+
+#[test]
+fn check_errors_returns_the_first_error() {
+    let mut handler = MockRenderHandler::new();
+    assert_eq!(handler.check_errors(), Ok(()));
+
+    handler.raise_error(GlError::DebugMessage(
+        "GL_INVALID_OPERATION in glDrawElements".to_owned(),
+    ));
+    handler.raise_error(GlError::InvalidOperation);
+    assert_eq!(
+        handler.check_errors(),
+        Err(GlError::DebugMessage(
+            "GL_INVALID_OPERATION in glDrawElements".to_owned()
+        ))
+    );
+
+    // Every error is taken by a check
+    assert_eq!(handler.take_errors(), Vec::new());
+    assert_eq!(
+        GlError::Unknown(0x1234).to_string(),
+        "unknown OpenGL error 0x1234"
+    );
+}
@@ -0,0 +1,138 @@
+use amazintosh_rs::render::framebuffer::{
+    AttachmentStorage, Framebuffer, FramebufferAttachment, FramebufferHandler, FramebufferSize,
+};
+use amazintosh_rs::render::mock::{MockAttachment, MockRenderHandler};
+use amazintosh_rs::render::texture::{TextureFormat, TextureOptions};
+
+fn window_sized(handler: &mut MockRenderHandler, scale: f32) -> Framebuffer<MockRenderHandler> {
+    Framebuffer::new(
+        handler,
+        FramebufferSize::Window(scale),
+        &[AttachmentStorage::Texture(
+            TextureFormat::Rgba8,
+            TextureOptions::pixelated(),
+        )],
+        Some(AttachmentStorage::Renderbuffer(
+            TextureFormat::Depth24Stencil8,
+        )),
+    )
+    .unwrap()
+}
+
+#[test]
+fn attachments_are_created_and_attached() {
+    let mut handler = MockRenderHandler::new();
+    handler.set_default_framebuffer_size((800, 600));
+    let framebuffer = window_sized(&mut handler, 0.5);
+
+    assert_eq!(framebuffer.size(), (400, 300));
+
+    let state = handler.state();
+    let attachments = &state.framebuffers[&framebuffer.handle()];
+    let color = framebuffer.color_texture(0).unwrap().handle();
+    assert_eq!(
+        attachments[&FramebufferAttachment::Color(0)],
+        MockAttachment::Texture(color)
+    );
+    let depth = attachments[&FramebufferAttachment::DepthStencil];
+    assert_eq!(state.attachment_size(depth), (400, 300));
+    assert_eq!(state.bound_framebuffer, 0);
+}
+
+#[test]
+fn every_color_attachment_is_drawn_to() {
+    let mut handler = MockRenderHandler::new();
+    let texture = AttachmentStorage::Texture(TextureFormat::Rgba8, TextureOptions::pixelated());
+    let framebuffer = Framebuffer::new(
+        &mut handler,
+        FramebufferSize::Fixed(64, 64),
+        &[texture, texture],
+        None,
+    )
+    .unwrap();
+
+    assert_eq!(
+        handler.state().draw_buffers[&framebuffer.handle()],
+        vec![
+            FramebufferAttachment::Color(0),
+            FramebufferAttachment::Color(1)
+        ]
+    );
+}
+
+#[test]
+fn binding_is_scoped() {
+    let mut handler = MockRenderHandler::new();
+    handler.set_default_framebuffer_size((800, 600));
+    handler.viewport(0, 0, 800, 600);
+    let mut framebuffer = Framebuffer::new(
+        &mut handler,
+        FramebufferSize::Fixed(128, 128),
+        &[AttachmentStorage::Renderbuffer(TextureFormat::Rgba8)],
+        None,
+    )
+    .unwrap();
+
+    {
+        let binding = framebuffer.bind();
+        let state = handler.state();
+        assert_eq!(state.bound_framebuffer, binding.handle());
+        assert_eq!(state.viewport, (0, 0, 128, 128));
+    }
+
+    let state = handler.state();
+    assert_eq!(state.bound_framebuffer, 0);
+    assert_eq!(state.viewport, (0, 0, 800, 600));
+}
+
+#[test]
+fn nested_bindings_restore_the_outer_framebuffer() {
+    let mut handler = MockRenderHandler::new();
+    handler.set_default_framebuffer_size((800, 600));
+    handler.viewport(0, 0, 800, 600);
+    let mut outer = window_sized(&mut handler, 0.5);
+    let mut inner = Framebuffer::new(
+        &mut handler,
+        FramebufferSize::Fixed(128, 128),
+        &[AttachmentStorage::Renderbuffer(TextureFormat::Rgba8)],
+        None,
+    )
+    .unwrap();
+
+    let outer_binding = outer.bind();
+    drop(inner.bind());
+
+    let state = handler.state();
+    assert_eq!(state.bound_framebuffer, outer_binding.handle());
+    assert_eq!(state.viewport, (0, 0, 400, 300));
+}
+
+#[test]
+fn window_sized_framebuffers_follow_resizes() {
+    let mut handler = MockRenderHandler::new();
+    handler.set_default_framebuffer_size((800, 600));
+    let mut framebuffer = window_sized(&mut handler, 1.0);
+
+    handler.set_default_framebuffer_size((1024, 768));
+    drop(framebuffer.bind());
+
+    assert_eq!(framebuffer.size(), (1024, 768));
+    let state = handler.state();
+    let color = framebuffer.color_texture(0).unwrap().handle();
+    assert_eq!(state.textures[&color].size, (1024, 768));
+    for &attachment in state.framebuffers[&framebuffer.handle()].values() {
+        assert_eq!(state.attachment_size(attachment), (1024, 768));
+    }
+}
+
+#[test]
+fn dropping_framebuffer_deletes_attachments() {
+    let mut handler = MockRenderHandler::new();
+    handler.set_default_framebuffer_size((64, 64));
+    drop(window_sized(&mut handler, 1.0));
+
+    let state = handler.state();
+    assert!(state.framebuffers.is_empty());
+    assert!(state.renderbuffers.is_empty());
+    assert!(state.textures.is_empty());
+}
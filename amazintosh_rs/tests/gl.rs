@@ -0,0 +1,24 @@
+use amazintosh_rs::render::buffer::BufferHandler;
+use amazintosh_rs::render::framebuffer::FramebufferHandler;
+use amazintosh_rs::render::mesh::MeshHandler;
+use amazintosh_rs::render::shader::ShaderHandler;
+use amazintosh_rs::render::texture::TextureHandler;
+use amazintosh_rs::render::Gl;
+
+#[test]
+fn nothing_is_deleted_after_the_context_is_destroyed() {
+    // None of the functions are loaded, so calling any of them panics
+    let mut gl = Gl::new(|_| std::ptr::null());
+    assert!(gl.is_context_alive());
+
+    gl.clone().destroy_context();
+    assert!(!gl.is_context_alive());
+
+    gl.delete_buffer(1);
+    gl.delete_vertex_array(1);
+    gl.delete_shader(1);
+    gl.delete_program(1);
+    gl.delete_texture(1);
+    gl.delete_framebuffer(1);
+    gl.delete_renderbuffer(1);
+}
@@ -0,0 +1,149 @@
+use amazintosh_rs::application::{Application, Control, Runner};
+use amazintosh_rs::error::EngineError;
+use amazintosh_rs::headless::HeadlessWindow;
+use amazintosh_rs::render::framebuffer::FramebufferHandler;
+use amazintosh_rs::render::mock::MockRenderHandler;
+use amazintosh_rs::sdl2::event::{Event, WindowEvent};
+use amazintosh_rs::timing::FixedTimestep;
+use amazintosh_rs::window::AWindow;
+use std::cell::RefCell;
+use std::time::Duration;
+
+thread_local! {
+    static CALLS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+fn record(call: String) {
+    CALLS.with(|calls| calls.borrow_mut().push(call));
+}
+
+/// Records ticks, frames and events, resizing the window on its fifth tick.
+struct Simulation {
+    ticks: u32,
+}
+
+impl Application<MockRenderHandler> for Simulation {
+    fn init<WindowType: AWindow<MockRenderHandler, Event>>(
+        _window: &mut WindowType,
+        _render_handler: &mut MockRenderHandler,
+    ) -> Result<Self, EngineError> {
+        Ok(Self { ticks: 0 })
+    }
+
+    fn update<WindowType: AWindow<MockRenderHandler, Event>>(
+        &mut self,
+        window: &mut WindowType,
+        _dt: Duration,
+    ) -> Control {
+        self.ticks += 1;
+        record(format!("tick {}", self.ticks));
+        if self.ticks == 5 {
+            window.set_size((640, 480)).ok();
+        }
+        Control::Continue
+    }
+
+    fn render<WindowType: AWindow<MockRenderHandler, Event>>(
+        &mut self,
+        _window: &mut WindowType,
+        _render_handler: &mut MockRenderHandler,
+        _alpha: f32,
+    ) {
+        record("render".to_owned());
+    }
+
+    fn on_resize<WindowType: AWindow<MockRenderHandler, Event>>(
+        &mut self,
+        _window: &mut WindowType,
+        render_handler: &mut MockRenderHandler,
+        size: (u32, u32),
+    ) {
+        record(format!(
+            "resize {:?} {:?} {:?}",
+            size,
+            render_handler.state().viewport,
+            render_handler.default_framebuffer_size()
+        ));
+    }
+
+    fn on_quit<WindowType: AWindow<MockRenderHandler, Event>>(&mut self, _window: &mut WindowType) {
+        record("quit".to_owned());
+    }
+}
+
+fn run(window: HeadlessWindow<MockRenderHandler>) -> Vec<String> {
+    CALLS.with(|calls| calls.borrow_mut().clear());
+    Runner::new(window)
+        .unwrap()
+        .with_timestep(FixedTimestep::new(40))
+        .run::<Simulation>()
+        .unwrap();
+    CALLS.with(|calls| calls.borrow().clone())
+}
+
+fn window() -> HeadlessWindow<MockRenderHandler> {
+    HeadlessWindow::new(&MockRenderHandler::new(), 300, 200)
+        .with_frame_time(Duration::from_millis(50))
+}
+
+fn count(calls: &[String], prefix: &str) -> usize {
+    calls.iter().filter(|call| call.starts_with(prefix)).count()
+}
+
+#[test]
+fn runs_for_max_frames_with_simulated_time() {
+    let handler = MockRenderHandler::new();
+    let window = HeadlessWindow::new(&handler, 300, 200)
+        .with_frame_time(Duration::from_millis(50))
+        .with_max_frames(10);
+    assert_eq!(handler.default_framebuffer_size(), (300, 200));
+
+    let calls = run(window);
+
+    // Two 25ms ticks run in every 50ms frame, however long the frames took
+    assert_eq!(count(&calls, "render"), 10);
+    assert_eq!(count(&calls, "tick"), 20);
+    // The application still shuts down when the frames run out
+    assert_eq!(count(&calls, "quit"), 1);
+    assert_eq!(calls.last().unwrap(), "quit");
+}
+
+#[test]
+fn resizing_sends_a_resize_event() {
+    let calls = run(window().with_max_frames(4));
+
+    assert_eq!(
+        calls[..9],
+        [
+            "tick 1", "tick 2", "render", "tick 3", "tick 4", "render", "tick 5", "tick 6",
+            "render",
+        ]
+    );
+    assert_eq!(calls[9], "resize (640, 480) (0, 0, 640, 480) (640, 480)");
+}
+
+#[test]
+fn scheduled_events_are_sent_after_their_frame() {
+    let mut window = window();
+    window.schedule_event(
+        2,
+        Event::Window {
+            timestamp: 0,
+            window_id: 0,
+            win_event: WindowEvent::Close,
+        },
+    );
+
+    let calls = run(window);
+
+    assert_eq!(count(&calls, "render"), 3);
+    assert_eq!(calls.last().unwrap(), "quit");
+}
+
+#[test]
+fn runs_are_repeatable() {
+    let first = run(window().with_max_frames(30));
+    let second = run(window().with_max_frames(30));
+
+    assert_eq!(first, second);
+}
@@ -0,0 +1,96 @@
+use amazintosh_rs::nalgebra::{Matrix4, Vector3};
+use amazintosh_rs::render::buffer::{BufferType, BufferUsage};
+use amazintosh_rs::render::mesh::InstancedMesh;
+use amazintosh_rs::render::mock::MockRenderHandler;
+use amazintosh_rs::render::vertex::{InstanceData, Vertex};
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Vertex)]
+struct PosVert {
+    position: Vector3<f32>,
+    color: Vector3<f32>,
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, InstanceData)]
+struct House {
+    model: Matrix4<f32>,
+    tint: [u8; 4],
+}
+
+fn houses(count: usize) -> Vec<House> {
+    (0..count)
+        .map(|i| House {
+            model: Matrix4::new_translation(&Vector3::new(i as f32, 0.0, 0.0)),
+            tint: [255; 4],
+        })
+        .collect()
+}
+
+#[test]
+fn instance_locations_follow_vertex_locations() {
+    let pointers = House::attrib_pointers(2);
+
+    let indices: Vec<_> = pointers.iter().map(|p| p.index).collect();
+    let offsets: Vec<_> = pointers.iter().map(|p| p.offset).collect();
+    assert_eq!(indices, vec![2, 3, 4, 5, 6]);
+    assert_eq!(offsets, vec![0, 16, 32, 48, 64]);
+    assert!(pointers[..4].iter().all(|p| p.size == 4));
+}
+
+#[test]
+fn instance_attributes_advance_per_instance() {
+    let mut handler = MockRenderHandler::new();
+    let mesh: InstancedMesh<_, PosVert, House, u16> = InstancedMesh::new(&mut handler);
+
+    let state = handler.state();
+    let vao = &state.vertex_arrays[&state.bound_vertex_array];
+    let instance_buffer = state.bound_buffer(BufferType::ArrayBuffer).unwrap();
+    assert_eq!(vao.attrib_pointers.len(), 7);
+    for pointer in &vao.attrib_pointers[2..] {
+        assert_eq!(pointer.buffer, instance_buffer);
+        assert_eq!(pointer.stride, std::mem::size_of::<House>());
+        assert_eq!(vao.divisors.get(&pointer.pointer.index), Some(&1));
+    }
+    assert!(!vao.divisors.contains_key(&0));
+    assert_eq!(mesh.instance_count(), 0);
+}
+
+#[test]
+fn render_draws_every_instance_at_once() {
+    let mut handler = MockRenderHandler::new();
+    let mut mesh: InstancedMesh<_, PosVert, House, u16> = InstancedMesh::new(&mut handler);
+    mesh.set_vertices(
+        vec![
+            PosVert {
+                position: Vector3::zeros(),
+                color: Vector3::zeros(),
+            };
+            3
+        ],
+        BufferUsage::StaticDraw,
+    );
+    mesh.set_indices(vec![0, 1, 2], BufferUsage::StaticDraw);
+    mesh.set_instances(houses(1000), BufferUsage::DynamicDraw);
+    mesh.render();
+
+    let state = handler.state();
+    assert_eq!(state.draw_calls.len(), 1);
+    let draw = &state.draw_calls[0];
+    assert_eq!(draw.count, 3);
+    assert_eq!(draw.instances, Some(1000));
+    assert_eq!(draw.enabled_attribs, (0..7).collect::<Vec<_>>());
+}
+
+#[test]
+fn render_without_instances_draws_nothing() {
+    let mut handler = MockRenderHandler::new();
+    let mut mesh: InstancedMesh<_, PosVert, House, u16> = InstancedMesh::new(&mut handler);
+    mesh.set_indices(vec![0, 1, 2], BufferUsage::StaticDraw);
+    mesh.render();
+
+    mesh.set_instances(houses(2), BufferUsage::DynamicDraw);
+    assert!(mesh.update_instances(1, &houses(2)).is_err());
+
+    assert!(handler.state().draw_calls.is_empty());
+}
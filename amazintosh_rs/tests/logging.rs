@@ -0,0 +1,95 @@
+use amazintosh_rs::log::{Level, LevelFilter, Log, Record};
+use amazintosh_rs::logging::{EngineLogger, LogEntry, LogHistory};
+use amazintosh_rs::render::buffer::{Buffer, BufferType};
+use amazintosh_rs::render::leak::{self, ResourceKind};
+use amazintosh_rs::render::mock::MockRenderHandler;
+use amazintosh_rs::render::shader::{Shader, ShaderProgram, ShaderType};
+
+fn entry(message: &str) -> LogEntry {
+    LogEntry {
+        level: Level::Warn,
+        target: "test".to_owned(),
+        message: message.to_owned(),
+    }
+}
+
+#[test]
+fn history_keeps_the_most_recent_messages() {
+    let history = LogHistory::new(2);
+    history.push(entry("one"));
+    history.push(entry("two"));
+    history.push(entry("three"));
+
+    let messages: Vec<_> = history
+        .recent(10)
+        .into_iter()
+        .map(|entry| entry.message)
+        .collect();
+    assert_eq!(messages, vec!["two", "three"]);
+    assert_eq!(history.recent(1)[0].message, "three");
+    assert_eq!(history.generation(), 3);
+}
+
+#[test]
+fn only_important_messages_are_kept() {
+    let history = LogHistory::new(8);
+    let logger = EngineLogger::new(LevelFilter::Off, history.clone());
+
+    for level in &[Level::Info, Level::Warn, Level::Error] {
+        logger.log(
+            &Record::builder()
+                .level(*level)
+                .target("amazintosh_rs::render::buffer")
+                .args(format_args!("{} message", level))
+                .build(),
+        );
+    }
+
+    let recent = history.recent(8);
+    assert_eq!(recent.len(), 2);
+    assert_eq!(recent[0].level, Level::Warn);
+    assert_eq!(recent[0].target, "amazintosh_rs::render::buffer");
+    assert_eq!(recent[1].message, "ERROR message");
+}
+
+#[test]
+fn skipped_uniforms_are_logged_once() {
+    let history = LogHistory::new(64);
+    EngineLogger::new(LevelFilter::Off, history.clone())
+        .install()
+        .unwrap();
+
+    let mut handler = MockRenderHandler::new();
+    let shader = Shader::create_shader(
+        &mut handler,
+        ShaderType::Fragment,
+        "#version 330\nuniform float time;\nvoid main() {}\n",
+    )
+    .unwrap();
+    let mut program = ShaderProgram::from_shaders(&mut handler, None, None, Some(shader)).unwrap();
+    program.uniform("missing", 1.0f32);
+    program.uniform("missing", 2.0f32);
+    program.uniform("time", 3.0f32);
+
+    // Other tests may log at the same time
+    let skipped = history
+        .recent(64)
+        .into_iter()
+        .filter(|entry| entry.message.contains("`missing`"))
+        .count();
+    assert_eq!(skipped, 1);
+}
+
+#[test]
+fn leaked_resources_are_reported() {
+    let mut handler = MockRenderHandler::new();
+    let buffer: Buffer<_, u8> = Buffer::new(&mut handler, BufferType::ArrayBuffer);
+    let handle = buffer.handle();
+    {
+        let _dropped: Buffer<_, u8> = Buffer::new(&mut handler, BufferType::ArrayBuffer);
+    }
+    assert_eq!(leak::live_resources(), vec![(ResourceKind::Buffer, handle)]);
+
+    std::mem::forget(buffer);
+    assert_eq!(leak::report_leaks(), 1);
+}
@@ -0,0 +1,226 @@
+use amazintosh_rs::nalgebra::Vector3;
+use amazintosh_rs::render::buffer::{BufferError, BufferType, BufferUsage};
+use amazintosh_rs::render::inner_gl;
+use amazintosh_rs::render::mesh::{Mesh, MeshError, MeshMode};
+use amazintosh_rs::render::mock::MockRenderHandler;
+use amazintosh_rs::render::vertex::{Vertex, VertexAttribPointer};
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+struct PosVert {
+    position: Vector3<f32>,
+    color: Vector3<f32>,
+}
+
+impl PosVert {
+    fn new(x: f32, y: f32) -> Self {
+        Self {
+            position: Vector3::new(x, y, 0.0),
+            color: Vector3::new(1.0, 1.0, 1.0),
+        }
+    }
+}
+
+impl Vertex for PosVert {
+    fn attrib_pointers() -> Vec<VertexAttribPointer> {
+        vec![
+            VertexAttribPointer::new::<f32>(0, 3, false, 0),
+            VertexAttribPointer::new::<f32>(1, 3, false, std::mem::size_of::<Vector3<f32>>()),
+        ]
+    }
+}
+
+fn triangle() -> Vec<PosVert> {
+    vec![
+        PosVert::new(0.0, 0.5),
+        PosVert::new(-0.5, -0.5),
+        PosVert::new(0.5, -0.5),
+    ]
+}
+
+#[test]
+fn new_mesh_sets_up_attrib_pointers() {
+    let mut handler = MockRenderHandler::new();
+    let _mesh: Mesh<_, PosVert, u16> = Mesh::new(&mut handler);
+
+    let state = handler.state();
+    assert_eq!(state.vertex_arrays.len(), 1);
+    assert_eq!(state.buffers.len(), 2);
+
+    let vao = &state.vertex_arrays[&state.bound_vertex_array];
+    let vbo = state.bound_buffer(BufferType::ArrayBuffer).unwrap();
+    assert_eq!(vao.attrib_pointers.len(), 2);
+    // Attributes stay enabled in the vertex array rather than per draw
+    assert_eq!(
+        vao.enabled_attribs.iter().copied().collect::<Vec<_>>(),
+        vec![0, 1]
+    );
+    for (pointer, offset) in vao.attrib_pointers.iter().zip(&[0, 12]) {
+        assert_eq!(pointer.buffer, vbo);
+        assert_eq!(pointer.stride, std::mem::size_of::<PosVert>());
+        assert_eq!(pointer.pointer.offset, *offset);
+        assert_eq!(pointer.pointer.data_type, inner_gl::FLOAT);
+    }
+}
+
+#[test]
+fn set_vertices_buffers_vertex_bytes() {
+    let mut handler = MockRenderHandler::new();
+    let mut mesh: Mesh<_, PosVert, u16> = Mesh::new(&mut handler);
+    mesh.set_vertices(triangle(), BufferUsage::StaticDraw);
+
+    let state = handler.state();
+    let vbo = state.bound_buffer(BufferType::ArrayBuffer).unwrap();
+    let buffer = &state.buffers[&vbo];
+    assert_eq!(buffer.buffer_type, Some(BufferType::ArrayBuffer));
+    assert_eq!(buffer.usage, Some(BufferUsage::StaticDraw));
+    assert_eq!(buffer.data.len(), 3 * std::mem::size_of::<PosVert>());
+    assert_eq!(&buffer.data[0..4], &0.0f32.to_ne_bytes());
+    assert_eq!(&buffer.data[4..8], &0.5f32.to_ne_bytes());
+}
+
+#[test]
+fn set_indices_buffers_into_vertex_array() {
+    let mut handler = MockRenderHandler::new();
+    let mut mesh: Mesh<_, PosVert, u16> = Mesh::new(&mut handler);
+    mesh.set_indices(vec![0, 1, 2], BufferUsage::StaticDraw);
+
+    let state = handler.state();
+    let ebo = state.vertex_arrays[&state.bound_vertex_array]
+        .element_buffer
+        .unwrap();
+    let data = &state.buffers[&ebo].data;
+    assert_eq!(data.len(), 3 * std::mem::size_of::<u16>());
+    assert_eq!(&data[2..4], &1u16.to_ne_bytes());
+}
+
+#[test]
+fn updates_replace_part_of_the_mesh() {
+    let mut handler = MockRenderHandler::new();
+    let mut mesh: Mesh<_, PosVert, u16> = Mesh::new(&mut handler);
+    mesh.set_vertices(triangle(), BufferUsage::DynamicDraw);
+    mesh.set_indices(vec![0, 1, 2], BufferUsage::DynamicDraw);
+
+    mesh.update_vertices(2, &[PosVert::new(0.25, 0.75)])
+        .unwrap();
+    mesh.update_indices(0, &[2, 1]).unwrap();
+
+    let error = mesh.update_vertices(2, &triangle()[..2]).unwrap_err();
+    assert_eq!(
+        error,
+        MeshError::FailedToUpdateData(BufferError::OutOfBounds {
+            offset: 2,
+            count: 2,
+            len: 3,
+        })
+    );
+    assert!(mesh.update_indices(3, &[0]).is_err());
+
+    let state = handler.state();
+    let vao = &state.vertex_arrays[&state.bound_vertex_array];
+    let indices = &state.buffers[&vao.element_buffer.unwrap()].data;
+    assert_eq!(
+        &indices[0..4],
+        &[2u16.to_ne_bytes(), 1u16.to_ne_bytes()].concat()[..]
+    );
+
+    let vertices = &state.buffers[&vao.attrib_pointers[0].buffer].data;
+    let offset = 2 * std::mem::size_of::<PosVert>();
+    assert_eq!(&vertices[offset..offset + 4], &0.25f32.to_ne_bytes());
+}
+
+#[test]
+fn render_without_vertices_draws_nothing() {
+    let mut handler = MockRenderHandler::new();
+    let mut mesh: Mesh<_, PosVert, u16> = Mesh::new(&mut handler);
+    mesh.render();
+
+    assert!(handler.state().draw_calls.is_empty());
+}
+
+#[test]
+fn render_without_indices_draws_arrays() {
+    let mut handler = MockRenderHandler::new();
+    let mut mesh: Mesh<_, PosVert, u16> = Mesh::new(&mut handler);
+    mesh.set_vertices(triangle(), BufferUsage::StaticDraw);
+    mesh.set_mode(MeshMode::LineLoop);
+    mesh.render();
+
+    let state = handler.state();
+    assert_eq!(state.draw_calls.len(), 1);
+
+    let draw = &state.draw_calls[0];
+    assert_eq!(draw.mode, MeshMode::LineLoop);
+    assert_eq!((draw.first, draw.count), (0, 3));
+    assert_eq!(draw.index_type, None);
+    assert_eq!(draw.element_buffer, None);
+    assert_eq!(draw.enabled_attribs, vec![0, 1]);
+}
+
+#[test]
+fn render_draws_elements() {
+    let mut handler = MockRenderHandler::new();
+    let mut mesh: Mesh<_, PosVert, u16> = Mesh::new(&mut handler);
+    mesh.set_vertices(triangle(), BufferUsage::StaticDraw);
+    mesh.set_indices(vec![0, 1, 2], BufferUsage::StaticDraw);
+    mesh.render();
+    mesh.render();
+
+    let state = handler.state();
+    assert_eq!(state.draw_calls.len(), 2);
+
+    let draw = &state.draw_calls[0];
+    assert_eq!(draw.mode, MeshMode::Triangles);
+    assert_eq!(draw.count, 3);
+    assert_eq!(draw.index_type, Some(inner_gl::UNSIGNED_SHORT));
+    assert_eq!(draw.enabled_attribs, vec![0, 1]);
+    assert!(draw.element_buffer.is_some());
+}
+
+#[test]
+fn render_with_empty_indices_draws_nothing() {
+    let mut handler = MockRenderHandler::new();
+    let mut mesh: Mesh<_, PosVert, u16> = Mesh::new(&mut handler);
+    mesh.set_vertices(triangle(), BufferUsage::StaticDraw);
+    mesh.set_indices(vec![0, 1, 2], BufferUsage::StaticDraw);
+    mesh.set_indices(Vec::new(), BufferUsage::StaticDraw);
+    mesh.render();
+
+    assert!(handler.state().draw_calls.is_empty());
+}
+
+#[test]
+fn render_uses_index_type() {
+    let mut handler = MockRenderHandler::new();
+    let mut wide: Mesh<_, PosVert, u32> = Mesh::new(&mut handler);
+    wide.set_vertices(triangle(), BufferUsage::StaticDraw);
+    wide.set_indices(vec![0, 1, 2], BufferUsage::StaticDraw);
+    wide.render();
+
+    let mut narrow: Mesh<_, PosVert, u8> = Mesh::new(&mut handler);
+    narrow.set_vertices(triangle(), BufferUsage::StaticDraw);
+    narrow.set_indices(vec![0, 1, 2], BufferUsage::StaticDraw);
+    narrow.render();
+
+    let state = handler.state();
+    assert_eq!(state.draw_calls[0].index_type, Some(inner_gl::UNSIGNED_INT));
+    assert_eq!(
+        state.draw_calls[1].index_type,
+        Some(inner_gl::UNSIGNED_BYTE)
+    );
+}
+
+#[test]
+fn dropping_mesh_deletes_resources() {
+    let mut handler = MockRenderHandler::new();
+    {
+        let mut mesh: Mesh<_, PosVert, u16> = Mesh::new(&mut handler);
+        mesh.set_vertices(triangle(), BufferUsage::StaticDraw);
+        mesh.set_indices(vec![0, 1, 2], BufferUsage::StaticDraw);
+    }
+
+    let state = handler.state();
+    assert!(state.vertex_arrays.is_empty());
+    assert!(state.buffers.is_empty());
+    assert_eq!(state.bound_vertex_array, 0);
+}
@@ -0,0 +1,142 @@
+use amazintosh_rs::render::preprocessor::{
+    MemoryFileSystem, PreprocessedSource, Preprocessor, VirtualFileSystem,
+};
+use amazintosh_rs::render::shader::ShaderError;
+
+fn lines(source: &PreprocessedSource) -> Vec<&str> {
+    source.source.lines().collect()
+}
+
+#[test]
+fn includes_are_expanded_with_line_directives() {
+    let files = MemoryFileSystem::new()
+        .with_file(
+            "shaders/main.glsl",
+            "#version 330\n#include \"lib/light.glsl\"\nvoid main() {}\n",
+        )
+        .with_file(
+            "shaders/lib/light.glsl",
+            "#include \"../common.glsl\"\nfloat light;\n",
+        )
+        .with_file("shaders/common.glsl", "float common;\n");
+
+    let source = Preprocessor::new()
+        .process(&files, "shaders/main.glsl")
+        .unwrap();
+
+    assert_eq!(
+        source.files,
+        vec![
+            "shaders/main.glsl",
+            "shaders/lib/light.glsl",
+            "shaders/common.glsl"
+        ]
+    );
+    assert_eq!(
+        lines(&source),
+        vec![
+            "#version 330",
+            "#line 2 0",
+            "#line 1 1",
+            "#line 1 2",
+            "float common;",
+            "#line 2 1",
+            "float light;",
+            "#line 3 0",
+            "void main() {}",
+        ]
+    );
+}
+
+#[test]
+fn defines_are_inserted_after_the_version() {
+    let files = MemoryFileSystem::new().with_file("main.glsl", "#version 330\nvoid main() {}\n");
+
+    let source = Preprocessor::new()
+        .with_define("SHADOWS", "1")
+        .with_define("MAX_LIGHTS", "8")
+        .process(&files, "main.glsl")
+        .unwrap();
+
+    assert_eq!(
+        lines(&source),
+        vec![
+            "#version 330",
+            "#define SHADOWS 1",
+            "#define MAX_LIGHTS 8",
+            "#line 2 0",
+            "void main() {}",
+        ]
+    );
+}
+
+#[test]
+fn pragma_once_files_are_included_once() {
+    let files = MemoryFileSystem::new()
+        .with_file(
+            "main.glsl",
+            "#include \"common.glsl\"\n#include \"common.glsl\"\n",
+        )
+        .with_file("common.glsl", "#pragma once\nfloat common;\n");
+
+    let source = Preprocessor::new().process(&files, "main.glsl").unwrap();
+
+    assert_eq!(source.files, vec!["main.glsl", "common.glsl"]);
+    assert_eq!(source.source.matches("float common;").count(), 1);
+}
+
+#[test]
+fn include_cycles_are_reported() {
+    let files = MemoryFileSystem::new()
+        .with_file("a.glsl", "#include \"b.glsl\"\n")
+        .with_file("b.glsl", "#include \"a.glsl\"\n");
+
+    match Preprocessor::new().process(&files, "a.glsl") {
+        Err(ShaderError::IncludeCycle(chain)) => assert_eq!(chain, "a.glsl -> b.glsl -> a.glsl"),
+        result => panic!("Expected an include cycle, got {:?}", result),
+    }
+}
+
+#[test]
+fn missing_and_invalid_includes_are_reported() {
+    let files = MemoryFileSystem::new()
+        .with_file("missing.glsl", "#include \"nothing.glsl\"\n")
+        .with_file("invalid.glsl", "#include nothing.glsl\n");
+    let preprocessor = Preprocessor::new();
+
+    assert!(matches!(
+        preprocessor.process(&files, "missing.glsl"),
+        Err(ShaderError::IncludeNotFound { .. })
+    ));
+    assert!(matches!(
+        preprocessor.process(&files, "invalid.glsl"),
+        Err(ShaderError::InvalidInclude(_))
+    ));
+    assert!(matches!(
+        preprocessor.process(&files, "other.glsl"),
+        Err(ShaderError::ReadSourceFailed { .. })
+    ));
+    assert!(files.read("other.glsl").is_err());
+}
+
+#[test]
+fn logs_are_remapped_to_file_names() {
+    let source = PreprocessedSource {
+        source: String::new(),
+        files: vec!["main.glsl".to_owned(), "light.glsl".to_owned()],
+    };
+
+    assert_eq!(
+        source.remap_log("1:4(12): error: undeclared identifier"),
+        "light.glsl:4(12): error: undeclared identifier"
+    );
+    assert_eq!(
+        source.remap_log("ERROR: 0:7: 'x' : undeclared identifier"),
+        "ERROR: main.glsl:7: 'x' : undeclared identifier"
+    );
+    assert_eq!(
+        source.remap_log("1(3) : error C1008: undefined variable"),
+        "light.glsl:3 : error C1008: undefined variable"
+    );
+    assert_eq!(source.remap_log("5:1(1): unknown"), "5:1(1): unknown");
+}
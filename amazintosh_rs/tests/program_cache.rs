@@ -0,0 +1,136 @@
+use amazintosh_rs::render::mock::MockRenderHandler;
+use amazintosh_rs::render::program_cache::ProgramBinaryCache;
+use amazintosh_rs::render::shader::ShaderProgram;
+use std::path::PathBuf;
+
+const VERTEX_SOURCE: &str = "#version 330
+layout(location = 0) in vec3 position;
+uniform mat4 view;
+void main() {}
+";
+
+const DRIVER: &str = "Mock renderer 0";
+
+const FRAGMENT_SOURCE: &str = "#version 330
+uniform sampler2D tex;
+void main() {}
+";
+
+fn temp_dir(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!(
+        "amazintosh_program_cache_{}_{}",
+        name,
+        std::process::id()
+    ))
+}
+
+fn build(
+    handler: &mut MockRenderHandler,
+    cache: &ProgramBinaryCache,
+) -> ShaderProgram<MockRenderHandler> {
+    ShaderProgram::from_sources_cached(
+        handler,
+        cache,
+        Some(VERTEX_SOURCE),
+        None,
+        Some(FRAGMENT_SOURCE),
+    )
+    .unwrap()
+}
+
+#[test]
+fn cached_programs_skip_compiling() {
+    let dir = temp_dir("hit");
+    let cache = ProgramBinaryCache::new(&dir).unwrap();
+    let mut handler = MockRenderHandler::new();
+
+    let first = build(&mut handler, &cache);
+    let second = build(&mut handler, &cache);
+
+    let state = handler.state();
+    assert!(!state.programs[&first.handle()].from_binary);
+    assert!(state.programs[&second.handle()].from_binary);
+    assert!(state.shaders.is_empty());
+    assert!(second.uniform_info("view").is_some());
+    assert_eq!(second.attribute_location("position"), Some(0));
+
+    drop(state);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn rejected_binaries_are_rebuilt() {
+    let dir = temp_dir("rejected");
+    let cache = ProgramBinaryCache::new(&dir).unwrap();
+    let key = ProgramBinaryCache::key(DRIVER, Some(VERTEX_SOURCE), None, Some(FRAGMENT_SOURCE));
+    cache.store(key, 7, b"from another driver").unwrap();
+
+    let mut handler = MockRenderHandler::new();
+    let program = build(&mut handler, &cache);
+
+    assert!(!handler.state().programs[&program.handle()].from_binary);
+    assert!(program.uniform_info("tex").is_some());
+
+    // The rejected binary was replaced
+    assert_ne!(cache.load(key).unwrap().0, 7);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn keys_depend_on_every_source() {
+    let key = ProgramBinaryCache::key(DRIVER, Some(VERTEX_SOURCE), None, Some(FRAGMENT_SOURCE));
+
+    assert_eq!(
+        key,
+        ProgramBinaryCache::key(DRIVER, Some(VERTEX_SOURCE), None, Some(FRAGMENT_SOURCE))
+    );
+    assert_ne!(
+        key,
+        ProgramBinaryCache::key(DRIVER, Some(VERTEX_SOURCE), Some(FRAGMENT_SOURCE), None)
+    );
+    assert_ne!(
+        key,
+        ProgramBinaryCache::key(DRIVER, Some(VERTEX_SOURCE), None, None)
+    );
+}
+
+#[test]
+fn keys_are_stable_and_depend_on_the_driver() {
+    // Keys name files on disk, so they must never change between builds
+    assert_eq!(
+        ProgramBinaryCache::key("", None, None, None),
+        0x5f24_2d39_c242_2be4
+    );
+    assert_ne!(
+        ProgramBinaryCache::key(DRIVER, Some(VERTEX_SOURCE), None, Some(FRAGMENT_SOURCE)),
+        ProgramBinaryCache::key(
+            "Mock renderer 1",
+            Some(VERTEX_SOURCE),
+            None,
+            Some(FRAGMENT_SOURCE)
+        )
+    );
+}
+
+#[test]
+fn driver_updates_rebuild_programs() {
+    let dir = temp_dir("driver");
+    let cache = ProgramBinaryCache::new(&dir).unwrap();
+    let mut handler = MockRenderHandler::new();
+
+    build(&mut handler, &cache);
+    handler.set_driver_version(1);
+    let program = build(&mut handler, &cache);
+
+    assert!(!handler.state().programs[&program.handle()].from_binary);
+    // Only the finished binaries are left behind
+    assert!(std::fs::read_dir(&dir).unwrap().all(|entry| entry
+        .unwrap()
+        .path()
+        .extension()
+        .unwrap()
+        == "bin"));
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,204 @@
+use amazintosh_rs::nalgebra::{Matrix3, Matrix4, Vector2, Vector3};
+use amazintosh_rs::render::mock::{MockRenderHandler, MockUniform};
+use amazintosh_rs::render::shader::{
+    GlslType, Sampler, Shader, ShaderError, ShaderProgram, ShaderType, ShaderVariable,
+};
+
+const VERTEX_SOURCE: &str = "#version 330
+layout(location = 1) in vec2 uv;
+layout(location = 0) in vec3 position;
+uniform mat4 projection;
+uniform mat4 view;
+uniform float weights[4];
+void main() {}
+";
+
+const FRAGMENT_SOURCE: &str = "#version 330
+void main() {}
+";
+
+fn test_program(handler: &mut MockRenderHandler) -> ShaderProgram<MockRenderHandler> {
+    let vertex_shader = Shader::create_shader(handler, ShaderType::Vertex, VERTEX_SOURCE).unwrap();
+    let fragment_shader =
+        Shader::create_shader(handler, ShaderType::Fragment, FRAGMENT_SOURCE).unwrap();
+
+    ShaderProgram::from_shaders(handler, Some(vertex_shader), None, Some(fragment_shader)).unwrap()
+}
+
+#[test]
+fn compile_error_is_reported() {
+    let mut handler = MockRenderHandler::new();
+    let result = Shader::create_shader(
+        &mut handler,
+        ShaderType::Fragment,
+        "#version 330\n#error missing output\n",
+    );
+
+    match result {
+        Err(ShaderError::CompileError(error)) => assert_eq!(error, "missing output"),
+        _ => panic!("shader should have failed to compile"),
+    }
+
+    // The failed shader is still cleaned up
+    assert!(handler.state().shaders.is_empty());
+}
+
+#[test]
+fn linking_releases_shaders() {
+    let mut handler = MockRenderHandler::new();
+    let program = test_program(&mut handler);
+
+    {
+        let state = handler.state();
+        assert!(state.shaders.is_empty());
+        assert_eq!(state.programs.len(), 1);
+        assert!(state.programs.values().all(|p| p.linked));
+    }
+
+    drop(program);
+    assert!(handler.state().programs.is_empty());
+}
+
+#[test]
+fn uniforms_are_set_by_name() {
+    let mut handler = MockRenderHandler::new();
+    let mut program = test_program(&mut handler);
+
+    program.bind();
+    program.uniform("view", Matrix4::<f32>::identity());
+    program.uniform("missing", Matrix4::<f32>::identity());
+
+    let state = handler.state();
+    assert_ne!(state.used_program, 0);
+    assert_eq!(state.uniforms.len(), 1);
+    assert_eq!(
+        state.uniforms[&1],
+        MockUniform::Mat4(Matrix4::<f32>::identity())
+    );
+}
+
+#[test]
+fn active_variables_are_reflected() {
+    let mut handler = MockRenderHandler::new();
+    let program = test_program(&mut handler);
+
+    assert_eq!(
+        program.uniform_info("weights"),
+        Some(&ShaderVariable {
+            name: "weights".to_owned(),
+            location: 2,
+            glsl_type: GlslType::Float,
+            size: 4,
+        })
+    );
+    assert_eq!(program.uniforms().count(), 3);
+
+    let attributes: Vec<(&str, i32)> = program
+        .attributes()
+        .iter()
+        .map(|attribute| (attribute.name.as_str(), attribute.location))
+        .collect();
+    assert_eq!(attributes, vec![("position", 0), ("uv", 1)]);
+    assert_eq!(program.attribute_location("uv"), Some(1));
+    assert_eq!(program.attribute_location("normal"), None);
+}
+
+#[test]
+fn checked_uniforms_report_errors() {
+    let mut handler = MockRenderHandler::new();
+    let mut program = test_program(&mut handler);
+    program.bind();
+
+    assert!(program
+        .try_uniform("projection", Matrix4::<f32>::identity())
+        .is_ok());
+    assert!(matches!(
+        program.try_uniform("missing", 1.0f32),
+        Err(ShaderError::UnknownUniform(name)) if name == "missing"
+    ));
+    assert!(matches!(
+        program.try_uniform("view", Matrix3::<f32>::identity()),
+        Err(ShaderError::UniformTypeMismatch {
+            expected: GlslType::Mat4,
+            found: GlslType::Mat3,
+            ..
+        })
+    ));
+
+    // Only the uniform with the right type was set
+    assert_eq!(handler.state().uniforms.len(), 1);
+}
+
+#[test]
+fn integer_bool_and_array_uniforms_are_set() {
+    let mut handler = MockRenderHandler::new();
+    let fragment_shader = Shader::create_shader(
+        &mut handler,
+        ShaderType::Fragment,
+        "#version 330
+uniform ivec2 tile;
+uniform uint flags;
+uniform bvec3 mask;
+uniform vec3 lights[8];
+uniform mat4 bones[2];
+uniform sampler2D layers[2];
+void main() {}
+",
+    )
+    .unwrap();
+    let mut program =
+        ShaderProgram::from_shaders(&mut handler, None, None, Some(fragment_shader)).unwrap();
+    program.bind();
+
+    let lights = [Vector3::new(1.0f32, 2.0, 3.0); 8];
+    let bones = vec![Matrix4::<f32>::identity(); 2];
+    program.try_uniform("tile", Vector2::new(3, -4)).unwrap();
+    program.try_uniform("flags", 5u32).unwrap();
+    program
+        .try_uniform("mask", Vector3::new(true, false, true))
+        .unwrap();
+    program.try_uniform("lights", lights).unwrap();
+    program.try_uniform("bones", bones.as_slice()).unwrap();
+    program
+        .try_uniform("layers", [Sampler(2), Sampler(5)])
+        .unwrap();
+    assert!(matches!(
+        program.try_uniform("lights", [1.0f32; 8]),
+        Err(ShaderError::UniformTypeMismatch { .. })
+    ));
+
+    let state = handler.state();
+    assert_eq!(state.uniforms[&0], MockUniform::IntArray(2, vec![3, -4]));
+    assert_eq!(state.uniforms[&1], MockUniform::UIntArray(1, vec![5]));
+    assert_eq!(state.uniforms[&2], MockUniform::IntArray(3, vec![1, 0, 1]));
+    match &state.uniforms[&3] {
+        MockUniform::FloatArray(3, values) => {
+            assert_eq!(values.len(), 24);
+            assert_eq!(&values[..3], &[1.0, 2.0, 3.0]);
+        }
+        uniform => panic!("unexpected lights uniform {:?}", uniform),
+    }
+    match &state.uniforms[&4] {
+        MockUniform::MatrixArray(4, values) => assert_eq!(values.len(), 32),
+        uniform => panic!("unexpected bones uniform {:?}", uniform),
+    }
+    assert_eq!(state.uniforms[&5], MockUniform::IntArray(1, vec![2, 5]));
+}
+
+#[test]
+fn warnings_do_not_fail_compilation() {
+    let mut handler = MockRenderHandler::new();
+    let shader = Shader::create_shader(
+        &mut handler,
+        ShaderType::Fragment,
+        "#version 330\n#warning unused variable\nvoid main() {}\n",
+    )
+    .unwrap();
+    assert_eq!(shader.warnings(), Some("unused variable"));
+
+    let program = ShaderProgram::from_shaders(&mut handler, None, None, Some(shader)).unwrap();
+    assert_eq!(
+        program.warnings(),
+        Some("fragment shader:\nunused variable")
+    );
+}
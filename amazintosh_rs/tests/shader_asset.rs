@@ -0,0 +1,178 @@
+use amazintosh_rs::nalgebra::Matrix4;
+use amazintosh_rs::render::mock::MockRenderHandler;
+use amazintosh_rs::render::shader_asset::ShaderAsset;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+const VERTEX_SOURCE: &str = "#version 330
+uniform mat4 view;
+void main() {}
+";
+
+const FRAGMENT_SOURCE: &str = "#version 330
+void main() {}
+";
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "amazintosh_shader_asset_{}_{}",
+        name,
+        std::process::id()
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Rewrites a file and moves its modification time forward, since the file
+/// system may not notice a change made within the same tick.
+fn rewrite(path: &Path, contents: &str, seconds: u64) {
+    std::fs::write(path, contents).unwrap();
+    File::options()
+        .write(true)
+        .open(path)
+        .unwrap()
+        .set_modified(SystemTime::now() + Duration::from_secs(seconds))
+        .unwrap();
+}
+
+fn load(handler: &mut MockRenderHandler, dir: &Path) -> ShaderAsset<MockRenderHandler> {
+    let vertex = dir.join("vertex.glsl");
+    let fragment = dir.join("fragment.glsl");
+    std::fs::write(&vertex, VERTEX_SOURCE).unwrap();
+    std::fs::write(&fragment, FRAGMENT_SOURCE).unwrap();
+
+    ShaderAsset::load(handler, Some(&vertex), None, Some(&fragment)).unwrap()
+}
+
+#[test]
+fn unchanged_files_are_not_reloaded() {
+    let dir = temp_dir("unchanged");
+    let mut handler = MockRenderHandler::new();
+    let mut asset = load(&mut handler, &dir);
+
+    assert!(!asset.reload_if_changed());
+    assert_eq!(handler.state().programs.len(), 1);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn changed_files_are_relinked_with_uniforms() {
+    let dir = temp_dir("changed");
+    let mut handler = MockRenderHandler::new();
+    let mut asset = load(&mut handler, &dir);
+    let old_handle = asset.handle();
+
+    // Moving the uniform changes its location
+    rewrite(
+        &dir.join("vertex.glsl"),
+        "#version 330\nuniform float time;\nuniform mat4 view;\nvoid main() {}\n",
+        10,
+    );
+    assert!(asset.reload_if_changed());
+    assert_ne!(asset.handle(), old_handle);
+
+    asset.bind();
+    asset.uniform("view", Matrix4::<f32>::identity());
+
+    let state = handler.state();
+    assert_eq!(state.programs.len(), 1);
+    assert!(state.uniforms.contains_key(&1));
+
+    drop(state);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn failed_reloads_keep_the_old_program() {
+    let dir = temp_dir("failed");
+    let mut handler = MockRenderHandler::new();
+    let mut asset = load(&mut handler, &dir);
+    let old_handle = asset.handle();
+
+    rewrite(
+        &dir.join("fragment.glsl"),
+        "#version 330\n#error oops\n",
+        10,
+    );
+    assert!(!asset.reload_if_changed());
+    assert_eq!(asset.handle(), old_handle);
+
+    // The broken file isn't retried until it changes again
+    assert!(!asset.reload_if_changed());
+
+    rewrite(&dir.join("fragment.glsl"), FRAGMENT_SOURCE, 20);
+    assert!(asset.reload_if_changed());
+
+    let state = handler.state();
+    assert_eq!(state.programs.len(), 1);
+    assert!(state.shaders.is_empty());
+
+    drop(state);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn failed_reloads_are_only_attempted_once() {
+    let dir = temp_dir("failed_once");
+    let mut handler = MockRenderHandler::new();
+    let mut asset = load(&mut handler, &dir);
+
+    // Both files are saved, but the first one to be compiled is broken
+    rewrite(&dir.join("vertex.glsl"), "#version 330\n#error oops\n", 10);
+    rewrite(&dir.join("fragment.glsl"), FRAGMENT_SOURCE, 10);
+    assert!(!asset.reload_if_changed());
+
+    let compiles = handler.state().shader_compiles;
+    assert!(!asset.reload_if_changed());
+    assert_eq!(handler.state().shader_compiles, compiles);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn changed_includes_are_reloaded() {
+    let dir = temp_dir("includes");
+    let mut handler = MockRenderHandler::new();
+    let mut asset = load(&mut handler, &dir);
+    let old_handle = asset.handle();
+
+    std::fs::write(dir.join("common.glsl"), "uniform float time;\n").unwrap();
+    rewrite(
+        &dir.join("vertex.glsl"),
+        "#version 330\n#include \"common.glsl\"\nuniform mat4 view;\nvoid main() {}\n",
+        10,
+    );
+    assert!(asset.reload_if_changed());
+    assert!(!asset.reload_if_changed());
+
+    rewrite(&dir.join("common.glsl"), "uniform vec2 offset;\n", 20);
+    assert!(asset.reload_if_changed());
+    assert_ne!(asset.handle(), old_handle);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn warnings_reach_the_program() {
+    let dir = temp_dir("warnings");
+    let mut handler = MockRenderHandler::new();
+    let vertex = dir.join("vertex.glsl");
+    let fragment = dir.join("fragment.glsl");
+    std::fs::write(&vertex, VERTEX_SOURCE).unwrap();
+    std::fs::write(
+        &fragment,
+        "#version 330\n#warning 0:2(1): warning: unused variable\nvoid main() {}\n",
+    )
+    .unwrap();
+
+    let asset = ShaderAsset::load(&mut handler, Some(&vertex), None, Some(&fragment)).unwrap();
+
+    assert_eq!(
+        asset.warnings(),
+        Some("fragment shader:\nfragment.glsl:2(1): warning: unused variable")
+    );
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,139 @@
+use amazintosh_rs::render::text::{layout_text, FontMetrics, TextAlign, TextOptions};
+use std::cell::Cell;
+
+/// Every character is 10 pixels wide, except that "AV" is kerned together.
+struct Monospace;
+
+impl FontMetrics for Monospace {
+    fn advance(&self, _ch: char) -> i32 {
+        10
+    }
+
+    fn kerning(&self, left: char, right: char) -> i32 {
+        if left == 'A' && right == 'V' {
+            -2
+        } else {
+            0
+        }
+    }
+
+    fn line_height(&self) -> i32 {
+        20
+    }
+}
+
+fn positions(text: &str, options: &TextOptions) -> Vec<(char, f32, f32)> {
+    layout_text(&Monospace, text, options)
+        .glyphs
+        .iter()
+        .map(|g| (g.ch, g.position.x, g.position.y))
+        .collect()
+}
+
+#[test]
+fn kerning_adjusts_pairs() {
+    let layout = layout_text(&Monospace, "AVA", &TextOptions::default());
+
+    assert_eq!(layout.width, 28.0);
+    assert_eq!(
+        positions("AVA", &TextOptions::default()),
+        vec![('A', 0.0, 0.0), ('V', 8.0, 0.0), ('A', 18.0, 0.0)]
+    );
+}
+
+#[test]
+fn newlines_start_new_lines() {
+    let layout = layout_text(&Monospace, "ab\n\nc", &TextOptions::default());
+
+    assert_eq!(layout.line_count, 3);
+    assert_eq!(layout.height, 60.0);
+    assert_eq!(layout.glyphs[2].position.y, 40.0);
+}
+
+#[test]
+fn long_lines_wrap_at_spaces() {
+    let options = TextOptions {
+        max_width: Some(50.0),
+        ..TextOptions::default()
+    };
+    let layout = layout_text(&Monospace, "pop 1200 ok", &options);
+
+    let lines: Vec<String> = (0..layout.line_count)
+        .map(|line| {
+            layout
+                .glyphs
+                .iter()
+                .filter(|g| g.position.y == line as f32 * 20.0)
+                .map(|g| g.ch)
+                .collect()
+        })
+        .collect();
+    assert_eq!(lines, vec!["pop", "1200", "ok"]);
+}
+
+#[test]
+fn words_longer_than_a_line_are_broken() {
+    let options = TextOptions {
+        max_width: Some(30.0),
+        ..TextOptions::default()
+    };
+
+    assert_eq!(layout_text(&Monospace, "abcdefg", &options).line_count, 3);
+}
+
+#[test]
+fn lines_are_aligned_within_wrap_width() {
+    let options = |align| TextOptions {
+        max_width: Some(100.0),
+        align,
+    };
+
+    assert_eq!(positions("ab", &options(TextAlign::Center))[0].1, 40.0);
+    assert_eq!(positions("ab", &options(TextAlign::Right))[0].1, 80.0);
+}
+
+#[test]
+fn multibyte_characters_are_single_glyphs() {
+    let layout = layout_text(&Monospace, "$1½€", &TextOptions::default());
+
+    assert_eq!(layout.glyphs.len(), 4);
+    assert_eq!(layout.glyphs[3].ch, '€');
+    assert_eq!(layout.width, 40.0);
+}
+
+/// Counts how many pairs are measured, which is slow for real fonts.
+struct CountingKerning(Cell<usize>);
+
+impl FontMetrics for CountingKerning {
+    fn advance(&self, _ch: char) -> i32 {
+        10
+    }
+
+    fn kerning(&self, _left: char, _right: char) -> i32 {
+        self.0.set(self.0.get() + 1);
+        0
+    }
+
+    fn line_height(&self) -> i32 {
+        20
+    }
+}
+
+#[test]
+fn wrapping_measures_each_pair_a_few_times() {
+    let options = TextOptions {
+        max_width: Some(400.0),
+        ..TextOptions::default()
+    };
+    let text = "word ".repeat(200);
+    let metrics = CountingKerning(Cell::new(0));
+    layout_text(&metrics, &text, &options);
+
+    // Measuring the whole line for every character would take tens of
+    // thousands of measurements
+    assert!(
+        metrics.0.get() < text.len() * 4,
+        "{} pairs",
+        metrics.0.get()
+    );
+}
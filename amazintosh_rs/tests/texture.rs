@@ -0,0 +1,87 @@
+use amazintosh_rs::render::mock::{MockRenderHandler, MockUniform};
+use amazintosh_rs::render::shader::{Shader, ShaderProgram, ShaderType};
+use amazintosh_rs::render::texture::{
+    Texture2D, TextureError, TextureFilter, TextureFormat, TextureOptions,
+};
+
+#[test]
+fn rgba_texture_is_uploaded() {
+    let mut handler = MockRenderHandler::new();
+    let pixels: Vec<u8> = (0..16).collect();
+    let texture =
+        Texture2D::from_rgba(&mut handler, 2, 2, &pixels, TextureOptions::pixelated()).unwrap();
+
+    let state = handler.state();
+    let mock = &state.textures[&texture.handle()];
+    assert_eq!(mock.format, Some(TextureFormat::Rgba8));
+    assert_eq!(mock.size, (2, 2));
+    assert_eq!(mock.data, pixels);
+    assert_eq!(
+        mock.filter,
+        Some((TextureFilter::Nearest, TextureFilter::Nearest, None))
+    );
+    assert!(!mock.mipmaps_generated);
+}
+
+#[test]
+fn wrong_data_length_is_rejected() {
+    let mut handler = MockRenderHandler::new();
+    let result = Texture2D::from_rgba(&mut handler, 2, 2, &[0; 15], TextureOptions::default());
+
+    assert_eq!(
+        result.err(),
+        Some(TextureError::InvalidDataLength {
+            expected: 16,
+            found: 15
+        })
+    );
+    assert!(handler.state().textures.is_empty());
+}
+
+#[test]
+fn update_replaces_region() {
+    let mut handler = MockRenderHandler::new();
+    let mut texture = Texture2D::new(
+        &mut handler,
+        TextureFormat::R8,
+        4,
+        2,
+        TextureOptions::default(),
+    );
+    texture.update((1, 1), (2, 1), &[7, 9]).unwrap();
+    assert_eq!(
+        texture.update((3, 1), (2, 1), &[7, 9]),
+        Err(TextureError::OutOfBounds)
+    );
+    assert_eq!(
+        texture.update((u32::MAX, 0), (2, 1), &[7, 9]),
+        Err(TextureError::OutOfBounds)
+    );
+
+    let state = handler.state();
+    let mock = &state.textures[&texture.handle()];
+    assert_eq!(mock.data, vec![0, 0, 0, 0, 0, 7, 9, 0]);
+    assert!(mock.mipmaps_generated);
+}
+
+#[test]
+fn sampler_uniform_uses_bound_unit() {
+    let mut handler = MockRenderHandler::new();
+    let mut texture =
+        Texture2D::from_rgba(&mut handler, 1, 1, &[255; 4], TextureOptions::default()).unwrap();
+    let fragment_shader = Shader::create_shader(
+        &mut handler,
+        ShaderType::Fragment,
+        "uniform sampler2D tex;\nvoid main() {}\n",
+    )
+    .unwrap();
+    let mut program =
+        ShaderProgram::from_shaders(&mut handler, None, None, Some(fragment_shader)).unwrap();
+
+    texture.bind(3);
+    program.uniform("tex", &texture);
+
+    let state = handler.state();
+    assert_eq!(state.bound_textures[&3], texture.handle());
+    assert_eq!(state.uniforms[&0], MockUniform::Int(3));
+}
@@ -0,0 +1,64 @@
+use amazintosh_rs::timing::{FixedTimestep, SimulationSpeed};
+use std::time::Duration;
+
+fn millis(millis: u64) -> Duration {
+    Duration::from_millis(millis)
+}
+
+#[test]
+fn leftover_time_carries_over() {
+    // 50ms ticks
+    let mut timestep = FixedTimestep::new(20);
+    assert_eq!(timestep.tick_duration(), millis(50));
+
+    assert_eq!(timestep.advance(millis(30)), 0);
+    assert!((timestep.alpha() - 0.6).abs() < 1e-6);
+
+    assert_eq!(timestep.advance(millis(30)), 1);
+    assert!((timestep.alpha() - 0.2).abs() < 1e-6);
+
+    assert_eq!(timestep.advance(millis(140)), 3);
+    assert!(timestep.alpha().abs() < 1e-6);
+}
+
+#[test]
+fn speed_multiplies_ticks() {
+    let mut timestep = FixedTimestep::new(20).with_speed(SimulationSpeed::Paused);
+    assert_eq!(timestep.advance(millis(100)), 0);
+    assert_eq!(timestep.alpha(), 0.0);
+
+    timestep.set_speed(SimulationSpeed::Fast);
+    assert_eq!(timestep.advance(millis(100)), 6);
+
+    timestep.set_speed(SimulationSpeed::Fastest);
+    assert_eq!(timestep.advance(millis(100)), 20);
+    assert_eq!(timestep.speed(), SimulationSpeed::Fastest);
+}
+
+#[test]
+fn long_frames_skip_instead_of_catching_up() {
+    let mut timestep = FixedTimestep::new(20).with_max_frame_time(millis(200));
+
+    // A two second hitch only runs four ticks
+    assert_eq!(timestep.advance(millis(2000)), 4);
+    assert_eq!(timestep.stats().skipped, millis(1800));
+
+    assert_eq!(timestep.advance(millis(50)), 1);
+}
+
+#[test]
+fn stats_average_recent_frames() {
+    let mut timestep = FixedTimestep::new(60);
+    assert_eq!(timestep.stats().fps(), 0.0);
+
+    for _ in 0..100 {
+        timestep.advance(millis(20));
+    }
+    assert!((timestep.stats().fps() - 50.0).abs() < 0.01);
+
+    timestep.record_tick(millis(4));
+    timestep.record_tick(millis(2));
+    assert_eq!(timestep.stats().ticks, 2);
+    assert!(timestep.stats().tick_time > millis(2));
+    assert!(timestep.stats().tick_time < millis(4));
+}
@@ -0,0 +1,123 @@
+use amazintosh_rs::nalgebra::{Matrix4, Vector3, Vector4};
+use amazintosh_rs::render::buffer::BufferType;
+use amazintosh_rs::render::mock::MockRenderHandler;
+use amazintosh_rs::render::shader::{Shader, ShaderError, ShaderProgram, ShaderType};
+use amazintosh_rs::render::uniform_block::{Std140, UniformBlock};
+
+#[derive(Std140, Debug, Copy, Clone, PartialEq)]
+#[repr(C)]
+struct Camera {
+    projection: Matrix4<f32>,
+    view: Matrix4<f32>,
+}
+
+// The intensity fills the space std140 leaves after the direction
+#[derive(Std140, Debug, Copy, Clone, PartialEq)]
+#[repr(C)]
+struct Sun {
+    direction: Vector3<f32>,
+    intensity: f32,
+    colors: [Vector4<f32>; 2],
+}
+
+const SOURCE: &str = "#version 330
+layout(std140) uniform Camera {
+    mat4 projection;
+    mat4 view;
+};
+layout(std140) uniform Sun {
+    vec3 direction;
+    float intensity;
+    vec4 colors[2];
+};
+void main() {}
+";
+
+fn program(handler: &mut MockRenderHandler, source: &str) -> ShaderProgram<MockRenderHandler> {
+    let shader = Shader::create_shader(handler, ShaderType::Vertex, source).unwrap();
+    ShaderProgram::from_shaders(handler, Some(shader), None, None).unwrap()
+}
+
+#[test]
+fn blocks_share_binding_points_by_name() {
+    let mut handler = MockRenderHandler::new();
+    let first = program(&mut handler, SOURCE);
+
+    // Blocks created after a program still bind to the program's blocks
+    let camera = UniformBlock::new(
+        &mut handler,
+        "Camera",
+        Camera {
+            projection: Matrix4::identity(),
+            view: Matrix4::identity(),
+        },
+    );
+    let second = program(
+        &mut handler,
+        "#version 330\nlayout(std140) uniform Camera {\n    mat4 projection;\n    mat4 view;\n};\n",
+    );
+
+    let state = handler.state();
+    let first_binding = state.programs[&first.handle()].block_bindings[&0];
+    let second_binding = state.programs[&second.handle()].block_bindings[&0];
+    assert_eq!(first_binding, camera.binding());
+    assert_eq!(second_binding, camera.binding());
+    assert_ne!(
+        state.programs[&first.handle()].block_bindings[&1],
+        camera.binding()
+    );
+    assert!(state
+        .indexed_buffers
+        .contains_key(&(BufferType::UniformBuffer, camera.binding())));
+}
+
+#[test]
+fn setting_a_block_updates_its_buffer() {
+    let mut handler = MockRenderHandler::new();
+    let sun = Sun {
+        direction: Vector3::new(0.0, -1.0, 0.0),
+        intensity: 1.0,
+        colors: [Vector4::new(1.0, 1.0, 1.0, 1.0); 2],
+    };
+    let mut block = UniformBlock::new(&mut handler, "Sun", sun);
+    block.set(Sun {
+        intensity: 0.5,
+        ..sun
+    });
+
+    let state = handler.state();
+    let handle = state.indexed_buffers[&(BufferType::UniformBuffer, block.binding())];
+    let data = &state.buffers[&handle].data;
+    assert_eq!(data.len(), std::mem::size_of::<Sun>());
+    assert_eq!(&data[12..16], &0.5f32.to_ne_bytes());
+}
+
+#[test]
+fn block_sizes_are_checked() {
+    let mut handler = MockRenderHandler::new();
+    let program = program(&mut handler, SOURCE);
+
+    assert_eq!(program.uniform_block_info("Sun").unwrap().size, 48);
+    assert!(program.check_uniform_block::<Camera>("Camera").is_ok());
+    assert!(program.check_uniform_block::<Sun>("Sun").is_ok());
+    assert!(matches!(
+        program.check_uniform_block::<Sun>("Camera"),
+        Err(ShaderError::UniformBlockSizeMismatch {
+            expected: 128,
+            found: 48,
+            ..
+        })
+    ));
+    assert!(matches!(
+        program.check_uniform_block::<Camera>("Sun"),
+        Err(ShaderError::UniformBlockSizeMismatch {
+            expected: 48,
+            found: 128,
+            ..
+        })
+    ));
+    assert!(matches!(
+        program.check_uniform_block::<Camera>("Lights"),
+        Err(ShaderError::UnknownUniformBlock(_))
+    ));
+}
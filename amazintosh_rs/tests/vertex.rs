@@ -0,0 +1,106 @@
+use amazintosh_rs::nalgebra::{Vector2, Vector3};
+use amazintosh_rs::render::inner_gl;
+use amazintosh_rs::render::types::Half;
+use amazintosh_rs::render::vertex::Vertex;
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Vertex)]
+struct PosColVert {
+    position: Vector3<f32>,
+    color: [u8; 4],
+    uv: Vector2<f32>,
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Vertex)]
+struct RelocatedVert {
+    position: Vector2<f32>,
+    #[location = 4]
+    tile: u32,
+    height: f32,
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Vertex)]
+struct TupleVert(f32, Vector3<f32>);
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Vertex)]
+struct PackedVert {
+    position: Vector3<f32>,
+    uv: [Half; 2],
+    normal: [i8; 4],
+}
+
+#[test]
+fn derived_offsets_follow_layout() {
+    let pointers = PosColVert::attrib_pointers();
+
+    let indices: Vec<_> = pointers.iter().map(|p| p.index).collect();
+    let offsets: Vec<_> = pointers.iter().map(|p| p.offset).collect();
+    let sizes: Vec<_> = pointers.iter().map(|p| p.size).collect();
+    assert_eq!(indices, vec![0, 1, 2]);
+    assert_eq!(offsets, vec![0, 12, 16]);
+    assert_eq!(sizes, vec![3, 4, 2]);
+}
+
+#[test]
+fn byte_colors_are_normalized() {
+    let color = &PosColVert::attrib_pointers()[1];
+
+    assert_eq!(color.data_type, inner_gl::UNSIGNED_BYTE);
+    assert!(color.normalized);
+}
+
+#[test]
+fn location_overrides_continue_numbering() {
+    let pointers = RelocatedVert::attrib_pointers();
+
+    let indices: Vec<_> = pointers.iter().map(|p| p.index).collect();
+    assert_eq!(indices, vec![0, 4, 5]);
+    assert_eq!(pointers[1].data_type, inner_gl::UNSIGNED_INT);
+    assert!(pointers[1].integer);
+    assert!(!pointers[0].integer);
+}
+
+#[test]
+fn tuple_structs_are_supported() {
+    let pointers = TupleVert::attrib_pointers();
+
+    assert_eq!(pointers.len(), 2);
+    assert_eq!(pointers[1].offset, 4);
+    assert_eq!(pointers[1].size, 3);
+}
+
+#[test]
+fn packed_attributes_use_small_types() {
+    let pointers = PackedVert::attrib_pointers();
+
+    assert_eq!(pointers[1].data_type, inner_gl::HALF_FLOAT);
+    assert_eq!(pointers[1].offset, 12);
+    assert_eq!(pointers[2].data_type, inner_gl::BYTE);
+    assert_eq!(pointers[2].offset, 16);
+    assert!(pointers[2].normalized);
+    assert!(pointers.iter().all(|p| !p.integer));
+}
+
+#[test]
+fn half_floats_round_trip() {
+    for &value in &[
+        0.0,
+        -0.0,
+        1.0,
+        -2.5,
+        0.333_251_95,
+        65504.0,
+        1.0 / 16_777_216.0,
+    ] {
+        assert_eq!(Half::from_f32(value).to_f32(), value);
+    }
+
+    assert_eq!(Half::from_f32(1.0), Half(0x3c00));
+    assert_eq!(Half::from_f32(100_000.0).to_f32(), f32::INFINITY);
+    assert!(Half::from_f32(f32::NAN).to_f32().is_nan());
+    // Halfway between 1 and the next half float, which rounds to even
+    assert_eq!(Half::from_f32(1.0 + 1.0 / 2048.0), Half(0x3c00));
+}
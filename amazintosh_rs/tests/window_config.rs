@@ -0,0 +1,120 @@
+use amazintosh_rs::error::report;
+use amazintosh_rs::window_config::{DisplayMode, SettingsError, Vsync, WindowConfig};
+use std::error::Error;
+
+fn config() -> WindowConfig {
+    WindowConfig::new("Test", 800, 600)
+        .with_min_size(320, 240)
+        .with_display_mode(DisplayMode::Borderless)
+        .with_vsync(Vsync::Adaptive)
+        .with_samples(4)
+        .with_depth_bits(32)
+        .with_stencil_bits(0)
+        .with_high_dpi(true)
+        .with_debug_context(false)
+}
+
+/// The configuration the game starts with before loading the settings.
+fn defaults() -> WindowConfig {
+    WindowConfig::new("Test", 300, 300)
+        .with_min_size(320, 240)
+        .with_depth_bits(32)
+        .with_stencil_bits(0)
+        .with_debug_context(false)
+}
+
+#[test]
+fn settings_round_trip() {
+    let saved = config();
+
+    let mut loaded = defaults();
+    loaded.apply_settings(&saved.to_settings()).unwrap();
+
+    assert_eq!(loaded, saved);
+}
+
+#[test]
+fn only_player_settings_are_saved() {
+    let settings = config()
+        .with_min_size(1, 1)
+        .with_depth_bits(16)
+        .with_stencil_bits(8)
+        .with_debug_context(true)
+        .to_settings();
+    for key in ["min_size", "depth_bits", "stencil_bits", "debug_context"] {
+        assert!(!settings.contains(key), "{} was saved", key);
+    }
+
+    // Old settings files can't override what the game needs either
+    let mut config = config();
+    config
+        .apply_settings(
+            "depth_bits = 16
+debug_context = true
+",
+        )
+        .unwrap();
+    assert_eq!(config, self::config());
+}
+
+#[test]
+fn missing_settings_are_left_alone() {
+    let mut config = config();
+    config
+        .apply_settings("# Only the size\n\n  size = 1024 x 768  \n")
+        .unwrap();
+
+    assert_eq!(config.size, (1024, 768));
+    assert_eq!(config.display_mode, DisplayMode::Borderless);
+    assert_eq!(config.samples, 4);
+}
+
+#[test]
+fn invalid_settings_change_nothing() {
+    let mut config = config();
+
+    let error = config
+        .apply_settings("samples = 8\nvsync = sometimes\n")
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        SettingsError::InvalidValue { line: 2, ref key, ref value }
+            if key == "vsync" && value == "sometimes"
+    ));
+
+    let error = config.apply_settings("fullscreen\n").unwrap_err();
+    assert!(matches!(error, SettingsError::InvalidLine(1)));
+
+    assert_eq!(config, self::config());
+}
+
+#[test]
+fn settings_files_are_saved_and_loaded() {
+    let path = std::env::temp_dir().join(format!("amazintosh_settings_{}.cfg", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    // Nothing has been saved yet, so the defaults are kept
+    let defaults = defaults();
+    assert_eq!(defaults.clone().load(&path).unwrap(), defaults);
+
+    config().save(&path).unwrap();
+    let loaded = defaults.load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded, config());
+}
+
+#[test]
+fn file_errors_keep_their_cause() {
+    // A directory can't be read or written as a file
+    let path = std::env::temp_dir();
+
+    let error = config().save(&path).unwrap_err();
+    assert!(matches!(error, SettingsError::WriteFailed(_)));
+    assert_eq!(error.to_string(), "failed to write settings");
+    assert!(error.source().is_some());
+
+    let error = config().load(&path).unwrap_err();
+    assert!(matches!(error, SettingsError::ReadFailed(_)));
+    assert!(report(&error).starts_with("failed to read settings: "));
+}
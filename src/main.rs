@@ -1,18 +1,34 @@
+use amazintosh_rs::application::{Application, Control, Runner};
+use amazintosh_rs::error::{report, EngineError};
+use amazintosh_rs::log::{error, warn, LevelFilter};
+use amazintosh_rs::logging;
 use amazintosh_rs::nalgebra;
 use amazintosh_rs::nalgebra::{Affine3, Isometry3, Matrix4, Perspective3, Similarity3, Vector3};
 use amazintosh_rs::render::buffer::BufferUsage;
-use amazintosh_rs::render::mesh::{Mesh, MeshMode};
+use amazintosh_rs::render::capture::{FrameCapture, Screenshot};
+use amazintosh_rs::render::mesh::Mesh;
+use amazintosh_rs::render::shader::ShaderError;
+#[cfg(not(debug_assertions))]
 use amazintosh_rs::render::shader::{Shader, ShaderProgram, ShaderType};
+#[cfg(debug_assertions)]
+use amazintosh_rs::render::shader_asset::ShaderAsset;
 use amazintosh_rs::render::types::RGBAColor;
-use amazintosh_rs::render::vertex::{Vertex, VertexAttribPointer};
-use amazintosh_rs::render::{Gl, RenderHandler};
+use amazintosh_rs::render::uniform_block::{Std140, UniformBlock};
+use amazintosh_rs::render::vertex::Vertex;
+use amazintosh_rs::render::Gl;
 use amazintosh_rs::sdl2::event::Event;
 use amazintosh_rs::sdl2::event::WindowEvent;
 use amazintosh_rs::sdl2::keyboard::Keycode;
+use amazintosh_rs::timing::SimulationSpeed;
 use amazintosh_rs::window::{AWindow, SdlWindow};
+use amazintosh_rs::window_config::{DisplayMode, WindowConfig};
+use std::time::Duration;
+
+/// Where the player's window settings are saved.
+const SETTINGS_PATH: &str = "settings.cfg";
 
 #[repr(C)]
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Vertex)]
 pub struct PosVert {
     position: Vector3<f32>,
     color: Vector3<f32>,
@@ -24,133 +40,278 @@ impl PosVert {
     }
 }
 
-// TODO: AUTO-IMPLEMENT
-impl Vertex for PosVert {
-    fn attrib_pointers() -> Vec<VertexAttribPointer> {
-        vec![
-            VertexAttribPointer::new::<f32>(0, 3, false, 0),
-            VertexAttribPointer::new::<f32>(1, 3, false, std::mem::size_of::<Vector3<f32>>()),
-        ]
-    }
+/// The camera matrices shared by every shader through the `Camera` uniform
+/// block.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Std140)]
+struct Camera {
+    projection: Matrix4<f32>,
+    view: Matrix4<f32>,
+}
 
-    fn render<RHType: RenderHandler>(render_handler: &mut RHType, elements: usize) {
-        render_handler.enable_attrib_array(0);
-        render_handler.enable_attrib_array(1);
-        render_handler.draw_elements::<u16>(MeshMode::Triangles, elements);
-        render_handler.disable_attrib_array(0);
-        render_handler.disable_attrib_array(1);
-    }
+/// Debug builds load shaders from the source tree so they can be edited while
+/// the game is running, but release builds can't rely on the source tree
+/// being there, so they embed them instead.
+#[cfg(debug_assertions)]
+type TestShaders = ShaderAsset<Gl>;
+#[cfg(not(debug_assertions))]
+type TestShaders = ShaderProgram<Gl>;
+
+#[cfg(debug_assertions)]
+fn load_test_shaders(gl: &mut Gl) -> Result<TestShaders, ShaderError> {
+    ShaderAsset::load(
+        gl,
+        Some(concat!(env!("CARGO_MANIFEST_DIR"), "/src/vertex_test.glsl")),
+        None,
+        Some(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/src/fragment_test.glsl"
+        )),
+    )
 }
 
-struct AppState {
-    test_shaders: ShaderProgram,
+#[cfg(not(debug_assertions))]
+fn load_test_shaders(gl: &mut Gl) -> Result<TestShaders, ShaderError> {
+    let vertex_shader =
+        Shader::create_shader(gl, ShaderType::Vertex, include_str!("vertex_test.glsl"))?;
+    let fragment_shader =
+        Shader::create_shader(gl, ShaderType::Fragment, include_str!("fragment_test.glsl"))?;
+
+    ShaderProgram::from_shaders(gl, Some(vertex_shader), None, Some(fragment_shader))
+}
+
+struct CityMonopolis {
+    camera: UniformBlock<Gl, Camera>,
+    test_shaders: TestShaders,
     test_mesh: Mesh<Gl, PosVert, u16>,
+    screenshot_requested: bool,
+    timelapse: Option<FrameCapture>,
+    /// The rotation of the test mesh after the previous tick and the latest
+    /// one, which frames are drawn between.
+    previous_angle: f32,
+    angle: f32,
 }
 
-fn main() {
-    let mut window = SdlWindow::new(
-        concat!("CityMonopolis v", env!("CARGO_PKG_VERSION")),
-        300,
-        300,
-    )
-    .expect("failed to create window");
+impl Application<Gl> for CityMonopolis {
+    fn init<WindowType: AWindow<Gl, Event>>(
+        _window: &mut WindowType,
+        gl: &mut Gl,
+    ) -> Result<Self, EngineError> {
+        let test_shaders = load_test_shaders(gl)?;
 
-    let mut render = window.ctx().expect("failed to get OpenGL context");
+        let camera = UniformBlock::new(
+            gl,
+            "Camera",
+            Camera {
+                projection: Matrix4::identity(),
+                view: Matrix4::identity(),
+            },
+        );
 
-    let test_shaders = {
-        let vertex_shader = Shader::create_shader(
-            &mut render,
-            ShaderType::Vertex,
-            include_str!("./vertex_test.glsl"),
-        )
-        .expect("failed to compile vertex shader");
-        let fragment_shader = Shader::create_shader(
-            &mut render,
-            ShaderType::Fragment,
-            include_str!("./fragment_test.glsl"),
-        )
-        .expect("failed to compile fragment shader");
-
-        ShaderProgram::from_shaders(
-            &mut render,
-            Some(vertex_shader),
-            None,
-            Some(fragment_shader),
-            vec!["projection", "view", "object"],
+        let test_mesh = {
+            let mut test_mesh = Mesh::new(gl);
+
+            let mesh_verts = vec![
+                PosVert::new(Vector3::new(0.0, 0.5, 0.0), Vector3::new(1.0, 0.0, 0.0)),
+                PosVert::new(Vector3::new(-0.5, -0.5, 0.0), Vector3::new(0.0, 1.0, 0.0)),
+                PosVert::new(Vector3::new(0.5, -0.5, 0.0), Vector3::new(0.0, 0.0, 1.0)),
+            ];
+            let mesh_inds = vec![0, 1, 2];
+
+            test_mesh.set_vertices(mesh_verts, BufferUsage::StaticDraw);
+            test_mesh.set_indices(mesh_inds, BufferUsage::StaticDraw);
+
+            test_mesh
+        };
+
+        gl.set_clear_color(RGBAColor::from_rgb(0.2, 0.3, 0.4));
+
+        Ok(Self {
+            camera,
+            test_shaders,
+            test_mesh,
+            screenshot_requested: false,
+            timelapse: None,
+            previous_angle: 0.0,
+            angle: 0.0,
+        })
+    }
+
+    fn update<WindowType: AWindow<Gl, Event>>(
+        &mut self,
+        _window: &mut WindowType,
+        dt: Duration,
+    ) -> Control {
+        self.previous_angle = self.angle;
+        self.angle += dt.as_secs_f32();
+        Control::Continue
+    }
+
+    fn render<WindowType: AWindow<Gl, Event>>(
+        &mut self,
+        window: &mut WindowType,
+        gl: &mut Gl,
+        alpha: f32,
+    ) {
+        #[cfg(debug_assertions)]
+        self.test_shaders.reload_if_changed();
+
+        gl.clear(true, false);
+
+        // TODO:
+        let angle = self.previous_angle + (self.angle - self.previous_angle) * alpha;
+        let (width, height) = window.size();
+        let projection = Perspective3::new(
+            width as f32 / height as f32,
+            std::f32::consts::PI / 2.0,
+            0.1,
+            100.0,
         )
-        .expect("failed to link program")
-    };
+        .to_homogeneous();
+        let view: Matrix4<f32> = nalgebra::convert(Isometry3::new(
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(0.0, 0.0, 0.0),
+        ));
+        let object: Matrix4<f32> = nalgebra::convert(Similarity3::new(
+            Vector3::new(0.0, 0.0, -1.0),
+            Vector3::new(0.0, 0.0, angle),
+            1.0,
+        ));
+
+        // The camera is uploaded once for every shader
+        self.camera.set(Camera { projection, view });
+
+        self.test_shaders.bind();
+        self.test_shaders.uniform("object", object);
+
+        self.test_mesh.render();
+        // END TODO
+
+        // Read the frame back before it is swapped away
+        if self.screenshot_requested {
+            self.screenshot_requested = false;
+            let path = format!("screenshot_{}.png", unix_time());
+            if let Err(e) = Screenshot::from_window(gl).save_png(&path) {
+                warn!("Failed to save screenshot: {}", e);
+            }
+        }
+        if let Some(timelapse) = &mut self.timelapse {
+            if let Err(e) = timelapse.capture_window(gl) {
+                warn!("Failed to save timelapse frame: {}", e);
+            }
+        }
+    }
 
-    let test_mesh = {
-        let mut test_mesh = Mesh::new(&mut render);
+    fn on_event<WindowType: AWindow<Gl, Event>>(
+        &mut self,
+        window: &mut WindowType,
+        event: Event,
+    ) -> Control {
+        match event {
+            Event::Quit { .. }
+            | Event::Window {
+                win_event: WindowEvent::Close,
+                ..
+            } => return Control::Quit,
+            Event::KeyDown {
+                keycode: Some(Keycode::Escape),
+                ..
+            } => return Control::Quit,
+            Event::KeyDown {
+                keycode: Some(Keycode::F12),
+                ..
+            } => self.screenshot_requested = true,
+            // Change how fast the simulation runs
+            Event::KeyDown {
+                keycode:
+                    Some(keycode @ (Keycode::Num0 | Keycode::Num1 | Keycode::Num2 | Keycode::Num3)),
+                ..
+            } => window.timestep_mut().set_speed(match keycode {
+                Keycode::Num0 => SimulationSpeed::Paused,
+                Keycode::Num1 => SimulationSpeed::Normal,
+                Keycode::Num2 => SimulationSpeed::Fast,
+                _ => SimulationSpeed::Fastest,
+            }),
+            // Toggle between windowed and borderless fullscreen
+            Event::KeyDown {
+                keycode: Some(Keycode::F10),
+                repeat: false,
+                ..
+            } => {
+                let display_mode = match window.config().display_mode {
+                    DisplayMode::Windowed => DisplayMode::Borderless,
+                    _ => DisplayMode::Windowed,
+                };
+                if window.set_display_mode(display_mode).is_err() {
+                    warn!("Failed to change the display mode");
+                }
+            }
+            // Toggle saving every 30th frame for timelapses
+            Event::KeyDown {
+                keycode: Some(Keycode::F11),
+                repeat: false,
+                ..
+            } => {
+                self.timelapse = match self.timelapse {
+                    Some(_) => None,
+                    None => FrameCapture::new(format!("timelapse_{}", unix_time()), 30)
+                        .map_err(|e| warn!("Failed to start timelapse: {}", e))
+                        .ok(),
+                };
+            }
+            _ => {}
+        }
 
-        let mesh_verts = vec![
-            PosVert::new(Vector3::new(0.0, 0.5, 0.0), Vector3::new(1.0, 0.0, 0.0)),
-            PosVert::new(Vector3::new(-0.5, -0.5, 0.0), Vector3::new(0.0, 1.0, 0.0)),
-            PosVert::new(Vector3::new(0.5, -0.5, 0.0), Vector3::new(0.0, 0.0, 1.0)),
-        ];
-        let mesh_inds = vec![0, 1, 2];
+        Control::Continue
+    }
 
-        test_mesh.set_vertices(mesh_verts, BufferUsage::StaticDraw);
-        test_mesh.set_indices(mesh_inds, BufferUsage::StaticDraw);
+    fn on_quit<WindowType: AWindow<Gl, Event>>(&mut self, window: &mut WindowType) {
+        if let Err(e) = window.config().save(SETTINGS_PATH) {
+            warn!("{}", report(&e));
+        }
+    }
+}
 
-        test_mesh
+fn main() {
+    let level = if cfg!(debug_assertions) {
+        LevelFilter::Debug
+    } else {
+        LevelFilter::Info
     };
+    logging::init(level).expect("failed to set up logging");
 
-    render.set_clear_color(RGBAColor::from_rgb(0.2, 0.3, 0.4));
+    if let Err(e) = run() {
+        error!("{}", report(&e));
+        std::process::exit(1);
+    }
+}
 
-    window
-        .start_loop(
-            AppState {
-                test_shaders,
-                test_mesh,
-            },
-            |window, app_state| {
-                if let Some(mut gl) = window.ctx() {
-                    gl.clear(true, false);
-                }
+fn run() -> Result<(), EngineError> {
+    let config = WindowConfig::new(
+        concat!("CityMonopolis v", env!("CARGO_PKG_VERSION")),
+        300,
+        300,
+    )
+    .with_min_size(200, 200)
+    .with_samples(4)
+    .with_high_dpi(true);
 
-                // TODO:
-                let (width, height) = window.size();
-                let projection = Perspective3::new(
-                    width as f32 / height as f32,
-                    std::f32::consts::PI / 2.0,
-                    0.1,
-                    100.0,
-                )
-                .to_homogeneous();
-                let view: Matrix4<f32> = nalgebra::convert(Isometry3::new(
-                    Vector3::new(0.0, 0.0, 0.0),
-                    Vector3::new(0.0, 0.0, 0.0),
-                ));
-                let object: Matrix4<f32> = nalgebra::convert(Similarity3::new(
-                    Vector3::new(0.0, 0.0, -1.0),
-                    Vector3::new(0.0, 0.0, 0.0),
-                    1.0,
-                ));
-
-                app_state.test_shaders.bind();
-                app_state.test_shaders.uniform("projection", projection);
-                app_state.test_shaders.uniform("view", view);
-                app_state.test_shaders.uniform("object", object);
-
-                app_state.test_mesh.render();
-                // END TODO
-
-                false
-            },
-            |_, _| false,
-            |_, _, e| match e {
-                Event::Window {
-                    win_event: WindowEvent::Close,
-                    ..
-                } => true,
-                Event::KeyDown {
-                    keycode: Some(Keycode::Escape),
-                    ..
-                } => true,
-                _ => false,
-            },
-        )
-        .expect("failed to start mod loop");
+    // Bad settings shouldn't stop the game from starting
+    let config = match config.clone().load(SETTINGS_PATH) {
+        Ok(config) => config,
+        Err(e) => {
+            warn!("Using the default window settings: {}", report(&e));
+            config
+        }
+    };
+    let window = SdlWindow::from_config(config)?;
+
+    Runner::new(window)?.run::<CityMonopolis>()
+}
+
+fn unix_time() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or(0)
 }
@@ -3,8 +3,11 @@
 layout (location = 0) in vec3 vert_pos;
 layout (location = 1) in vec3 vert_col;
 
-uniform mat4 projection;
-uniform mat4 view;
+layout (std140) uniform Camera {
+    mat4 projection;
+    mat4 view;
+};
+
 uniform mat4 object;
 
 out vec3 vert_col_frag;